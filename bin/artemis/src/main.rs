@@ -2,9 +2,12 @@ use anyhow::Result;
 use clap::Parser;
 use binance_uni::types::{Action, Event};
 use ethers::providers::{Provider, Ws};
+use artemis_core::collectors::binance_depth_collector::BinanceDepthCollector;
 use artemis_core::collectors::block_collector::BlockCollector;
 use std::sync::Arc;
 use binance_uni::strategy::BinanceUni;
+use binance_uni::constants::POOL_ADDRESS;
+use binance_uni::pool_state_collector::PoolStateCollector;
 use artemis_core::engine::Engine;
 use artemis_core::types::{CollectorMap};
 use tracing::{info};
@@ -46,6 +49,16 @@ async fn main() -> Result<()> {
     let block_collector = CollectorMap::new(block_collector, Event::NewBlock);
     engine.add_collector(Box::new(block_collector));
 
+    // Set up Binance order book collector.
+    let binance_collector = Box::new(BinanceDepthCollector::new("ETHUSDC"));
+    let binance_collector = CollectorMap::new(binance_collector, Event::CexOrderBook);
+    engine.add_collector(Box::new(binance_collector));
+
+    // Set up Uniswap V3 pool state collector.
+    let pool_collector = Box::new(PoolStateCollector::new(provider.clone(), *POOL_ADDRESS));
+    let pool_collector = CollectorMap::new(pool_collector, Event::PoolState);
+    engine.add_collector(Box::new(pool_collector));
+
     let strategy = BinanceUni::new(Arc::new(provider.clone()));
     engine.add_strategy(Box::new(strategy));
 
@@ -0,0 +1,502 @@
+use crate::{
+    collectors::cex_collector::{CexCollector, CexOrderBook, Venue},
+    types::{Collector, CollectorStream},
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{future, StreamExt};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::{collections::BTreeMap, time::Duration};
+use thiserror::Error;
+use tokio::{sync::mpsc, time::sleep};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tracing::{error, info, warn};
+
+/// Default Binance spot websocket endpoint.
+pub const BINANCE_WS_URL: &str = "wss://stream.binance.com:9443";
+
+/// Default Binance spot REST endpoint.
+pub const BINANCE_REST_URL: &str = "https://api.binance.com";
+
+/// Delay before reconnecting after the stream drops or the book falls out of sync.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// A collector that subscribes to the Binance diff-depth and bookTicker streams for a
+/// symbol, and maintains a local order book using the
+/// [snapshot-plus-update-ID procedure](https://binance-docs.github.io/apidocs/spot/en/#how-to-manage-a-local-order-book-correctly).
+/// It generates a stream of [events](BinanceDepthEvent) with the top of book and
+/// depth snapshots of the local book.
+#[derive(Debug, Clone)]
+pub struct BinanceDepthCollector {
+    /// Symbol to subscribe to, e.g. `ETHUSDC`.
+    symbol: String,
+    /// Base websocket URL.
+    ws_url: String,
+    /// Base REST URL, used to fetch the depth snapshot.
+    rest_url: String,
+    /// Number of levels requested in the REST snapshot.
+    snapshot_limit: u32,
+    /// Number of levels per side included in emitted depth snapshots.
+    depth_levels: usize,
+}
+
+impl BinanceDepthCollector {
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into().to_uppercase(),
+            ws_url: BINANCE_WS_URL.to_string(),
+            rest_url: BINANCE_REST_URL.to_string(),
+            snapshot_limit: 1000,
+            depth_levels: 20,
+        }
+    }
+
+    /// Override the websocket and REST endpoints, e.g. to point at a mock server.
+    pub fn with_endpoints(
+        mut self,
+        ws_url: impl Into<String>,
+        rest_url: impl Into<String>,
+    ) -> Self {
+        self.ws_url = ws_url.into();
+        self.rest_url = rest_url.into();
+        self
+    }
+
+    /// Set the number of levels per side included in emitted depth snapshots.
+    pub fn with_depth_levels(mut self, depth_levels: usize) -> Self {
+        self.depth_levels = depth_levels;
+        self
+    }
+}
+
+/// An event emitted by the [BinanceDepthCollector](BinanceDepthCollector).
+#[derive(Debug, Clone)]
+pub enum BinanceDepthEvent {
+    /// Best bid and ask, as pushed by the bookTicker stream.
+    TopOfBook(TopOfBook),
+    /// Snapshot of the local order book after a depth update has been applied.
+    Depth(DepthSnapshot),
+}
+
+/// Best bid and ask for a symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopOfBook {
+    pub symbol: String,
+    pub update_id: u64,
+    pub bid_price: Decimal,
+    pub bid_qty: Decimal,
+    pub ask_price: Decimal,
+    pub ask_qty: Decimal,
+}
+
+/// The top levels of the local order book, with the best price first on each side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DepthSnapshot {
+    pub symbol: String,
+    pub last_update_id: u64,
+    /// (price, quantity) levels, highest price first.
+    pub bids: Vec<(Decimal, Decimal)>,
+    /// (price, quantity) levels, lowest price first.
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+impl From<DepthSnapshot> for CexOrderBook {
+    fn from(depth: DepthSnapshot) -> Self {
+        CexOrderBook::new(Venue::Binance, depth.symbol, depth.bids, depth.asks)
+    }
+}
+
+/// Response from the `api/v3/depth` REST endpoint.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepthSnapshotResponse {
+    pub last_update_id: u64,
+    pub bids: Vec<(Decimal, Decimal)>,
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+/// A message from the diff-depth stream.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DepthUpdate {
+    /// First update ID in the event.
+    #[serde(rename = "U")]
+    pub first_update_id: u64,
+    /// Final update ID in the event.
+    #[serde(rename = "u")]
+    pub final_update_id: u64,
+    #[serde(rename = "b")]
+    pub bids: Vec<(Decimal, Decimal)>,
+    #[serde(rename = "a")]
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+/// A message from the bookTicker stream.
+#[derive(Debug, Clone, Deserialize)]
+struct BookTickerUpdate {
+    #[serde(rename = "u")]
+    update_id: u64,
+    #[serde(rename = "s")]
+    symbol: String,
+    #[serde(rename = "b")]
+    bid_price: Decimal,
+    #[serde(rename = "B")]
+    bid_qty: Decimal,
+    #[serde(rename = "a")]
+    ask_price: Decimal,
+    #[serde(rename = "A")]
+    ask_qty: Decimal,
+}
+
+/// Wrapper around messages sent on a combined stream.
+#[derive(Debug, Deserialize)]
+struct CombinedStreamMessage {
+    stream: String,
+    data: serde_json::Value,
+}
+
+/// Error returned when a depth update cannot be applied to the local order book.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum OrderBookError {
+    /// The update does not follow on from the last applied update, so the book must be resynced.
+    #[error("gap in depth updates: book is at update {last_update_id}, got updates {first_update_id}..={final_update_id}")]
+    Gap {
+        last_update_id: u64,
+        first_update_id: u64,
+        final_update_id: u64,
+    },
+}
+
+/// A local order book, initialized from a REST snapshot and kept current by applying
+/// diff-depth updates.
+#[derive(Debug, Clone, Default)]
+pub struct OrderBook {
+    last_update_id: u64,
+    /// Whether an update has been applied on top of the snapshot yet.
+    synced: bool,
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+}
+
+impl OrderBook {
+    /// Create a book from a REST depth snapshot.
+    pub fn from_snapshot(snapshot: DepthSnapshotResponse) -> Self {
+        let mut book = Self {
+            last_update_id: snapshot.last_update_id,
+            ..Default::default()
+        };
+        Self::update_levels(&mut book.bids, snapshot.bids);
+        Self::update_levels(&mut book.asks, snapshot.asks);
+        book
+    }
+
+    /// Apply a diff-depth update. Returns `Ok(false)` if the update is older than the
+    /// book and was dropped, and an error if there is a gap in the update IDs.
+    pub fn apply(&mut self, update: DepthUpdate) -> Result<bool, OrderBookError> {
+        if update.final_update_id <= self.last_update_id {
+            return Ok(false);
+        }
+        let next_update_id = self.last_update_id + 1;
+        // The first update after the snapshot must straddle it, every later update
+        // must start right where the previous one ended.
+        let in_sequence = if self.synced {
+            update.first_update_id == next_update_id
+        } else {
+            update.first_update_id <= next_update_id
+        };
+        if !in_sequence {
+            return Err(OrderBookError::Gap {
+                last_update_id: self.last_update_id,
+                first_update_id: update.first_update_id,
+                final_update_id: update.final_update_id,
+            });
+        }
+        Self::update_levels(&mut self.bids, update.bids);
+        Self::update_levels(&mut self.asks, update.asks);
+        self.last_update_id = update.final_update_id;
+        self.synced = true;
+        Ok(true)
+    }
+
+    /// Last update ID reflected in the book.
+    pub fn last_update_id(&self) -> u64 {
+        self.last_update_id
+    }
+
+    /// Highest bid as (price, quantity).
+    pub fn best_bid(&self) -> Option<(Decimal, Decimal)> {
+        self.bids.iter().next_back().map(|(p, q)| (*p, *q))
+    }
+
+    /// Lowest ask as (price, quantity).
+    pub fn best_ask(&self) -> Option<(Decimal, Decimal)> {
+        self.asks.iter().next().map(|(p, q)| (*p, *q))
+    }
+
+    /// Top `levels` levels on each side of the book.
+    pub fn snapshot(&self, symbol: &str, levels: usize) -> DepthSnapshot {
+        DepthSnapshot {
+            symbol: symbol.to_string(),
+            last_update_id: self.last_update_id,
+            bids: self
+                .bids
+                .iter()
+                .rev()
+                .take(levels)
+                .map(|(p, q)| (*p, *q))
+                .collect(),
+            asks: self
+                .asks
+                .iter()
+                .take(levels)
+                .map(|(p, q)| (*p, *q))
+                .collect(),
+        }
+    }
+
+    /// Quantities are absolute, and a zero quantity removes the level.
+    fn update_levels(side: &mut BTreeMap<Decimal, Decimal>, levels: Vec<(Decimal, Decimal)>) {
+        for (price, qty) in levels {
+            if qty.is_zero() {
+                side.remove(&price);
+            } else {
+                side.insert(price, qty);
+            }
+        }
+    }
+}
+
+/// Implementation of the [Collector](Collector) trait for the
+/// [BinanceDepthCollector](BinanceDepthCollector). The websocket connection is driven
+/// by a background task, which reconnects and resyncs the book whenever the stream
+/// drops or a gap in update IDs is detected.
+#[async_trait]
+impl Collector<BinanceDepthEvent> for BinanceDepthCollector {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, BinanceDepthEvent>> {
+        let (tx, rx) = mpsc::channel(512);
+        let collector = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match collector.stream_book(&tx).await {
+                    Ok(()) => warn!("binance stream for {} closed", collector.symbol),
+                    Err(e) => error!("binance stream for {} failed: {}", collector.symbol, e),
+                }
+                if tx.is_closed() {
+                    return;
+                }
+                sleep(RECONNECT_DELAY).await;
+                info!("resyncing binance order book for {}", collector.symbol);
+            }
+        });
+        Ok(Box::pin(ReceiverStream::new(rx)))
+    }
+}
+
+/// Implementation of the [Collector](Collector) trait for the
+/// [BinanceDepthCollector](BinanceDepthCollector) as an [exchange adapter](CexCollector),
+/// which only forwards the depth snapshots.
+#[async_trait]
+impl Collector<CexOrderBook> for BinanceDepthCollector {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, CexOrderBook>> {
+        let stream = Collector::<BinanceDepthEvent>::get_event_stream(self).await?;
+        let stream = stream.filter_map(|event| {
+            future::ready(match event {
+                BinanceDepthEvent::Depth(depth) => Some(depth.into()),
+                BinanceDepthEvent::TopOfBook(_) => None,
+            })
+        });
+        Ok(Box::pin(stream))
+    }
+}
+
+impl CexCollector for BinanceDepthCollector {
+    fn venue(&self) -> Venue {
+        Venue::Binance
+    }
+}
+
+impl BinanceDepthCollector {
+    /// Connect to the combined stream, sync the local book, and forward events until
+    /// the connection closes or the book falls out of sync.
+    async fn stream_book(&self, tx: &mpsc::Sender<BinanceDepthEvent>) -> Result<()> {
+        let stream_name = self.symbol.to_lowercase();
+        let url = format!(
+            "{}/stream?streams={}@depth@100ms/{}@bookTicker",
+            self.ws_url, stream_name, stream_name
+        );
+        let (mut ws, _) = connect_async(url).await?;
+
+        // Buffer depth updates while the snapshot is in flight.
+        let mut buffered = vec![];
+        let snapshot = self.fetch_snapshot();
+        tokio::pin!(snapshot);
+        let snapshot = loop {
+            tokio::select! {
+                snapshot = &mut snapshot => break snapshot?,
+                msg = ws.next() => match msg {
+                    Some(msg) => {
+                        if let Some(update) = self.handle_message(msg?, tx).await? {
+                            buffered.push(update);
+                        }
+                    }
+                    None => return Ok(()),
+                },
+            }
+        };
+
+        let mut book = OrderBook::from_snapshot(snapshot);
+        for update in buffered {
+            book.apply(update)?;
+        }
+        send_event(
+            tx,
+            BinanceDepthEvent::Depth(book.snapshot(&self.symbol, self.depth_levels)),
+        )
+        .await?;
+
+        while let Some(msg) = ws.next().await {
+            if let Some(update) = self.handle_message(msg?, tx).await? {
+                if book.apply(update)? {
+                    let snapshot = book.snapshot(&self.symbol, self.depth_levels);
+                    send_event(tx, BinanceDepthEvent::Depth(snapshot)).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Forward top of book messages, and return depth updates to be applied to the book.
+    async fn handle_message(
+        &self,
+        msg: Message,
+        tx: &mpsc::Sender<BinanceDepthEvent>,
+    ) -> Result<Option<DepthUpdate>> {
+        let text = match msg {
+            Message::Text(text) => text,
+            _ => return Ok(None),
+        };
+        let msg: CombinedStreamMessage = serde_json::from_str(&text)?;
+        if msg.stream.ends_with("@bookTicker") {
+            let ticker: BookTickerUpdate = serde_json::from_value(msg.data)?;
+            let top = TopOfBook {
+                symbol: ticker.symbol,
+                update_id: ticker.update_id,
+                bid_price: ticker.bid_price,
+                bid_qty: ticker.bid_qty,
+                ask_price: ticker.ask_price,
+                ask_qty: ticker.ask_qty,
+            };
+            send_event(tx, BinanceDepthEvent::TopOfBook(top)).await?;
+            Ok(None)
+        } else if msg.stream.contains("@depth") {
+            Ok(Some(serde_json::from_value(msg.data)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Fetch a depth snapshot from the REST API.
+    async fn fetch_snapshot(&self) -> Result<DepthSnapshotResponse> {
+        let url = format!(
+            "{}/api/v3/depth?symbol={}&limit={}",
+            self.rest_url, self.symbol, self.snapshot_limit
+        );
+        let snapshot = reqwest::get(url)
+            .await?
+            .error_for_status()?
+            .json::<DepthSnapshotResponse>()
+            .await?;
+        Ok(snapshot)
+    }
+}
+
+async fn send_event(tx: &mpsc::Sender<BinanceDepthEvent>, event: BinanceDepthEvent) -> Result<()> {
+    tx.send(event)
+        .await
+        .map_err(|_| anyhow::anyhow!("event stream dropped"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn d(val: &str) -> Decimal {
+        Decimal::from_str(val).unwrap()
+    }
+
+    fn snapshot() -> DepthSnapshotResponse {
+        serde_json::from_str(
+            r#"{
+                "lastUpdateId": 100,
+                "bids": [["2000.00", "1.0"], ["1999.00", "2.0"]],
+                "asks": [["2001.00", "1.0"], ["2002.00", "1.5"]]
+            }"#,
+        )
+        .unwrap()
+    }
+
+    fn update(first: u64, last: u64, bids: &[(&str, &str)], asks: &[(&str, &str)]) -> DepthUpdate {
+        DepthUpdate {
+            first_update_id: first,
+            final_update_id: last,
+            bids: bids.iter().map(|(p, q)| (d(p), d(q))).collect(),
+            asks: asks.iter().map(|(p, q)| (d(p), d(q))).collect(),
+        }
+    }
+
+    #[test]
+    fn can_deserialize_depth_update() {
+        let update: DepthUpdate = serde_json::from_str(
+            r#"{"e":"depthUpdate","E":123456789,"s":"ETHUSDC","U":157,"u":160,"b":[["0.0024","10"]],"a":[["0.0026","100"]]}"#,
+        )
+        .unwrap();
+        assert_eq!(update.first_update_id, 157);
+        assert_eq!(update.final_update_id, 160);
+        assert_eq!(update.bids, vec![(d("0.0024"), d("10"))]);
+    }
+
+    #[test]
+    fn drops_stale_updates_and_applies_straddling_update() {
+        let mut book = OrderBook::from_snapshot(snapshot());
+        assert_eq!(
+            book.apply(update(90, 100, &[("2000.00", "0")], &[])),
+            Ok(false)
+        );
+        assert_eq!(book.best_bid(), Some((d("2000"), d("1"))));
+
+        let applied = book.apply(update(95, 102, &[("2000.00", "0")], &[("2001.00", "3")]));
+        assert_eq!(applied, Ok(true));
+        assert_eq!(book.last_update_id(), 102);
+        assert_eq!(book.best_bid(), Some((d("1999"), d("2"))));
+        assert_eq!(book.best_ask(), Some((d("2001"), d("3"))));
+    }
+
+    #[test]
+    fn detects_gaps() {
+        let mut book = OrderBook::from_snapshot(snapshot());
+        // First update must include lastUpdateId + 1.
+        assert!(book.apply(update(105, 110, &[], &[])).is_err());
+
+        book.apply(update(101, 102, &[], &[])).unwrap();
+        // Later updates must continue from the previous final update ID.
+        assert_eq!(
+            book.apply(update(104, 105, &[], &[])),
+            Err(OrderBookError::Gap {
+                last_update_id: 102,
+                first_update_id: 104,
+                final_update_id: 105
+            })
+        );
+        assert_eq!(book.apply(update(103, 105, &[], &[])), Ok(true));
+    }
+
+    #[test]
+    fn snapshot_is_sorted_best_first() {
+        let book = OrderBook::from_snapshot(snapshot());
+        let depth = book.snapshot("ETHUSDC", 1);
+        assert_eq!(depth.bids, vec![(d("2000"), d("1"))]);
+        assert_eq!(depth.asks, vec![(d("2001"), d("1"))]);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::types::{Collector, CollectorStream};
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{stream::select_all, Future};
+use rust_decimal::Decimal;
+use std::{cmp::Reverse, fmt, time::Duration};
+use tokio_stream::{wrappers::IntervalStream, StreamExt};
+use tracing::error;
+
+/// A centralized exchange that order books can be collected from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Venue {
+    Binance,
+    Coinbase,
+    Kraken,
+    Okx,
+}
+
+impl fmt::Display for Venue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Venue::Binance => "Binance",
+            Venue::Coinbase => "Coinbase",
+            Venue::Kraken => "Kraken",
+            Venue::Okx => "OKX",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A single price level of an order book.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PriceLevel {
+    pub price: Decimal,
+    pub size: Decimal,
+}
+
+impl From<(Decimal, Decimal)> for PriceLevel {
+    fn from((price, size): (Decimal, Decimal)) -> Self {
+        Self { price, size }
+    }
+}
+
+/// A venue-agnostic order book, with prices in quote units and sizes in base units.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CexOrderBook {
+    pub venue: Venue,
+    /// Venue-specific market symbol, e.g. `ETHUSDC` or `ETH-USD`.
+    pub symbol: String,
+    /// Bids, highest price first.
+    pub bids: Vec<PriceLevel>,
+    /// Asks, lowest price first.
+    pub asks: Vec<PriceLevel>,
+}
+
+impl CexOrderBook {
+    /// Create a book from (price, size) levels, sorting each side best price first.
+    pub fn new(
+        venue: Venue,
+        symbol: impl Into<String>,
+        bids: impl IntoIterator<Item = (Decimal, Decimal)>,
+        asks: impl IntoIterator<Item = (Decimal, Decimal)>,
+    ) -> Self {
+        let mut bids: Vec<PriceLevel> = bids.into_iter().map(PriceLevel::from).collect();
+        let mut asks: Vec<PriceLevel> = asks.into_iter().map(PriceLevel::from).collect();
+        bids.sort_by_key(|level| Reverse(level.price));
+        asks.sort_by_key(|level| level.price);
+        Self {
+            venue,
+            symbol: symbol.into(),
+            bids,
+            asks,
+        }
+    }
+
+    /// Highest bid.
+    pub fn best_bid(&self) -> Option<PriceLevel> {
+        self.bids.first().copied()
+    }
+
+    /// Lowest ask.
+    pub fn best_ask(&self) -> Option<PriceLevel> {
+        self.asks.first().copied()
+    }
+
+    /// Whether the market is quoted in `asset`, e.g. `USDC` for `ETHUSDC` or `ETH-USDC`.
+    pub fn is_quoted_in(&self, asset: &str) -> bool {
+        let symbol: String = self
+            .symbol
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect();
+        symbol.to_uppercase().ends_with(&asset.to_uppercase())
+    }
+
+    /// Midpoint between the best bid and the best ask.
+    pub fn mid_price(&self) -> Option<Decimal> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / Decimal::TWO)
+    }
+
+    /// Quote amount received for selling `base` into the bids, or `None` if the book
+    /// is not deep enough.
+    pub fn quote_for_selling(&self, base: Decimal) -> Option<Decimal> {
+        let mut remaining = base;
+        let mut quote = Decimal::ZERO;
+        for level in &self.bids {
+            let filled = remaining.min(level.size);
+            quote += filled * level.price;
+            remaining -= filled;
+            if remaining.is_zero() {
+                return Some(quote);
+            }
+        }
+        None
+    }
+
+    /// Base amount received for spending `quote` on the asks, or `None` if the book
+    /// is not deep enough.
+    pub fn base_for_buying(&self, quote: Decimal) -> Option<Decimal> {
+        let mut remaining = quote;
+        let mut base = Decimal::ZERO;
+        for level in &self.asks {
+            let filled = remaining.min(level.size * level.price);
+            base += filled / level.price;
+            remaining -= filled;
+            if remaining.is_zero() {
+                return Some(base);
+            }
+        }
+        None
+    }
+}
+
+/// Book with the highest bid across venues, i.e. the best venue to sell base on.
+pub fn best_bid_book<'a>(
+    books: impl IntoIterator<Item = &'a CexOrderBook>,
+) -> Option<&'a CexOrderBook> {
+    books
+        .into_iter()
+        .filter_map(|book| book.best_bid().map(|bid| (book, bid.price)))
+        .max_by(|a, b| a.1.cmp(&b.1))
+        .map(|(book, _)| book)
+}
+
+/// Book with the lowest ask across venues, i.e. the best venue to buy base on.
+pub fn best_ask_book<'a>(
+    books: impl IntoIterator<Item = &'a CexOrderBook>,
+) -> Option<&'a CexOrderBook> {
+    books
+        .into_iter()
+        .filter_map(|book| book.best_ask().map(|ask| (book, ask.price)))
+        .min_by(|a, b| a.1.cmp(&b.1))
+        .map(|(book, _)| book)
+}
+
+/// Collector trait implemented by exchange adapters, which generate a stream of
+/// [order books](CexOrderBook) for a single market on a single venue.
+pub trait CexCollector: Collector<CexOrderBook> {
+    /// The venue this adapter collects from.
+    fn venue(&self) -> Venue;
+}
+
+/// A collector that merges the order book streams of several
+/// [exchange adapters](CexCollector), so that a strategy can compare venues.
+pub struct MultiCexCollector {
+    collectors: Vec<Box<dyn CexCollector>>,
+}
+
+impl MultiCexCollector {
+    pub fn new(collectors: Vec<Box<dyn CexCollector>>) -> Self {
+        Self { collectors }
+    }
+}
+
+/// Implementation of the [Collector](Collector) trait for the
+/// [MultiCexCollector](MultiCexCollector).
+#[async_trait]
+impl Collector<CexOrderBook> for MultiCexCollector {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, CexOrderBook>> {
+        let mut streams = vec![];
+        for collector in &self.collectors {
+            streams.push(collector.get_event_stream().await?);
+        }
+        Ok(Box::pin(select_all(streams)))
+    }
+}
+
+/// Helper for REST adapters, which calls `fetch` every `interval` and streams the
+/// books that were fetched successfully.
+pub(crate) fn poll_order_book<'a, F, Fut>(
+    interval: Duration,
+    fetch: F,
+) -> CollectorStream<'a, CexOrderBook>
+where
+    F: Fn() -> Fut + Send + 'a,
+    Fut: Future<Output = Result<CexOrderBook>> + Send + 'a,
+{
+    let stream = IntervalStream::new(tokio::time::interval(interval))
+        .then(move |_| fetch())
+        .filter_map(|res| match res {
+            Ok(book) => Some(book),
+            Err(e) => {
+                error!("error fetching order book: {}", e);
+                None
+            }
+        });
+    Box::pin(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn d(val: &str) -> Decimal {
+        Decimal::from_str(val).unwrap()
+    }
+
+    fn book(venue: Venue, bids: &[(&str, &str)], asks: &[(&str, &str)]) -> CexOrderBook {
+        CexOrderBook::new(
+            venue,
+            "ETH-USD",
+            bids.iter().map(|(p, s)| (d(p), d(s))),
+            asks.iter().map(|(p, s)| (d(p), d(s))),
+        )
+    }
+
+    #[test]
+    fn walks_the_book() {
+        let book = book(
+            Venue::Coinbase,
+            &[("1999", "2"), ("2000", "1")],
+            &[("2001", "1"), ("2002", "2")],
+        );
+        assert_eq!(book.best_bid().unwrap().price, d("2000"));
+        assert_eq!(book.mid_price(), Some(d("2000.5")));
+        assert_eq!(book.quote_for_selling(d("2")), Some(d("3999")));
+        assert_eq!(book.quote_for_selling(d("4")), None);
+        assert_eq!(book.base_for_buying(d("4003")), Some(d("2")));
+    }
+
+    #[test]
+    fn matches_quote_asset() {
+        let mut book = book(Venue::Coinbase, &[], &[]);
+        assert!(!book.is_quoted_in("USDC"));
+        assert!(book.is_quoted_in("usd"));
+        book.symbol = "ETH-USDC".to_string();
+        assert!(book.is_quoted_in("USDC"));
+        book.symbol = "ETHUSDC".to_string();
+        assert!(book.is_quoted_in("USDC"));
+        book.symbol = "XETHZUSD".to_string();
+        assert!(!book.is_quoted_in("USDC"));
+    }
+
+    #[test]
+    fn picks_best_venue() {
+        let books = vec![
+            book(Venue::Binance, &[("2000", "1")], &[("2002", "1")]),
+            book(Venue::Kraken, &[("2001", "1")], &[("2003", "1")]),
+        ];
+        assert_eq!(best_bid_book(&books).unwrap().venue, Venue::Kraken);
+        assert_eq!(best_ask_book(&books).unwrap().venue, Venue::Binance);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{
+    collectors::cex_collector::{poll_order_book, CexCollector, CexOrderBook, Venue},
+    types::{Collector, CollectorStream},
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{de::IgnoredAny, Deserialize};
+use std::time::Duration;
+
+/// Default Coinbase Exchange REST endpoint.
+pub const COINBASE_REST_URL: &str = "https://api.exchange.coinbase.com";
+
+/// An [exchange adapter](CexCollector) that polls the Coinbase Exchange level 2 order
+/// book for a product, and generates a stream of [order books](CexOrderBook).
+#[derive(Debug, Clone)]
+pub struct CoinbaseBookCollector {
+    client: reqwest::Client,
+    /// Product to poll, e.g. `ETH-USD`.
+    product_id: String,
+    rest_url: String,
+    interval: Duration,
+}
+
+impl CoinbaseBookCollector {
+    pub fn new(product_id: impl Into<String>, interval: Duration) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            product_id: product_id.into(),
+            rest_url: COINBASE_REST_URL.to_string(),
+            interval,
+        }
+    }
+
+    /// Override the REST endpoint, e.g. to point at a mock server.
+    pub fn with_rest_url(mut self, rest_url: impl Into<String>) -> Self {
+        self.rest_url = rest_url.into();
+        self
+    }
+
+    /// Fetch the current order book.
+    pub async fn fetch_order_book(&self) -> Result<CexOrderBook> {
+        let url = format!(
+            "{}/products/{}/book?level=2",
+            self.rest_url, self.product_id
+        );
+        // Coinbase rejects requests without a user agent.
+        let res = self
+            .client
+            .get(url)
+            .header(reqwest::header::USER_AGENT, "artemis")
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<CoinbaseBookResponse>()
+            .await?;
+        Ok(res.into_order_book(&self.product_id))
+    }
+}
+
+/// Response from the `products/{product_id}/book` endpoint.
+#[derive(Debug, Deserialize)]
+struct CoinbaseBookResponse {
+    /// (price, size, number of orders) levels.
+    bids: Vec<(Decimal, Decimal, IgnoredAny)>,
+    asks: Vec<(Decimal, Decimal, IgnoredAny)>,
+}
+
+impl CoinbaseBookResponse {
+    fn into_order_book(self, product_id: &str) -> CexOrderBook {
+        CexOrderBook::new(
+            Venue::Coinbase,
+            product_id,
+            self.bids.into_iter().map(|(price, size, _)| (price, size)),
+            self.asks.into_iter().map(|(price, size, _)| (price, size)),
+        )
+    }
+}
+
+/// Implementation of the [Collector](Collector) trait for the
+/// [CoinbaseBookCollector](CoinbaseBookCollector).
+#[async_trait]
+impl Collector<CexOrderBook> for CoinbaseBookCollector {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, CexOrderBook>> {
+        Ok(poll_order_book(self.interval, move || {
+            self.fetch_order_book()
+        }))
+    }
+}
+
+impl CexCollector for CoinbaseBookCollector {
+    fn venue(&self) -> Venue {
+        Venue::Coinbase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn can_deserialize_book() {
+        let res: CoinbaseBookResponse = serde_json::from_str(
+            r#"{
+                "bids": [["1999.99", "0.5", 2], ["2000.01", "1.25", 1]],
+                "asks": [["2000.50", "3", 4]],
+                "sequence": 60360543402,
+                "auction_mode": false,
+                "auction": null,
+                "time": "2023-06-20T12:00:00.000000Z"
+            }"#,
+        )
+        .unwrap();
+        let book = res.into_order_book("ETH-USD");
+        assert_eq!(
+            book.best_bid().unwrap().price,
+            Decimal::from_str("2000.01").unwrap()
+        );
+        assert_eq!(book.best_ask().unwrap().size, Decimal::from(3));
+    }
+}
@@ -0,0 +1,331 @@
+use crate::{
+    types::{Collector, CollectorStream},
+    utilities::txpool::pending_transactions,
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::{
+    prelude::Middleware,
+    providers::PubsubClient,
+    types::{Block, BlockNumber, Transaction, H256, U256, U64},
+};
+use std::sync::Arc;
+use tokio_stream::StreamExt;
+use tracing::error;
+
+/// Default percentiles used for priority fees and pending gas prices.
+pub const DEFAULT_PERCENTILES: [f64; 5] = [10.0, 25.0, 50.0, 75.0, 90.0];
+
+/// Base fee change denominator, from EIP-1559.
+const BASE_FEE_MAX_CHANGE_DENOMINATOR: u64 = 8;
+
+/// Gas target is the gas limit divided by the elasticity multiplier, from EIP-1559.
+const ELASTICITY_MULTIPLIER: u64 = 2;
+
+/// A collector that listens for new blocks, and generates a stream of
+/// [fee market snapshots](FeeMarket), so that strategies and executors can price gas
+/// from a shared view.
+pub struct FeeMarketCollector<M> {
+    provider: Arc<M>,
+    /// Number of blocks to compute the priority fee distribution over.
+    history_blocks: u64,
+    /// Percentiles of the priority fee and pending gas price distributions.
+    percentiles: Vec<f64>,
+    /// Whether to fetch pending transactions for the pending gas price distribution.
+    sample_txpool: bool,
+}
+
+/// A fee market snapshot, taken after each new block.
+#[derive(Debug, Clone)]
+pub struct FeeMarket {
+    pub block_number: U64,
+    pub block_hash: H256,
+    /// Base fee of the latest block.
+    pub base_fee: U256,
+    /// Base fee of the next block, predicted from the latest block's gas usage.
+    pub next_base_fee: U256,
+    /// For each percentile, the median over recent blocks of that percentile of the
+    /// priority fees paid in the block.
+    pub priority_fees: Vec<(f64, U256)>,
+    /// For each percentile, that percentile of the gas prices of pending transactions
+    /// in the mempool, assuming they are included in the next block. Falls back to the
+    /// node's pending block if it doesn't expose its mempool. Empty unless txpool
+    /// sampling is enabled.
+    pub pending_gas_prices: Vec<(f64, U256)>,
+}
+
+impl FeeMarket {
+    /// Priority fee at a given percentile, if it was collected.
+    pub fn priority_fee(&self, percentile: f64) -> Option<U256> {
+        find_percentile(&self.priority_fees, percentile)
+    }
+
+    /// Pending gas price at a given percentile, if it was collected.
+    pub fn pending_gas_price(&self, percentile: f64) -> Option<U256> {
+        find_percentile(&self.pending_gas_prices, percentile)
+    }
+}
+
+fn find_percentile(values: &[(f64, U256)], percentile: f64) -> Option<U256> {
+    values
+        .iter()
+        .find(|(p, _)| *p == percentile)
+        .map(|(_, value)| *value)
+}
+
+impl<M> FeeMarketCollector<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self {
+            provider,
+            history_blocks: 10,
+            percentiles: DEFAULT_PERCENTILES.to_vec(),
+            sample_txpool: false,
+        }
+    }
+
+    /// Set the number of blocks the priority fee distribution is computed over.
+    pub fn with_history_blocks(mut self, history_blocks: u64) -> Self {
+        self.history_blocks = history_blocks;
+        self
+    }
+
+    /// Set the percentiles of the distributions, in ascending order. Fails if a
+    /// percentile is outside of 0 to 100.
+    pub fn with_percentiles(mut self, percentiles: Vec<f64>) -> Result<Self> {
+        if let Some(percentile) = percentiles
+            .iter()
+            .find(|percentile| !(0.0..=100.0).contains(*percentile))
+        {
+            return Err(anyhow!("percentile {} is outside of 0 to 100", percentile));
+        }
+        self.percentiles = percentiles;
+        Ok(self)
+    }
+
+    /// Also compute the pending gas price distribution, by fetching the node's pending
+    /// transactions on each block. Off by default, as the txpool of a busy node is large.
+    pub fn with_txpool_sampling(mut self) -> Self {
+        self.sample_txpool = true;
+        self
+    }
+}
+
+impl<M> FeeMarketCollector<M>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    /// Build a fee market snapshot for a block.
+    pub async fn fee_market(&self, block: &Block<H256>) -> Result<FeeMarket> {
+        let (Some(block_number), Some(block_hash)) = (block.number, block.hash) else {
+            return Err(anyhow!("block is pending"));
+        };
+        let base_fee = block
+            .base_fee_per_gas
+            .ok_or_else(|| anyhow!("block {} has no base fee", block_number))?;
+        let next_base_fee = next_base_fee(base_fee, block.gas_used, block.gas_limit);
+
+        let history = self
+            .provider
+            .fee_history(
+                self.history_blocks,
+                BlockNumber::Number(block_number),
+                &self.percentiles,
+            )
+            .await?;
+        let priority_fees = self
+            .percentiles
+            .iter()
+            .enumerate()
+            .map(|(i, percentile)| {
+                let mut rewards: Vec<U256> = history
+                    .reward
+                    .iter()
+                    .filter_map(|rewards| rewards.get(i).copied())
+                    .collect();
+                rewards.sort();
+                let median = rewards.get(rewards.len() / 2).copied().unwrap_or_default();
+                (*percentile, median)
+            })
+            .collect();
+
+        let pending_gas_prices = if self.sample_txpool {
+            let pending = pending_transactions(self.provider.as_ref()).await?;
+            let mut gas_prices: Vec<U256> = pending
+                .iter()
+                .filter_map(|tx| effective_gas_price(tx, next_base_fee))
+                .collect();
+            gas_prices.sort();
+            self.percentiles
+                .iter()
+                .filter_map(|percentile| {
+                    Some((*percentile, percentile_of(&gas_prices, *percentile)?))
+                })
+                .collect()
+        } else {
+            vec![]
+        };
+
+        Ok(FeeMarket {
+            block_number,
+            block_hash,
+            base_fee,
+            next_base_fee,
+            priority_fees,
+            pending_gas_prices,
+        })
+    }
+}
+
+/// Predict the base fee of the next block from the base fee, gas used and gas limit
+/// of the current block, following EIP-1559.
+pub fn next_base_fee(base_fee: U256, gas_used: U256, gas_limit: U256) -> U256 {
+    let gas_target = gas_limit / ELASTICITY_MULTIPLIER;
+    if gas_target.is_zero() || gas_used == gas_target {
+        return base_fee;
+    }
+    if gas_used > gas_target {
+        let delta =
+            base_fee * (gas_used - gas_target) / gas_target / BASE_FEE_MAX_CHANGE_DENOMINATOR;
+        base_fee + delta.max(U256::one())
+    } else {
+        let delta =
+            base_fee * (gas_target - gas_used) / gas_target / BASE_FEE_MAX_CHANGE_DENOMINATOR;
+        base_fee.saturating_sub(delta)
+    }
+}
+
+/// Gas price a transaction would pay at a given base fee, or `None` if it can't be
+/// included at that base fee.
+fn effective_gas_price(tx: &Transaction, base_fee: U256) -> Option<U256> {
+    match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+        (Some(max_fee), Some(max_priority_fee)) if max_fee >= base_fee => {
+            Some(max_fee.min(base_fee + max_priority_fee))
+        }
+        (Some(_), Some(_)) => None,
+        _ => tx.gas_price.filter(|gas_price| *gas_price >= base_fee),
+    }
+}
+
+/// Nearest-rank percentile of sorted values.
+fn percentile_of(sorted: &[U256], percentile: f64) -> Option<U256> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted.get(rank.clamp(1, sorted.len()) - 1).copied()
+}
+
+/// Implementation of the [Collector](Collector) trait for the [FeeMarketCollector](FeeMarketCollector).
+/// This implementation uses the [PubsubClient](PubsubClient) to subscribe to new blocks.
+#[async_trait]
+impl<M> Collector<FeeMarket> for FeeMarketCollector<M>
+where
+    M: Middleware,
+    M::Provider: PubsubClient,
+    M::Error: 'static,
+{
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, FeeMarket>> {
+        let stream = self.provider.subscribe_blocks().await?;
+        let stream = stream
+            .then(move |block| async move { self.fee_market(&block).await })
+            .filter_map(|res| match res {
+                Ok(fee_market) => Some(fee_market),
+                Err(e) => {
+                    error!("error building fee market snapshot: {}", e);
+                    None
+                }
+            });
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        providers::Provider,
+        types::{Address, FeeHistory, TxpoolContent},
+    };
+
+    #[test]
+    fn predicts_next_base_fee() {
+        let base_fee = U256::from(100_000_000_000u64);
+        let gas_limit = U256::from(30_000_000);
+        // Full block, base fee goes up by 12.5%.
+        assert_eq!(
+            next_base_fee(base_fee, gas_limit, gas_limit),
+            U256::from(112_500_000_000u64)
+        );
+        // Empty block, base fee goes down by 12.5%.
+        assert_eq!(
+            next_base_fee(base_fee, U256::zero(), gas_limit),
+            U256::from(87_500_000_000u64)
+        );
+        assert_eq!(next_base_fee(base_fee, gas_limit / 2, gas_limit), base_fee);
+    }
+
+    #[test]
+    fn computes_percentiles() {
+        let values: Vec<U256> = (1..=10u64).map(U256::from).collect();
+        assert_eq!(percentile_of(&values, 50.0), Some(U256::from(5)));
+        assert_eq!(percentile_of(&values, 90.0), Some(U256::from(9)));
+        assert_eq!(percentile_of(&values, 0.0), Some(U256::from(1)));
+        assert_eq!(percentile_of(&[], 50.0), None);
+    }
+
+    #[test]
+    fn rejects_percentiles_out_of_range() {
+        let collector = || FeeMarketCollector::new(Arc::new(Provider::mocked().0));
+        assert!(collector().with_percentiles(vec![0.0, 50.0, 100.0]).is_ok());
+        assert!(collector().with_percentiles(vec![50.0, 101.0]).is_err());
+        assert!(collector().with_percentiles(vec![-1.0]).is_err());
+        assert!(collector().with_percentiles(vec![f64::NAN]).is_err());
+    }
+
+    #[tokio::test]
+    async fn samples_txpool_only_when_enabled() {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+        let block = Block {
+            number: Some(1.into()),
+            hash: Some(H256::zero()),
+            base_fee_per_gas: Some(100.into()),
+            ..Default::default()
+        };
+        let history = FeeHistory {
+            base_fee_per_gas: vec![],
+            gas_used_ratio: vec![],
+            oldest_block: U256::one(),
+            reward: vec![vec![U256::from(2); DEFAULT_PERCENTILES.len()]],
+        };
+
+        // Only the fee history is fetched by default.
+        mock.push(history.clone()).unwrap();
+        let fee_market = FeeMarketCollector::new(provider.clone())
+            .fee_market(&block)
+            .await
+            .unwrap();
+        assert_eq!(fee_market.priority_fee(50.0), Some(U256::from(2)));
+        assert!(fee_market.pending_gas_prices.is_empty());
+
+        let mut txpool = TxpoolContent::default();
+        let tx = Transaction {
+            gas_price: Some(150.into()),
+            ..Default::default()
+        };
+        txpool
+            .pending
+            .entry(Address::zero())
+            .or_default()
+            .insert("0".to_string(), tx);
+        mock.push(txpool).unwrap();
+        mock.push(history).unwrap();
+        let fee_market = FeeMarketCollector::new(provider)
+            .with_txpool_sampling()
+            .fee_market(&block)
+            .await
+            .unwrap();
+        assert_eq!(fee_market.pending_gas_price(50.0), Some(U256::from(150)));
+    }
+}
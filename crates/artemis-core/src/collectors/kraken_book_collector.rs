@@ -0,0 +1,144 @@
+use crate::{
+    collectors::cex_collector::{poll_order_book, CexCollector, CexOrderBook, Venue},
+    types::{Collector, CollectorStream},
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{de::IgnoredAny, Deserialize};
+use std::{collections::HashMap, time::Duration};
+
+/// Default Kraken REST endpoint.
+pub const KRAKEN_REST_URL: &str = "https://api.kraken.com";
+
+/// An [exchange adapter](CexCollector) that polls the Kraken order book for a pair,
+/// and generates a stream of [order books](CexOrderBook).
+#[derive(Debug, Clone)]
+pub struct KrakenBookCollector {
+    client: reqwest::Client,
+    /// Pair to poll, e.g. `ETHUSD`.
+    pair: String,
+    /// Number of levels per side to request.
+    depth: u32,
+    rest_url: String,
+    interval: Duration,
+}
+
+impl KrakenBookCollector {
+    pub fn new(pair: impl Into<String>, depth: u32, interval: Duration) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            pair: pair.into(),
+            depth,
+            rest_url: KRAKEN_REST_URL.to_string(),
+            interval,
+        }
+    }
+
+    /// Override the REST endpoint, e.g. to point at a mock server.
+    pub fn with_rest_url(mut self, rest_url: impl Into<String>) -> Self {
+        self.rest_url = rest_url.into();
+        self
+    }
+
+    /// Fetch the current order book.
+    pub async fn fetch_order_book(&self) -> Result<CexOrderBook> {
+        let url = format!(
+            "{}/0/public/Depth?pair={}&count={}",
+            self.rest_url, self.pair, self.depth
+        );
+        let res = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<KrakenDepthResponse>()
+            .await?;
+        res.into_order_book(&self.pair)
+    }
+}
+
+/// Response from the `0/public/Depth` endpoint.
+#[derive(Debug, Deserialize)]
+struct KrakenDepthResponse {
+    error: Vec<String>,
+    /// Books keyed by Kraken's name for the pair, which may differ from the requested one.
+    #[serde(default)]
+    result: HashMap<String, KrakenBook>,
+}
+
+#[derive(Debug, Deserialize)]
+struct KrakenBook {
+    /// (price, volume, timestamp) levels.
+    bids: Vec<(Decimal, Decimal, IgnoredAny)>,
+    asks: Vec<(Decimal, Decimal, IgnoredAny)>,
+}
+
+impl KrakenDepthResponse {
+    fn into_order_book(self, pair: &str) -> Result<CexOrderBook> {
+        if !self.error.is_empty() {
+            return Err(anyhow!("kraken error: {}", self.error.join(", ")));
+        }
+        let book = self
+            .result
+            .into_values()
+            .next()
+            .ok_or_else(|| anyhow!("kraken returned no book for {}", pair))?;
+        Ok(CexOrderBook::new(
+            Venue::Kraken,
+            pair,
+            book.bids.into_iter().map(|(price, size, _)| (price, size)),
+            book.asks.into_iter().map(|(price, size, _)| (price, size)),
+        ))
+    }
+}
+
+/// Implementation of the [Collector](Collector) trait for the
+/// [KrakenBookCollector](KrakenBookCollector).
+#[async_trait]
+impl Collector<CexOrderBook> for KrakenBookCollector {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, CexOrderBook>> {
+        Ok(poll_order_book(self.interval, move || {
+            self.fetch_order_book()
+        }))
+    }
+}
+
+impl CexCollector for KrakenBookCollector {
+    fn venue(&self) -> Venue {
+        Venue::Kraken
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn can_deserialize_book() {
+        let res: KrakenDepthResponse = serde_json::from_str(
+            r#"{
+                "error": [],
+                "result": {
+                    "XETHZUSD": {
+                        "asks": [["2000.10000", "1.500", 1688671834], ["2000.20000", "2.000", 1688671830]],
+                        "bids": [["1999.90000", "0.750", 1688671820]]
+                    }
+                }
+            }"#,
+        )
+        .unwrap();
+        let book = res.into_order_book("ETHUSD").unwrap();
+        assert_eq!(
+            book.best_ask().unwrap().price,
+            Decimal::from_str("2000.1").unwrap()
+        );
+        assert_eq!(book.bids.len(), 1);
+
+        let res: KrakenDepthResponse =
+            serde_json::from_str(r#"{"error": ["EQuery:Unknown asset pair"]}"#).unwrap();
+        assert!(res.into_order_book("FOOBAR").is_err());
+    }
+}
@@ -1,12 +1,26 @@
 use crate::types::{Collector, CollectorStream};
 use anyhow::Result;
 use async_trait::async_trait;
+use ethers::{
+    abi::RawLog,
+    contract::{EthCall, EthLogDecode},
+    types::{Address, Selector},
+};
 pub use mev_share_sse::Event;
 use mev_share_sse::EventClient;
+use std::{collections::HashSet, time::Duration};
 use tokio_stream::StreamExt;
+use tracing::{error, warn};
+
+/// Delay before the first reconnect attempt, doubled after each failed attempt.
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Upper bound for the delay between reconnect attempts.
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
 
 /// A collector that streams from MEV-Share SSE endpoint
 /// and generates [events](Event), which return tx hash, logs, and bundled txs.
+/// The collector reconnects with backoff when the SSE stream disconnects.
 pub struct MevShareCollector {
     mevshare_sse_url: String,
 }
@@ -18,16 +32,188 @@ impl MevShareCollector {
 }
 
 /// Implementation of the [Collector](Collector) trait for the
-/// [MevShareCollector](MevShareCollector).
+/// [MevShareCollector](MevShareCollector). Returns an error if the endpoint can't be
+/// reached, and reconnects if the stream ends afterwards.
 #[async_trait]
 impl Collector<Event> for MevShareCollector {
     async fn get_event_stream(&self) -> Result<CollectorStream<'_, Event>> {
         let client = EventClient::default();
-        let stream = client.events(&self.mevshare_sse_url).await.unwrap();
-        let stream = stream.filter_map(|event| match event {
-            Ok(evt) => Some(evt),
-            Err(_) => None,
-        });
+        let stream = client.events(&self.mevshare_sse_url).await?;
+
+        let stream = futures::stream::unfold(
+            (client, Some(stream), RECONNECT_DELAY),
+            move |(client, mut stream, mut delay)| async move {
+                loop {
+                    let Some(active) = stream.as_mut() else {
+                        tokio::time::sleep(delay).await;
+                        match client.events(&self.mevshare_sse_url).await {
+                            Ok(new_stream) => stream = Some(new_stream),
+                            Err(e) => {
+                                error!("error reconnecting to mev-share: {}", e);
+                                delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+                            }
+                        }
+                        continue;
+                    };
+                    match active.next().await {
+                        Some(Ok(event)) => return Some((event, (client, stream, RECONNECT_DELAY))),
+                        Some(Err(e)) => error!("error in mev-share stream: {}", e),
+                        None => {
+                            warn!("mev-share stream disconnected, reconnecting");
+                            stream = None;
+                        }
+                    }
+                }
+            },
+        );
         Ok(Box::pin(stream))
     }
 }
+
+/// A log from a MEV-Share hint, decoded with contract bindings.
+#[derive(Debug, Clone)]
+pub struct DecodedLog<D> {
+    /// The address of the contract that emitted the log.
+    pub address: Address,
+    pub event: D,
+}
+
+/// Helpers to turn the raw hints of a MEV-Share [Event](Event) into typed data.
+pub trait EventHints {
+    /// Logs that decode to events of the bindings `D`, e.g. `UniswapV2PairEvents`.
+    /// Logs which are only partially shared, or don't match `D`, are skipped. Logs of
+    /// any contract emitting matching events are kept, so check their `address`.
+    fn decode_logs<D: EthLogDecode>(&self) -> Vec<DecodedLog<D>>;
+
+    /// Function selectors shared by the transactions of the event.
+    fn function_selectors(&self) -> Vec<Selector>;
+
+    /// Whether a transaction of the event calls the function `C`.
+    fn calls<C: EthCall>(&self) -> bool;
+
+    /// Calls to the function `C`, decoded from the transactions that share calldata.
+    fn decode_calls<C: EthCall>(&self) -> Vec<C>;
+
+    /// Addresses from `pools` which emitted a log or were called by the event.
+    fn known_pools(&self, pools: &HashSet<Address>) -> HashSet<Address>;
+}
+
+impl EventHints for Event {
+    fn decode_logs<D: EthLogDecode>(&self) -> Vec<DecodedLog<D>> {
+        self.logs
+            .iter()
+            .filter_map(|log| {
+                let raw = RawLog::from((log.topics.clone(), log.data.to_vec()));
+                let event = D::decode_log(&raw).ok()?;
+                Some(DecodedLog {
+                    address: log.address,
+                    event,
+                })
+            })
+            .collect()
+    }
+
+    fn function_selectors(&self) -> Vec<Selector> {
+        self.transactions
+            .iter()
+            .filter_map(|tx| tx.function_selector.as_ref().map(|selector| selector.0))
+            .collect()
+    }
+
+    fn calls<C: EthCall>(&self) -> bool {
+        self.function_selectors().contains(&C::selector())
+    }
+
+    fn decode_calls<C: EthCall>(&self) -> Vec<C> {
+        self.transactions
+            .iter()
+            .filter_map(|tx| C::decode(tx.calldata.as_ref()?).ok())
+            .collect()
+    }
+
+    fn known_pools(&self, pools: &HashSet<Address>) -> HashSet<Address> {
+        let emitters = self.logs.iter().map(|log| log.address);
+        let callees = self.transactions.iter().filter_map(|tx| tx.to);
+        emitters
+            .chain(callees)
+            .filter(|address| pools.contains(address))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::AbiEncode,
+        contract::{abigen, EthEvent},
+        types::{Bytes, H256, U256},
+    };
+    use mev_share_sse::{EventTransaction, EventTransactionLog};
+
+    abigen!(
+        UniswapV2Pair,
+        r#"[
+            event Sync(uint112 reserve0, uint112 reserve1)
+            event Mint(address indexed sender, uint256 amount0, uint256 amount1)
+            function swap(uint256 amount0Out, uint256 amount1Out, address to, bytes data)
+        ]"#
+    );
+
+    fn event() -> Event {
+        let pool = Address::repeat_byte(1);
+        let call = SwapCall {
+            amount_0_out: U256::from(1),
+            amount_1_out: U256::zero(),
+            to: Address::repeat_byte(2),
+            data: Bytes::default(),
+        };
+        let sync = (U256::from(10), U256::from(20)).encode();
+        Event {
+            hash: H256::zero(),
+            transactions: vec![EventTransaction {
+                to: Some(pool),
+                function_selector: Some(SwapCall::selector().into()),
+                calldata: Some(call.encode().into()),
+            }],
+            logs: vec![
+                EventTransactionLog {
+                    address: pool,
+                    topics: vec![SyncFilter::signature()],
+                    data: sync.into(),
+                },
+                // Partially shared log, without data.
+                EventTransactionLog {
+                    address: Address::repeat_byte(3),
+                    topics: vec![SyncFilter::signature()],
+                    data: Bytes::default(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn decodes_hints() {
+        let event = event();
+
+        let logs = event.decode_logs::<UniswapV2PairEvents>();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].address, Address::repeat_byte(1));
+        match &logs[0].event {
+            UniswapV2PairEvents::SyncFilter(sync) => assert_eq!(sync.reserve_0, 10),
+            event => panic!("unexpected event {:?}", event),
+        }
+
+        assert!(event.calls::<SwapCall>());
+        assert_eq!(
+            event.decode_calls::<SwapCall>()[0].amount_0_out,
+            U256::from(1)
+        );
+
+        let pools = HashSet::from([Address::repeat_byte(1), Address::repeat_byte(4)]);
+        assert_eq!(
+            event.known_pools(&pools),
+            HashSet::from([Address::repeat_byte(1)])
+        );
+    }
+}
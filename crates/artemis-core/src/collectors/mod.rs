@@ -2,16 +2,38 @@
 //! turning them into internal events. For example, a collector might listen to
 //! a stream of new blocks, and turn them into a stream of `NewBlock` events.
 
+/// This collector maintains a local Binance order book from the depth streams.
+pub mod binance_depth_collector;
+
 /// This collector listens to a stream of new blocks.
 pub mod block_collector;
 
+/// This module contains the venue-agnostic order book type and the exchange adapter trait.
+pub mod cex_collector;
+
+/// This collector polls the Coinbase order book.
+pub mod coinbase_book_collector;
+
+/// This collector emits fee market snapshots for each new block.
+pub mod fee_market_collector;
+
+/// This collector polls the Kraken order book.
+pub mod kraken_book_collector;
+
 /// This collector listens to a stream of new event logs.
 pub mod log_collector;
 
 /// This collector listens to a stream of new pending transactions.
 pub mod mempool_collector;
 
+/// This collector polls the OKX order book.
+pub mod okx_book_collector;
+
 /// This collector listens to a stream of new Opensea orders.
 pub mod opensea_order_collector;
 
+/// This collector emits named ticks on interval, cron and block schedules.
+pub mod timer_collector;
+
+/// This collector listens to a stream of MEV-Share events, and parses their hints.
 pub mod mevshare_collector;
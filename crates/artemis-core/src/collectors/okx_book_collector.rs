@@ -0,0 +1,146 @@
+use crate::{
+    collectors::cex_collector::{poll_order_book, CexCollector, CexOrderBook, Venue},
+    types::{Collector, CollectorStream},
+};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde::{de::IgnoredAny, Deserialize};
+use std::time::Duration;
+
+/// Default OKX REST endpoint.
+pub const OKX_REST_URL: &str = "https://www.okx.com";
+
+/// An [exchange adapter](CexCollector) that polls the OKX order book for an
+/// instrument, and generates a stream of [order books](CexOrderBook).
+#[derive(Debug, Clone)]
+pub struct OkxBookCollector {
+    client: reqwest::Client,
+    /// Instrument to poll, e.g. `ETH-USDC`.
+    inst_id: String,
+    /// Number of levels per side to request.
+    depth: u32,
+    rest_url: String,
+    interval: Duration,
+}
+
+impl OkxBookCollector {
+    pub fn new(inst_id: impl Into<String>, depth: u32, interval: Duration) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            inst_id: inst_id.into(),
+            depth,
+            rest_url: OKX_REST_URL.to_string(),
+            interval,
+        }
+    }
+
+    /// Override the REST endpoint, e.g. to point at a mock server.
+    pub fn with_rest_url(mut self, rest_url: impl Into<String>) -> Self {
+        self.rest_url = rest_url.into();
+        self
+    }
+
+    /// Fetch the current order book.
+    pub async fn fetch_order_book(&self) -> Result<CexOrderBook> {
+        let url = format!(
+            "{}/api/v5/market/books?instId={}&sz={}",
+            self.rest_url, self.inst_id, self.depth
+        );
+        let res = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<OkxBooksResponse>()
+            .await?;
+        res.into_order_book(&self.inst_id)
+    }
+}
+
+/// Response from the `api/v5/market/books` endpoint.
+#[derive(Debug, Deserialize)]
+struct OkxBooksResponse {
+    code: String,
+    msg: String,
+    data: Vec<OkxBook>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OkxBook {
+    /// (price, size, deprecated, number of orders) levels.
+    bids: Vec<(Decimal, Decimal, IgnoredAny, IgnoredAny)>,
+    asks: Vec<(Decimal, Decimal, IgnoredAny, IgnoredAny)>,
+}
+
+impl OkxBooksResponse {
+    fn into_order_book(self, inst_id: &str) -> Result<CexOrderBook> {
+        if self.code != "0" {
+            return Err(anyhow!("okx error {}: {}", self.code, self.msg));
+        }
+        let book = self
+            .data
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow!("okx returned no book for {}", inst_id))?;
+        Ok(CexOrderBook::new(
+            Venue::Okx,
+            inst_id,
+            book.bids
+                .into_iter()
+                .map(|(price, size, _, _)| (price, size)),
+            book.asks
+                .into_iter()
+                .map(|(price, size, _, _)| (price, size)),
+        ))
+    }
+}
+
+/// Implementation of the [Collector](Collector) trait for the
+/// [OkxBookCollector](OkxBookCollector).
+#[async_trait]
+impl Collector<CexOrderBook> for OkxBookCollector {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, CexOrderBook>> {
+        Ok(poll_order_book(self.interval, move || {
+            self.fetch_order_book()
+        }))
+    }
+}
+
+impl CexCollector for OkxBookCollector {
+    fn venue(&self) -> Venue {
+        Venue::Okx
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn can_deserialize_book() {
+        let res: OkxBooksResponse = serde_json::from_str(
+            r#"{
+                "code": "0",
+                "msg": "",
+                "data": [{
+                    "asks": [["2000.5", "8.4", "0", "2"]],
+                    "bids": [["1999.8", "0.6", "0", "1"], ["2000.1", "1.2", "0", "3"]],
+                    "ts": "1688671834123"
+                }]
+            }"#,
+        )
+        .unwrap();
+        let book = res.into_order_book("ETH-USDC").unwrap();
+        assert_eq!(
+            book.best_bid().unwrap().price,
+            Decimal::from_str("2000.1").unwrap()
+        );
+        assert_eq!(
+            book.best_ask().unwrap().size,
+            Decimal::from_str("8.4").unwrap()
+        );
+    }
+}
@@ -1,24 +1,66 @@
 use crate::types::{Collector, CollectorStream};
 use anyhow::Result;
 use async_trait::async_trait;
+use futures::stream::select_all;
 use opensea_stream::{
     client,
-    schema::{self, ItemListedData},
+    schema::{
+        self, CollectionOfferData, ItemCancelledData, ItemListedData, ItemReceivedOfferData,
+        ItemSoldData, ItemTransferredData,
+    },
     subscribe_to, Collection, Network,
 };
 use tokio_stream::wrappers::BroadcastStream;
 use tokio_stream::StreamExt;
 
 /// A collector that listens for new orders on OpenSea, and generates a stream of
-/// [events](OpenseaOrder) which contain the order.
-#[derive(Default)]
+/// [events](OpenseaOrder) which contain the order. The collector can also generate
+/// a stream of all [order events](OpenseaEvent), including sales, cancellations,
+/// transfers and offers.
 pub struct OpenseaOrderCollector {
     api_key: String,
+    /// Networks to connect to.
+    networks: Vec<Network>,
+    /// Slugs of the collections to subscribe to. All collections when empty.
+    collections: Vec<String>,
 }
 
 impl OpenseaOrderCollector {
     pub fn new(api_key: String) -> Self {
-        Self { api_key }
+        Self {
+            api_key,
+            networks: vec![Network::Mainnet],
+            collections: vec![],
+        }
+    }
+
+    /// Only subscribe to the collections with the given slugs, and drop events of
+    /// other collections.
+    pub fn with_collections(mut self, slugs: Vec<String>) -> Self {
+        self.collections = slugs;
+        self
+    }
+
+    /// Subscribe on each of the given networks instead of mainnet only.
+    pub fn with_networks(mut self, networks: Vec<Network>) -> Self {
+        self.networks = networks;
+        self
+    }
+
+    /// Whether an event matches the configured collections. Events are only received
+    /// on the configured networks.
+    fn accepts(&self, event: &OpenseaEvent) -> bool {
+        self.collections.is_empty()
+            || self
+                .collections
+                .iter()
+                .any(|slug| slug == event.kind.collection_slug())
+    }
+}
+
+impl Default for OpenseaOrderCollector {
+    fn default() -> Self {
+        Self::new(String::new())
     }
 }
 
@@ -26,29 +68,167 @@ impl OpenseaOrderCollector {
 #[derive(Debug, Clone)]
 pub struct OpenseaOrder {
     pub listing: ItemListedData,
+    /// Network the order was received on.
+    pub network: Network,
+}
+
+/// An order event received on a network.
+#[derive(Debug, Clone)]
+pub struct OpenseaEvent {
+    pub network: Network,
+    pub kind: OpenseaEventKind,
+}
+
+/// Kind of an order event, containing the data of the underlying OpenSea stream event.
+#[derive(Debug, Clone)]
+pub enum OpenseaEventKind {
+    Listed(ItemListedData),
+    Cancelled(ItemCancelledData),
+    Sold(ItemSoldData),
+    Transferred(ItemTransferredData),
+    ItemOffer(ItemReceivedOfferData),
+    CollectionOffer(CollectionOfferData),
+}
+
+impl OpenseaEventKind {
+    /// Convert a stream payload into an event kind, or `None` for payloads that are not
+    /// order events, such as metadata updates.
+    pub fn from_payload(payload: schema::Payload) -> Option<Self> {
+        match payload {
+            schema::Payload::ItemListed(data) => Some(OpenseaEventKind::Listed(data)),
+            schema::Payload::ItemCancelled(data) => Some(OpenseaEventKind::Cancelled(data)),
+            schema::Payload::ItemSold(data) => Some(OpenseaEventKind::Sold(data)),
+            schema::Payload::ItemTransferred(data) => Some(OpenseaEventKind::Transferred(data)),
+            schema::Payload::ItemReceivedOffer(data) => Some(OpenseaEventKind::ItemOffer(data)),
+            schema::Payload::CollectionOffer(data) => Some(OpenseaEventKind::CollectionOffer(data)),
+            _ => None,
+        }
+    }
+
+    /// Slug of the collection the event belongs to.
+    pub fn collection_slug(&self) -> &str {
+        match self {
+            OpenseaEventKind::Listed(data) => &data.context.collection.slug,
+            OpenseaEventKind::Cancelled(data) => &data.context.collection.slug,
+            OpenseaEventKind::Sold(data) => &data.context.collection.slug,
+            OpenseaEventKind::Transferred(data) => &data.context.collection.slug,
+            OpenseaEventKind::ItemOffer(data) => &data.context.collection.slug,
+            OpenseaEventKind::CollectionOffer(data) => &data.collection.slug,
+        }
+    }
 }
 
 /// Implementation of the [Collector](Collector) trait for the [OpenseaOrderCollector](OpenseaOrderCollector).
+/// This implementation subscribes to each configured collection on each configured network,
+/// and merges the subscriptions into a single stream of the events matching them.
+#[async_trait]
+impl Collector<OpenseaEvent> for OpenseaOrderCollector {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, OpenseaEvent>> {
+        let collections = if self.collections.is_empty() {
+            vec![Collection::All]
+        } else {
+            self.collections
+                .iter()
+                .map(|slug| Collection::Collection(slug.clone()))
+                .collect()
+        };
+
+        let mut streams = vec![];
+        for network in &self.networks {
+            let mut client = client(*network, &self.api_key).await;
+            for collection in &collections {
+                let (_, subscription) = subscribe_to(&mut client, collection.clone()).await?;
+                let network = *network;
+                streams.push(BroadcastStream::new(subscription).map(move |event| (network, event)));
+            }
+        }
+
+        let stream = select_all(streams).filter_map(move |(network, event)| {
+            let event = event.ok()?.into_custom_payload()?;
+            let kind = OpenseaEventKind::from_payload(event.payload)?;
+            let event = OpenseaEvent { network, kind };
+            self.accepts(&event).then_some(event)
+        });
+
+        Ok(Box::pin(stream))
+    }
+}
+
+/// Implementation of the [Collector](Collector) trait for the [OpenseaOrderCollector](OpenseaOrderCollector),
+/// which only keeps new listings.
 #[async_trait]
 impl Collector<OpenseaOrder> for OpenseaOrderCollector {
     async fn get_event_stream(&self) -> Result<CollectorStream<'_, OpenseaOrder>> {
-        let mut client = client(Network::Mainnet, &self.api_key).await;
+        let stream = Collector::<OpenseaEvent>::get_event_stream(self).await?;
+        let stream = stream.filter_map(|event| match event.kind {
+            OpenseaEventKind::Listed(listing) => Some(OpenseaOrder {
+                listing,
+                network: event.network,
+            }),
+            _ => None,
+        });
+        Ok(Box::pin(stream))
+    }
+}
 
-        let collection = Collection::All;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn read_events() -> Vec<Option<OpenseaEventKind>> {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources/opensea_stream_events.json");
+        let res = std::fs::read_to_string(d).unwrap();
+        let payloads: Vec<schema::Payload> = serde_json::from_str(&res).unwrap();
+        payloads
+            .into_iter()
+            .map(OpenseaEventKind::from_payload)
+            .collect()
+    }
 
-        let (_, subscription) = subscribe_to(&mut client, collection).await?;
+    #[test]
+    fn parses_order_events() {
+        let events = read_events();
+        assert_eq!(events.len(), 8);
+        assert!(matches!(events[0], Some(OpenseaEventKind::Listed(_))));
+        assert!(matches!(events[1], Some(OpenseaEventKind::Cancelled(_))));
+        assert!(matches!(events[2], Some(OpenseaEventKind::Sold(_))));
+        assert!(matches!(events[3], Some(OpenseaEventKind::Transferred(_))));
+        assert!(matches!(events[4], Some(OpenseaEventKind::ItemOffer(_))));
+        assert!(matches!(
+            events[5],
+            Some(OpenseaEventKind::CollectionOffer(_))
+        ));
+        // Bids and metadata updates are not order events.
+        assert!(events[6].is_none());
+        assert!(events[7].is_none());
+        for event in events.iter().flatten() {
+            assert_eq!(event.collection_slug(), "doodles-official");
+        }
+    }
 
-        let stream = BroadcastStream::new(subscription);
+    #[test]
+    fn filters_collections() {
+        let events: Vec<_> = read_events()
+            .into_iter()
+            .flatten()
+            .map(|kind| OpenseaEvent {
+                network: Network::Mainnet,
+                kind,
+            })
+            .collect();
+        let collector = OpenseaOrderCollector::default();
+        assert!(events.iter().all(|event| collector.accepts(event)));
 
-        let stream = stream.filter_map(|event| {
-            let event = event.ok()?.into_custom_payload()?;
-            if let schema::Payload::ItemListed(listing) = event.payload {
-                Some(OpenseaOrder { listing })
-            } else {
-                None
-            }
-        });
+        let collector = OpenseaOrderCollector::default()
+            .with_collections(vec!["boredapeyachtclub".to_string()]);
+        assert!(events.iter().all(|event| !collector.accepts(event)));
 
-        Ok(Box::pin(stream))
+        let collector = OpenseaOrderCollector::default().with_collections(vec![
+            "boredapeyachtclub".to_string(),
+            "doodles-official".to_string(),
+        ]);
+        assert!(events.iter().all(|event| collector.accepts(event)));
     }
 }
@@ -0,0 +1,211 @@
+use crate::{
+    collectors::block_collector::NewBlock,
+    types::{Collector, CollectorStream},
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ethers::types::U64;
+use futures::{stream, stream::select_all, StreamExt};
+use std::{
+    str::FromStr,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::time::{sleep, Instant, MissedTickBehavior};
+use tokio_stream::wrappers::IntervalStream;
+
+/// A collector that generates [tick events](Tick) on a set of named
+/// [schedules](Schedule), so that a single strategy can multiplex several
+/// periodic tasks.
+pub struct TimerCollector {
+    schedules: Vec<(String, Schedule)>,
+    blocks: Option<Box<dyn Collector<NewBlock>>>,
+}
+
+/// When a schedule fires.
+#[derive(Debug, Clone)]
+pub enum Schedule {
+    /// Fire every non-zero `period`. When `aligned`, ticks land on multiples of the period
+    /// since the Unix epoch, e.g. on the minute for a period of 60 seconds.
+    Interval { period: Duration, aligned: bool },
+    /// Fire on a cron expression, evaluated in UTC. Expressions include a seconds
+    /// field, e.g. `0 */5 * * * *` for every five minutes.
+    Cron(Box<cron::Schedule>),
+    /// Fire `delay` after every non-zero `every`-th block.
+    Blocks { every: u64, delay: Duration },
+}
+
+/// A tick event, containing the name of the schedule that fired.
+#[derive(Debug, Clone)]
+pub struct Tick {
+    pub name: String,
+    pub timestamp: DateTime<Utc>,
+    /// The block that triggered the tick, for block schedules.
+    pub block_number: Option<U64>,
+}
+
+impl Schedule {
+    /// Parse a cron expression into a schedule.
+    pub fn cron(expression: &str) -> Result<Self> {
+        Ok(Schedule::Cron(Box::new(cron::Schedule::from_str(
+            expression,
+        )?)))
+    }
+}
+
+impl TimerCollector {
+    pub fn new() -> Self {
+        Self {
+            schedules: vec![],
+            blocks: None,
+        }
+    }
+
+    /// Add a named schedule.
+    pub fn with_schedule(mut self, name: impl Into<String>, schedule: Schedule) -> Self {
+        self.schedules.push((name.into(), schedule));
+        self
+    }
+
+    /// Set the block source used by [block schedules](Schedule::Blocks), usually a
+    /// [BlockCollector](crate::collectors::block_collector::BlockCollector).
+    pub fn with_blocks(mut self, blocks: Box<dyn Collector<NewBlock>>) -> Self {
+        self.blocks = Some(blocks);
+        self
+    }
+
+    async fn schedule_stream<'a>(
+        &'a self,
+        name: &'a str,
+        schedule: &'a Schedule,
+    ) -> Result<CollectorStream<'a, Tick>> {
+        let tick = move |block_number| Tick {
+            name: name.to_string(),
+            timestamp: Utc::now(),
+            block_number,
+        };
+        let stream: CollectorStream<'a, Tick> = match schedule {
+            Schedule::Interval { period, aligned } => {
+                if period.is_zero() {
+                    anyhow::bail!("schedule {} has a zero period", name);
+                }
+                let start = if *aligned {
+                    Instant::now() + until_aligned(*period)
+                } else {
+                    Instant::now()
+                };
+                let mut interval = tokio::time::interval_at(start, *period);
+                interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+                Box::pin(IntervalStream::new(interval).map(move |_| tick(None)))
+            }
+            Schedule::Cron(cron) => {
+                let stream = stream::unfold((), move |_| async move {
+                    let next = cron.upcoming(Utc).next()?;
+                    sleep((next - Utc::now()).to_std().unwrap_or_default()).await;
+                    Some((tick(None), ()))
+                });
+                Box::pin(stream)
+            }
+            Schedule::Blocks { every, delay } => {
+                if *every == 0 {
+                    anyhow::bail!("schedule {} has a zero period", name);
+                }
+                let blocks = self.blocks.as_ref().ok_or_else(|| {
+                    anyhow::anyhow!("block schedule {} requires a block source", name)
+                })?;
+                let every = *every;
+                let due = move |block: &NewBlock| block.number.as_u64() % every == 0;
+                let stream = blocks
+                    .get_event_stream()
+                    .await?
+                    .filter(move |block| futures::future::ready(due(block)))
+                    .then(move |block| async move {
+                        sleep(*delay).await;
+                        tick(Some(block.number))
+                    });
+                Box::pin(stream)
+            }
+        };
+        Ok(stream)
+    }
+}
+
+impl Default for TimerCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Time left until the next multiple of `period` since the Unix epoch.
+fn until_aligned(period: Duration) -> Duration {
+    let period = period.as_nanos();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    Duration::from_nanos(((period - now % period) % period) as u64)
+}
+
+/// Implementation of the [Collector](Collector) trait for the
+/// [TimerCollector](TimerCollector). Ticks of all schedules are merged into one stream.
+#[async_trait]
+impl Collector<Tick> for TimerCollector {
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, Tick>> {
+        let mut streams = vec![];
+        for (name, schedule) in &self.schedules {
+            streams.push(self.schedule_stream(name, schedule).await?);
+        }
+        Ok(Box::pin(select_all(streams)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_cron_schedules() {
+        assert!(Schedule::cron("0 */5 * * * *").is_ok());
+        assert!(Schedule::cron("every five minutes").is_err());
+    }
+
+    struct NoBlocks;
+
+    #[async_trait]
+    impl Collector<NewBlock> for NoBlocks {
+        async fn get_event_stream(&self) -> Result<CollectorStream<'_, NewBlock>> {
+            Ok(Box::pin(stream::empty()))
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_zero_period() {
+        let collector = TimerCollector::new().with_schedule(
+            "requote",
+            Schedule::Interval {
+                period: Duration::ZERO,
+                aligned: false,
+            },
+        );
+        let err = collector.get_event_stream().await.err().unwrap();
+        assert_eq!(err.to_string(), "schedule requote has a zero period");
+
+        let collector = TimerCollector::new()
+            .with_blocks(Box::new(NoBlocks))
+            .with_schedule(
+                "rebalance",
+                Schedule::Blocks {
+                    every: 0,
+                    delay: Duration::ZERO,
+                },
+            );
+        let err = collector.get_event_stream().await.err().unwrap();
+        assert_eq!(err.to_string(), "schedule rebalance has a zero period");
+    }
+
+    #[test]
+    fn aligns_to_period() {
+        let period = Duration::from_secs(60);
+        assert!(until_aligned(period) < period);
+    }
+}
@@ -0,0 +1,59 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use binance::{
+    client::{BinanceApiConfig, BinanceClient},
+    types::{NewOrder, OrderId},
+};
+use serde::Serialize;
+use tracing::info;
+
+use crate::types::Executor;
+
+/// An executor that places and cancels orders on Binance.
+pub struct BinanceOrderExecutor {
+    client: BinanceClient,
+}
+
+/// An order to place on or cancel from Binance.
+#[derive(Debug, Clone, Serialize)]
+pub enum BinanceOrder {
+    Place(NewOrder),
+    Cancel { symbol: String, order_id: OrderId },
+}
+
+impl BinanceOrderExecutor {
+    pub fn new(cfg: BinanceApiConfig) -> Self {
+        Self {
+            client: BinanceClient::new(cfg),
+        }
+    }
+
+    /// Get the underlying client, e.g. to query orders.
+    pub fn client(&self) -> &BinanceClient {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl Executor<BinanceOrder> for BinanceOrderExecutor {
+    /// Place or cancel an order.
+    async fn execute(&self, action: BinanceOrder) -> Result<()> {
+        match action {
+            BinanceOrder::Place(order) => {
+                let order = self.client.place_order(&order).await?;
+                info!(
+                    "binance order {} on {}: {:?}, filled {} of {}",
+                    order.order_id, order.symbol, order.status, order.executed_qty, order.orig_qty
+                );
+            }
+            BinanceOrder::Cancel { symbol, order_id } => {
+                let order = self.client.cancel_order(&symbol, &order_id).await?;
+                info!(
+                    "cancelled binance order {} on {}, filled {} of {}",
+                    order.order_id, order.symbol, order.executed_qty, order.orig_qty
+                );
+            }
+        }
+        Ok(())
+    }
+}
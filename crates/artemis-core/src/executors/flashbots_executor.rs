@@ -1,36 +1,229 @@
-use std::sync::Arc;
+use std::{
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
 
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 use ethers::{
-    providers::Middleware, signers::Signer, types::transaction::eip2718::TypedTransaction,
+    providers::Middleware,
+    signers::Signer,
+    types::{transaction::eip2718::TypedTransaction, Bytes, H256, U256, U64},
+};
+use ethers_flashbots::{BundleRequest, FlashbotsMiddleware, SimulatedBundle};
+use futures::future::join_all;
+use matchmaker::{
+    client::{Client, RelayConfig},
+    types::EthBundleRequest,
 };
-use ethers_flashbots::{BundleRequest, FlashbotsMiddleware};
 use reqwest::Url;
-use tracing::error;
+use tracing::{error, info};
 
-use crate::types::Executor;
+use crate::{
+    types::Executor,
+    utilities::{
+        bundle_replacement::{CancelBundle, ReplacementUuids, SupersedeBundle},
+        gas_bidder::{GasBidder, PricedBundle},
+        inclusion_tracker::{BundleStatsSource, InclusionTrackerHandle, TrackedBundle},
+        nonce_manager::{NonceManager, NonceReservation},
+    },
+};
 
-/// A Flashbots executor that sends transactions to the Flashbots relay.
+/// A Flashbots executor that sends transactions to the Flashbots relay, and
+/// optionally to other block builders.
 pub struct FlashbotsExecutor<M, S> {
-    /// The Flashbots middleware.
-    fb_client: FlashbotsMiddleware<Arc<M>, S>,
+    /// The builders bundles are sent to. The first one is the Flashbots relay, which
+    /// is also used to simulate bundles.
+    builders: Vec<BuilderClient<M, S>>,
 
     /// The signer to sign transactions before sending to the relay.
     tx_signer: S,
+
+    /// Nonce manager used to fill the nonce of transactions which don't set one.
+    nonce_manager: Option<Arc<NonceManager<M>>>,
+
+    /// Number of future blocks each bundle targets.
+    target_blocks: u64,
+
+    /// Policy deciding whether to send a bundle after simulating it.
+    simulation_policy: SimulationPolicy,
+
+    /// Tracker reporting whether sent bundles landed, with the strategy name to report under.
+    inclusion_tracker: Option<(InclusionTrackerHandle, String)>,
+
+    /// Relay superseding bundles are sent to, by url, with their replacement IDs.
+    replacement_relay: Option<(String, Client<S>)>,
+    replacement_uuids: ReplacementUuids,
+
+    /// Bidder pricing [priced bundles](PricedBundle).
+    gas_bidder: Option<Box<dyn GasBidder>>,
 }
 
 /// A bundle of transactions to send to the Flashbots relay.
 pub type FlashbotsBundle = Vec<TypedTransaction>;
 
+/// A block builder endpoint, with the stats of the bundles sent to it.
+struct BuilderClient<M, S> {
+    name: String,
+    client: Arc<FlashbotsMiddleware<Arc<M>, S>>,
+    stats: Mutex<BuilderStats>,
+}
+
+/// Submission stats of a builder.
+#[derive(Debug, Clone, Default)]
+pub struct BuilderStats {
+    /// Number of bundles sent, including failed submissions.
+    pub sent: u64,
+    /// Number of submissions the builder rejected or didn't answer.
+    pub failed: u64,
+    /// Total time spent waiting for the builder to answer.
+    pub total_latency: Duration,
+}
+
+impl BuilderStats {
+    /// Share of the submissions the builder accepted.
+    pub fn success_rate(&self) -> Option<f64> {
+        (self.sent > 0).then(|| (self.sent - self.failed) as f64 / self.sent as f64)
+    }
+
+    /// Average time spent waiting for the builder to answer.
+    pub fn average_latency(&self) -> Option<Duration> {
+        (self.sent > 0).then(|| self.total_latency / self.sent as u32)
+    }
+}
+
+/// Policy deciding whether to send a bundle after simulating it.
+#[derive(Debug, Clone, Default)]
+pub struct SimulationPolicy {
+    /// Skip sending when the simulation fails or any transaction reverts.
+    pub skip_on_failure: bool,
+    /// Skip sending when the bundle pays the coinbase less than this.
+    pub min_coinbase_diff: U256,
+}
+
+impl SimulationPolicy {
+    /// Reason to skip sending a simulated bundle, if any.
+    fn skip_reason(&self, simulated_bundle: &SimulatedBundle) -> Option<String> {
+        if self.skip_on_failure {
+            let reverted = simulated_bundle
+                .transactions
+                .iter()
+                .find(|tx| tx.error.is_some() || tx.revert.is_some());
+            if let Some(tx) = reverted {
+                return Some(format!("tx {:?} reverted", tx.hash));
+            }
+        }
+        if simulated_bundle.coinbase_diff < self.min_coinbase_diff {
+            return Some(format!(
+                "coinbase diff {} is below {}",
+                simulated_bundle.coinbase_diff, self.min_coinbase_diff
+            ));
+        }
+        None
+    }
+}
+
 impl<M: Middleware, S: Signer> FlashbotsExecutor<M, S> {
     pub fn new(client: Arc<M>, tx_signer: S, relay_signer: S, relay_url: impl Into<Url>) -> Self {
-        let fb_client = FlashbotsMiddleware::new(client, relay_url, relay_signer);
+        let relay_url = relay_url.into();
+        let builder = BuilderClient {
+            name: relay_url.to_string(),
+            client: Arc::new(FlashbotsMiddleware::new(client, relay_url, relay_signer)),
+            stats: Mutex::new(BuilderStats::default()),
+        };
         Self {
-            fb_client,
+            builders: vec![builder],
             tx_signer,
+            nonce_manager: None,
+            target_blocks: 1,
+            simulation_policy: SimulationPolicy::default(),
+            inclusion_tracker: None,
+            replacement_relay: None,
+            replacement_uuids: ReplacementUuids::default(),
+            gas_bidder: None,
         }
     }
+
+    /// Price [priced bundles](PricedBundle) with `gas_bidder`.
+    pub fn with_gas_bidder(mut self, gas_bidder: Box<dyn GasBidder>) -> Self {
+        self.gas_bidder = Some(gas_bidder);
+        self
+    }
+
+    /// Take nonces from a nonce manager, which can be shared with other executors.
+    pub fn with_nonce_manager(mut self, nonce_manager: Arc<NonceManager<M>>) -> Self {
+        self.nonce_manager = Some(nonce_manager);
+        self
+    }
+
+    /// Target each of the next `target_blocks` blocks, instead of the next block only.
+    pub fn with_target_blocks(mut self, target_blocks: u64) -> Self {
+        self.target_blocks = target_blocks.max(1);
+        self
+    }
+
+    /// Decide whether to send bundles from their simulation.
+    pub fn with_simulation_policy(mut self, simulation_policy: SimulationPolicy) -> Self {
+        self.simulation_policy = simulation_policy;
+        self
+    }
+
+    /// Also send bundles to the builder at `url`, signing requests with `relay_signer`.
+    pub fn with_builder(
+        mut self,
+        name: impl Into<String>,
+        url: impl Into<Url>,
+        relay_signer: S,
+    ) -> Self {
+        let client = self.builders[0].client.inner().clone();
+        self.builders.push(BuilderClient {
+            name: name.into(),
+            client: Arc::new(FlashbotsMiddleware::new(client, url, relay_signer)),
+            stats: Mutex::new(BuilderStats::default()),
+        });
+        self
+    }
+
+    /// Track sent bundles, reporting their outcomes under `strategy`.
+    pub fn with_inclusion_tracker(
+        mut self,
+        inclusion_tracker: InclusionTrackerHandle,
+        strategy: impl Into<String>,
+    ) -> Self {
+        self.inclusion_tracker = Some((inclusion_tracker, strategy.into()));
+        self
+    }
+
+    /// Submission stats of each builder, by name.
+    pub fn builder_stats(&self) -> Vec<(String, BuilderStats)> {
+        self.builders
+            .iter()
+            .map(|builder| (builder.name.clone(), builder.stats.lock().unwrap().clone()))
+            .collect()
+    }
+}
+
+impl<M: Middleware, S: Signer + Clone + 'static> FlashbotsExecutor<M, S> {
+    /// Send superseding bundles to the relay configured by `config`, which must support
+    /// replacement IDs, signing requests with `relay_signer`.
+    pub fn with_replacement_relay(mut self, config: RelayConfig, relay_signer: S) -> Result<Self> {
+        let url = config.url.clone();
+        self.replacement_relay = Some((url, Client::from_config(relay_signer, config)?));
+        Ok(self)
+    }
+}
+
+/// A bundle of signed transactions targeting the next block, simulated on top of the
+/// current block.
+fn next_block_bundle(txs: Vec<Bytes>, block_number: U64) -> BundleRequest {
+    let mut bundle = BundleRequest::new();
+    for tx in txs {
+        bundle.add_transaction(tx);
+    }
+    bundle
+        .set_block(block_number + 1)
+        .set_simulation_block(block_number)
+        .set_simulation_timestamp(0)
 }
 
 #[async_trait]
@@ -40,37 +233,414 @@ where
     M::Error: 'static,
     S: Signer + 'static,
 {
-    /// Send a bundle to transactions to the Flashbots relay.
+    /// Send a bundle to transactions to the Flashbots relay and the other builders.
     async fn execute(&self, action: FlashbotsBundle) -> Result<()> {
-        // Add txs to bundle.
-        let mut bundle = BundleRequest::new();
+        let (block_number, txs, reservations) = self.sign_bundle(action).await?;
+        let bundle = next_block_bundle(txs, block_number);
+        if !self.passes_simulation(&bundle).await {
+            return Ok(());
+        }
+
+        // Send bundle to each builder, for each target block.
+        let last_target_block = block_number + self.target_blocks;
+        let submissions = (1..=self.target_blocks).flat_map(|offset| {
+            let bundle = bundle.clone().set_block(block_number + offset);
+            self.builders.iter().enumerate().map(move |(i, builder)| {
+                let bundle = bundle.clone();
+                async move { (i, self.send_to_builder(builder, &bundle).await) }
+            })
+        });
+        let responses = join_all(submissions).await;
+        let sent = responses.iter().any(|(_, response)| response.is_some());
+
+        // Bundle stats are queried from the relay, with the hash it returned.
+        if let Some((inclusion_tracker, strategy)) =
+            self.inclusion_tracker.as_ref().filter(|_| sent)
+        {
+            let bundle_hash = responses
+                .iter()
+                .filter(|(i, _)| *i == 0)
+                .find_map(|(_, response)| response.flatten());
+            let stats: Arc<dyn BundleStatsSource> = self.builders[0].client.clone();
+            inclusion_tracker.track(TrackedBundle {
+                strategy: strategy.clone(),
+                bundle_hash,
+                tx_hashes: bundle.transaction_hashes(),
+                first_block: block_number + 1,
+                last_block: last_target_block,
+                stats: Some(stats),
+            });
+        }
+
+        // The nonces are in use until the last target block has passed.
+        if sent {
+            reservations
+                .into_iter()
+                .for_each(|reservation| reservation.commit(Some(last_target_block)));
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<M, S> Executor<PricedBundle<FlashbotsBundle>> for FlashbotsExecutor<M, S>
+where
+    M: Middleware + 'static,
+    M::Error: 'static,
+    S: Signer + 'static,
+{
+    /// Price a bundle with the gas bidder, and send the bundle the strategy builds from
+    /// the bid.
+    async fn execute(&self, action: PricedBundle<FlashbotsBundle>) -> Result<()> {
+        let gas_bidder = self
+            .gas_bidder
+            .as_ref()
+            .ok_or_else(|| anyhow!("Priced bundle without a gas bidder"))?;
+        let bundle = action.build(gas_bidder.as_ref()).await?;
+        Executor::<FlashbotsBundle>::execute(self, bundle).await
+    }
+}
+
+#[async_trait]
+impl<M, S> Executor<SupersedeBundle<FlashbotsBundle>> for FlashbotsExecutor<M, S>
+where
+    M: Middleware + 'static,
+    M::Error: 'static,
+    S: Signer + Clone + 'static,
+{
+    /// Send a bundle to the replacement relay, replacing the last bundle sent for the
+    /// same key. Superseding bundles target the next block only, as each bundle
+    /// replaces the previous one.
+    async fn execute(&self, action: SupersedeBundle<FlashbotsBundle>) -> Result<()> {
+        let (url, relay) = self
+            .replacement_relay
+            .as_ref()
+            .ok_or_else(|| anyhow!("No replacement relay to supersede bundles with"))?;
+        let (block_number, txs, reservations) = self.sign_bundle(action.bundle).await?;
+
+        let bundle = next_block_bundle(txs.clone(), block_number);
+        if !self.passes_simulation(&bundle).await {
+            return Ok(());
+        }
+
+        self.replacement_uuids.prune(block_number + 1);
+        let replacement_uuid = self
+            .replacement_uuids
+            .get_or_insert(&action.key, block_number + 1);
+        let request = EthBundleRequest {
+            txs,
+            block_number: block_number + 1,
+            replacement_uuid: Some(replacement_uuid),
+            ..Default::default()
+        };
+        match relay.send_eth_bundle(&request).await {
+            Ok(response) => info!(
+                "bundle {:?} for {} sent to {}, replacing {}",
+                response.bundle_hash, action.key, url, replacement_uuid
+            ),
+            Err(e) => {
+                return Err(anyhow!(
+                    "Error sending bundle for {} to {}: {}",
+                    action.key,
+                    url,
+                    e
+                ))
+            }
+        }
+
+        reservations
+            .into_iter()
+            .for_each(|reservation| reservation.commit(Some(block_number + 1)));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<M, S> Executor<CancelBundle> for FlashbotsExecutor<M, S>
+where
+    M: Middleware + 'static,
+    M::Error: 'static,
+    S: Signer + Clone + 'static,
+{
+    /// Cancel the last bundle sent for a key.
+    async fn execute(&self, action: CancelBundle) -> Result<()> {
+        let (url, relay) = self
+            .replacement_relay
+            .as_ref()
+            .ok_or_else(|| anyhow!("No replacement relay to cancel bundles with"))?;
+        let Some(replacement_uuid) = self.replacement_uuids.remove(&action.key) else {
+            info!("no bundle to cancel for {}", action.key);
+            return Ok(());
+        };
+        relay.cancel_bundle(replacement_uuid).await.map_err(|e| {
+            anyhow!(
+                "Error cancelling bundle for {} on {}: {}",
+                action.key,
+                url,
+                e
+            )
+        })?;
+        info!("cancelled bundle for {} on {}", action.key, url);
+        Ok(())
+    }
+}
+
+impl<M, S> FlashbotsExecutor<M, S>
+where
+    M: Middleware + 'static,
+    M::Error: 'static,
+    S: Signer + 'static,
+{
+    /// Sign the transactions of a bundle, filling missing nonces from the nonce manager.
+    /// Returns the current block number, the signed transactions, and the nonce
+    /// reservations to commit once the bundle is sent.
+    async fn sign_bundle(
+        &self,
+        action: FlashbotsBundle,
+    ) -> Result<(U64, Vec<Bytes>, Vec<NonceReservation>)> {
+        let block_number = self.builders[0].client.get_block_number().await?;
+
+        // Forget nonces of bundles which targeted past blocks.
+        let signer = self.tx_signer.address();
+        if let Some(nonce_manager) = &self.nonce_manager {
+            nonce_manager.reconcile(signer, block_number).await?;
+        }
 
         // Sign each transaction in bundle.
-        for tx in action {
+        let mut txs = vec![];
+        let mut reservations: Vec<NonceReservation> = vec![];
+        for mut tx in action {
+            if let Some(nonce_manager) = &self.nonce_manager {
+                if tx.nonce().is_none() {
+                    let reservation = nonce_manager.reserve(signer).await?;
+                    tx.set_nonce(reservation.nonce());
+                    reservations.push(reservation);
+                }
+            }
             let signature = self.tx_signer.sign_transaction(&tx).await?;
-            bundle.add_transaction(tx.rlp_signed(&signature));
+            txs.push(tx.rlp_signed(&signature));
         }
+        Ok((block_number, txs, reservations))
+    }
 
-        // Simulate bundle.
-        let block_number = self.fb_client.get_block_number().await?;
-        let bundle = bundle
-            .set_block(block_number + 1)
-            .set_simulation_block(block_number)
-            .set_simulation_timestamp(0);
+    /// Simulate a bundle on the relay, and decide whether to send it from the
+    /// simulation policy.
+    async fn passes_simulation(&self, bundle: &BundleRequest) -> bool {
+        match self.builders[0].client.simulate_bundle(bundle).await {
+            Ok(simulated_bundle) => {
+                if let Some(reason) = self.simulation_policy.skip_reason(&simulated_bundle) {
+                    info!("Skipping bundle: {}", reason);
+                    return false;
+                }
+                true
+            }
+            Err(simulate_error) => {
+                error!("Error simulating bundle: {:?}", simulate_error);
+                !self.simulation_policy.skip_on_failure
+            }
+        }
+    }
 
-        let simulated_bundle = self.fb_client.simulate_bundle(&bundle).await;
+    /// Send a bundle to a builder and record the outcome. Returns `None` if the builder
+    /// rejected the bundle, or the bundle hash it returned, if any.
+    async fn send_to_builder(
+        &self,
+        builder: &BuilderClient<M, S>,
+        bundle: &BundleRequest,
+    ) -> Option<Option<H256>> {
+        let start = Instant::now();
+        let pending_bundle = builder.client.send_bundle(bundle).await;
+        let latency = start.elapsed();
 
-        if let Err(simulate_error) = simulated_bundle {
-            error!("Error simulating bundle: {:?}", simulate_error);
+        let mut stats = builder.stats.lock().unwrap();
+        stats.sent += 1;
+        stats.total_latency += latency;
+        match pending_bundle {
+            Ok(pending_bundle) => Some(pending_bundle.bundle_hash),
+            Err(send_error) => {
+                stats.failed += 1;
+                error!(
+                    "Error sending bundle to {} for block {:?}: {:?}",
+                    builder.name,
+                    bundle.block(),
+                    send_error
+                );
+                None
+            }
         }
+    }
+}
 
-        // Send bundle.
-        let pending_bundle = self.fb_client.send_bundle(&bundle).await;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        core::rand::thread_rng,
+        providers::{MockProvider, Provider},
+        signers::LocalWallet,
+        types::{Address, TransactionRequest},
+    };
+    use serde_json::json;
+    use test_support::mock_relay::{MockRelay, MockResponse};
 
-        if let Err(send_error) = pending_bundle {
-            error!("Error sending bundle: {:?}", send_error);
+    /// An executor sending to each relay, the first one simulating bundles, at block 100.
+    fn executor(
+        relays: &[MockRelay],
+        relay_signer: &LocalWallet,
+    ) -> FlashbotsExecutor<Provider<MockProvider>, LocalWallet> {
+        let (provider, mock) = Provider::mocked();
+        mock.push(U64::from(100)).unwrap();
+        let url = |relay: &MockRelay| Url::parse(relay.url()).unwrap();
+        let tx_signer = LocalWallet::new(&mut thread_rng());
+        let mut executor = FlashbotsExecutor::new(
+            Arc::new(provider),
+            tx_signer,
+            relay_signer.clone(),
+            url(&relays[0]),
+        );
+        for (i, relay) in relays.iter().enumerate().skip(1) {
+            executor =
+                executor.with_builder(format!("builder {}", i), url(relay), relay_signer.clone());
         }
+        executor
+    }
 
-        Ok(())
+    fn bundle() -> FlashbotsBundle {
+        vec![TransactionRequest::new()
+            .to(Address::repeat_byte(1))
+            .value(1)
+            .nonce(0)
+            .gas(21_000)
+            .gas_price(1)
+            .into()]
+    }
+
+    /// A simulation of a single transaction, which reverted if `revert` is set.
+    fn simulation(revert: Option<&str>) -> serde_json::Value {
+        json!({
+            "bundleHash": H256::random(),
+            "coinbaseDiff": "21000",
+            "ethSentToCoinbase": "0",
+            "bundleGasPrice": "1",
+            "totalGasUsed": "21000",
+            "gasFees": "21000",
+            "stateBlockNumber": "0x64",
+            "results": [{
+                "txHash": H256::random(),
+                "coinbaseDiff": "21000",
+                "ethSentToCoinbase": "0",
+                "gasPrice": "1",
+                "gasUsed": "21000",
+                "gasFees": "21000",
+                "fromAddress": Address::repeat_byte(2),
+                "toAddress": Address::repeat_byte(1),
+                "value": "0x",
+                "error": revert.map(|_| "execution reverted"),
+                "revert": revert,
+            }],
+        })
+    }
+
+    #[tokio::test]
+    async fn fans_out_to_target_blocks_and_builders() {
+        let relays = [MockRelay::spawn().await, MockRelay::spawn().await];
+        let relay_signer = LocalWallet::new(&mut thread_rng());
+        let executor = executor(&relays, &relay_signer).with_target_blocks(3);
+        executor.execute(bundle()).await.unwrap();
+
+        for relay in &relays {
+            let requests: Vec<_> = relay
+                .requests()
+                .into_iter()
+                .filter(|request| request.method == "eth_sendBundle")
+                .collect();
+            assert_eq!(requests.len(), 3);
+            assert!(requests
+                .iter()
+                .all(|request| request.signer == Some(relay_signer.address())));
+            let mut blocks: Vec<_> = requests
+                .iter()
+                .map(|request| request.params[0]["blockNumber"].clone())
+                .collect();
+            blocks.sort_by_key(|block| block.to_string());
+            assert_eq!(blocks, vec![json!("0x65"), json!("0x66"), json!("0x67")]);
+        }
+        let stats = executor.builder_stats();
+        assert!(stats
+            .iter()
+            .all(|(_, stats)| stats.sent == 3 && stats.failed == 0));
+    }
+
+    #[tokio::test]
+    async fn skips_reverting_bundles() {
+        let relays = [MockRelay::spawn().await, MockRelay::spawn().await];
+        relays[0].respond(
+            "eth_callBundle",
+            MockResponse::Result(simulation(Some("0x"))),
+        );
+        let relay_signer = LocalWallet::new(&mut thread_rng());
+        let executor = executor(&relays, &relay_signer).with_simulation_policy(SimulationPolicy {
+            skip_on_failure: true,
+            ..Default::default()
+        });
+        executor.execute(bundle()).await.unwrap();
+
+        assert_eq!(relays[0].requests()[0].method, "eth_callBundle");
+        assert!(relays.iter().all(|relay| relay.bundles().is_empty()));
+        assert!(executor
+            .builder_stats()
+            .iter()
+            .all(|(_, stats)| stats.sent == 0));
+    }
+
+    #[test]
+    fn skips_from_simulation() {
+        let simulated: SimulatedBundle = serde_json::from_value(simulation(None)).unwrap();
+        let reverted: SimulatedBundle = serde_json::from_value(simulation(Some("0x"))).unwrap();
+        let policy = SimulationPolicy::default();
+        assert_eq!(policy.skip_reason(&reverted), None);
+
+        let policy = SimulationPolicy {
+            skip_on_failure: true,
+            min_coinbase_diff: 21_000.into(),
+        };
+        assert_eq!(policy.skip_reason(&simulated), None);
+        assert!(policy.skip_reason(&reverted).unwrap().contains("reverted"));
+
+        let policy = SimulationPolicy {
+            skip_on_failure: false,
+            min_coinbase_diff: 21_001.into(),
+        };
+        assert!(policy
+            .skip_reason(&simulated)
+            .unwrap()
+            .contains("coinbase diff"));
+    }
+
+    #[tokio::test]
+    async fn tolerates_failing_builder() {
+        let relays = [MockRelay::spawn().await, MockRelay::spawn().await];
+        relays[1].respond("eth_sendBundle", MockResponse::HttpStatus(503));
+        let relay_signer = LocalWallet::new(&mut thread_rng());
+        let executor = executor(&relays, &relay_signer);
+        executor.execute(bundle()).await.unwrap();
+
+        assert_eq!(relays[0].bundles().len(), 1);
+        let stats = executor.builder_stats();
+        assert_eq!((stats[0].1.sent, stats[0].1.failed), (1, 0));
+        assert_eq!((stats[1].1.sent, stats[1].1.failed), (1, 1));
+    }
+
+    #[test]
+    fn builder_stats() {
+        assert_eq!(BuilderStats::default().success_rate(), None);
+        let stats = BuilderStats {
+            sent: 4,
+            failed: 1,
+            total_latency: Duration::from_millis(400),
+        };
+        assert_eq!(stats.success_rate(), Some(0.75));
+        assert_eq!(stats.average_latency(), Some(Duration::from_millis(100)));
     }
 }
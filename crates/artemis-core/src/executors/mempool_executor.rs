@@ -1,23 +1,87 @@
 use std::{
     ops::{Div, Mul},
     sync::Arc,
+    time::Duration,
 };
 
-use crate::types::Executor;
-use anyhow::{Context, Result};
+use crate::{
+    collectors::fee_market_collector::next_base_fee,
+    types::Executor,
+    utilities::{
+        gas_bidder::{BidRequest, GasBid, GasBidder, ProfitPercentageBidder},
+        nonce_manager::{is_nonce_error, NonceManager, NonceReservation},
+    },
+};
+use anyhow::{anyhow, Context, Result};
 use async_trait::async_trait;
 use ethers::{
     providers::Middleware,
-    types::{transaction::eip2718::TypedTransaction, U256},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockNumber, Eip1559TransactionRequest,
+        TransactionRequest, U256, U64,
+    },
 };
+use serde::Serialize;
+use tracing::{error, info};
+
+/// Interval at which the pending transactions are checked for replacement.
+const WATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Gas limit of a plain ether transfer, used by cancellations.
+const TRANSFER_GAS: u64 = 21_000;
+
+/// Smallest fee bump nodes accept for a replacement transaction.
+const MIN_BUMP_PERCENTAGE: u64 = 10;
+
+/// Number of blocks after which the nonce of a sent transaction is no longer held, in
+/// case the transaction was dropped from the mempool. If it is still pending, the
+/// chain's pending nonce keeps the nonce from being reused.
+const NONCE_EXPIRY_BLOCKS: u64 = 25;
 
-/// An executor that sends transactions to the mempool.
+/// An executor that sends transactions to the mempool. Legacy transactions are sent
+/// with a gas price, and EIP-1559 transactions with a max fee and a max priority fee.
 pub struct MempoolExecutor<M> {
     client: Arc<M>,
+    /// Upper bound for the gas price of legacy transactions and the max fee per gas
+    /// of EIP-1559 transactions.
+    max_fee_per_gas: Option<U256>,
+    /// Nonce manager used to fill the nonce of transactions which don't set one.
+    nonce_manager: Option<Arc<NonceManager<M>>>,
+    /// Policy for bumping the fees of transactions that stay pending.
+    replacement_policy: Option<ReplacementPolicy>,
+    /// Bidder pricing every transaction. Without one, transactions with a gas bid bid
+    /// a percentage of their profit, legacy ones as their gas price, and other
+    /// transactions use the node's estimate.
+    gas_bidder: Option<Box<dyn GasBidder>>,
+    /// Minimum profit a gas bid must leave.
+    min_retained_profit: Option<U256>,
 }
 
-/// Information about the gas bid for a transaction.
+/// Policy for replacing transactions that are stuck in the mempool with the same
+/// transaction at a higher fee.
 #[derive(Debug, Clone)]
+pub struct ReplacementPolicy {
+    /// Number of blocks a transaction can stay pending before its fees are bumped.
+    pub bump_after_blocks: u64,
+    /// Percentage to bump the fees by. Nodes only accept a replacement if its fees are
+    /// at least 10% higher, so smaller percentages are raised to 10.
+    pub bump_percentage: u64,
+    /// Maximum number of bumps for a single transaction.
+    pub max_bumps: u32,
+}
+
+impl Default for ReplacementPolicy {
+    fn default() -> Self {
+        Self {
+            bump_after_blocks: 2,
+            bump_percentage: 12,
+            max_bumps: 3,
+        }
+    }
+}
+
+/// Information about the gas bid for a transaction.
+#[derive(Debug, Clone, Serialize)]
 pub struct GasBidInfo {
     /// Total profit expected from opportunity
     pub total_profit: U256,
@@ -26,22 +90,131 @@ pub struct GasBidInfo {
     pub bid_percentage: u64,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct SubmitTxToMempool {
     pub tx: TypedTransaction,
     pub gas_bid_info: Option<GasBidInfo>,
+    /// Block after which the transaction is cancelled if it is still pending.
+    pub deadline: Option<U64>,
+}
+
+impl GasBidInfo {
+    /// Gas price corresponding to the bid percentage of the total profit.
+    pub fn bid_gas_price(&self, gas_usage: U256) -> U256 {
+        // gas price at which we'd break even, meaning 100% of profit goes to validator
+        let breakeven_gas_price = self.total_profit / gas_usage;
+        // gas price corresponding to bid percentage
+        breakeven_gas_price.mul(self.bid_percentage).div(100)
+    }
 }
 
 impl<M: Middleware> MempoolExecutor<M> {
     pub fn new(client: Arc<M>) -> Self {
-        Self { client }
+        Self {
+            client,
+            max_fee_per_gas: None,
+            nonce_manager: None,
+            replacement_policy: None,
+            gas_bidder: None,
+            min_retained_profit: None,
+        }
+    }
+
+    /// Price transactions with `gas_bidder`.
+    pub fn with_gas_bidder(mut self, gas_bidder: Box<dyn GasBidder>) -> Self {
+        self.gas_bidder = Some(gas_bidder);
+        self
+    }
+
+    /// Reject gas bids which would leave less than `min_retained_profit` of the profit.
+    pub fn with_min_retained_profit(mut self, min_retained_profit: U256) -> Self {
+        self.min_retained_profit = Some(min_retained_profit);
+        self
+    }
+
+    /// Bump the fees of transactions which stay pending, following `policy`.
+    pub fn with_replacement_policy(mut self, mut policy: ReplacementPolicy) -> Self {
+        policy.bump_percentage = policy.bump_percentage.max(MIN_BUMP_PERCENTAGE);
+        self.replacement_policy = Some(policy);
+        self
+    }
+
+    /// Take nonces from a nonce manager, which can be shared with other executors.
+    pub fn with_nonce_manager(mut self, nonce_manager: Arc<NonceManager<M>>) -> Self {
+        self.nonce_manager = Some(nonce_manager);
+        self
+    }
+
+    /// Cap the gas price of legacy transactions and the max fee per gas of EIP-1559
+    /// transactions. Cancellations of transactions at the cap go over it by the minimum
+    /// bump, so that they replace the transaction.
+    pub fn with_max_fee_per_gas(mut self, max_fee_per_gas: U256) -> Self {
+        self.max_fee_per_gas = Some(max_fee_per_gas);
+        self
+    }
+
+    fn cap(&self, fee: U256) -> U256 {
+        match self.max_fee_per_gas {
+            Some(max_fee_per_gas) => fee.min(max_fee_per_gas),
+            None => fee,
+        }
+    }
+}
+
+impl<M> MempoolExecutor<M>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    /// Predict the base fee of the next block from the latest block.
+    async fn predict_base_fee(&self) -> Result<U256> {
+        let block = self
+            .client
+            .get_block(BlockNumber::Latest)
+            .await
+            .context("Error getting latest block")?
+            .ok_or_else(|| anyhow!("Latest block not found"))?;
+        let base_fee = block
+            .base_fee_per_gas
+            .ok_or_else(|| anyhow!("Latest block has no base fee"))?;
+        Ok(next_base_fee(base_fee, block.gas_used, block.gas_limit))
+    }
+
+    /// Reserve a nonce for the transaction, if it has no nonce and a nonce manager is set,
+    /// returning it with the current block number. The nonces of the sender are first
+    /// reconciled with the chain, which forgets the nonces of mined transactions and
+    /// catches up with transactions sent elsewhere.
+    async fn reserve_nonce(
+        &self,
+        tx: &mut TypedTransaction,
+    ) -> Result<Option<(NonceReservation, U64)>> {
+        let Some(nonce_manager) = &self.nonce_manager else {
+            return Ok(None);
+        };
+        if tx.nonce().is_some() {
+            return Ok(None);
+        }
+        let sender = tx
+            .from()
+            .copied()
+            .or_else(|| self.client.default_sender())
+            .ok_or_else(|| anyhow!("Transaction has no sender to reserve a nonce for"))?;
+        let block_number = self
+            .client
+            .get_block_number()
+            .await
+            .context("Error getting block number")?;
+        nonce_manager.reconcile(sender, block_number).await?;
+        let reservation = nonce_manager.reserve(sender).await?;
+        tx.set_nonce(reservation.nonce());
+        Ok(Some((reservation, block_number)))
     }
 }
 
 #[async_trait]
 impl<M> Executor<SubmitTxToMempool> for MempoolExecutor<M>
 where
-    M: Middleware,
+    M: Middleware + 'static,
     M::Error: 'static,
 {
     /// Send a transaction to the mempool.
@@ -52,23 +225,797 @@ where
             .await
             .context("Error estimating gas usage: {}")?;
 
-        let bid_gas_price;
-        if let Some(gas_bid_info) = action.gas_bid_info {
-            // gas price at which we'd break even, meaning 100% of profit goes to validator
-            let breakeven_gas_price = gas_bid_info.total_profit / gas_usage;
-            // gas price corresponding to bid percentage
-            bid_gas_price = breakeven_gas_price
-                .mul(gas_bid_info.bid_percentage)
-                .div(100);
+        // Without a bidder, a gas bid is a percentage of the total profit. Legacy
+        // transactions bid it as their gas price, which doesn't need a base fee, so that
+        // they can be sent on chains without one.
+        let profit_bidder;
+        let bidder: Option<&dyn GasBidder> = match (&self.gas_bidder, &action.gas_bid_info) {
+            (Some(bidder), _) => Some(bidder.as_ref()),
+            (None, Some(gas_bid_info)) if matches!(action.tx, TypedTransaction::Eip1559(_)) => {
+                profit_bidder = ProfitPercentageBidder {
+                    bid_percentage: gas_bid_info.bid_percentage,
+                };
+                Some(&profit_bidder)
+            }
+            _ => None,
+        };
+
+        if let (None, Some(gas_bid_info)) = (bidder, &action.gas_bid_info) {
+            let bid_gas_price = gas_bid_info.bid_gas_price(gas_usage);
+            if let Some(min_retained_profit) = self.min_retained_profit {
+                let bid = GasBid {
+                    max_fee_per_gas: bid_gas_price,
+                    ..Default::default()
+                };
+                let request = BidRequest {
+                    tx: &action.tx,
+                    gas_usage,
+                    base_fee: U256::zero(),
+                    total_profit: Some(gas_bid_info.total_profit),
+                };
+                bid.ensure_retained_profit(&request, min_retained_profit)?;
+            }
+            action.tx.set_gas_price(self.cap(bid_gas_price));
+        } else if let Some(bidder) = bidder {
+            let base_fee = self.predict_base_fee().await?;
+            let request = BidRequest {
+                tx: &action.tx,
+                gas_usage,
+                base_fee,
+                total_profit: action
+                    .gas_bid_info
+                    .as_ref()
+                    .map(|gas_bid_info| gas_bid_info.total_profit),
+            };
+            let bid = bidder.bid(&request).await?;
+            if !bid.coinbase_transfer.is_zero() {
+                return Err(anyhow!(
+                    "Mempool transactions can't pay a coinbase transfer of {}",
+                    bid.coinbase_transfer
+                ));
+            }
+            if let Some(min_retained_profit) = self.min_retained_profit {
+                bid.ensure_retained_profit(&request, min_retained_profit)?;
+            }
+            let max_fee = self.cap(bid.max_fee_per_gas);
+            match &mut action.tx {
+                TypedTransaction::Eip1559(tx) => {
+                    tx.max_fee_per_gas = Some(max_fee);
+                    tx.max_priority_fee_per_gas = Some(bid.max_priority_fee_per_gas.min(max_fee));
+                }
+                tx => {
+                    tx.set_gas_price(max_fee);
+                }
+            }
+        } else if let TypedTransaction::Eip1559(tx) = &mut action.tx {
+            let (max_fee, max_priority_fee) = self
+                .client
+                .estimate_eip1559_fees(None)
+                .await
+                .context("Error estimating EIP-1559 fees")?;
+            let max_fee = self.cap(max_fee);
+            tx.max_fee_per_gas = Some(max_fee);
+            tx.max_priority_fee_per_gas = Some(max_priority_fee.min(max_fee));
         } else {
-            bid_gas_price = self
+            let gas_price = self
                 .client
                 .get_gas_price()
                 .await
                 .context("Error getting gas price: {}")?;
+            action.tx.set_gas_price(self.cap(gas_price));
+        }
+
+        let reservation = self.reserve_nonce(&mut action.tx).await?;
+        let watch = self.replacement_policy.is_some() || action.deadline.is_some();
+        if watch {
+            // The nonce and sender are needed to replace the transaction.
+            self.client
+                .fill_transaction(&mut action.tx, None)
+                .await
+                .context("Error filling transaction")?;
+        }
+        match self.client.send_transaction(action.tx.clone(), None).await {
+            Ok(pending_tx) => {
+                if let Some((reservation, block_number)) = reservation {
+                    reservation.commit(Some(block_number + NONCE_EXPIRY_BLOCKS));
+                }
+                if let (true, Some(from), Some(nonce)) =
+                    (watch, action.tx.from().copied(), action.tx.nonce().copied())
+                {
+                    info!("watching tx {:?}", *pending_tx);
+                    let watched = WatchedTx {
+                        client: self.client.clone(),
+                        tx: action.tx,
+                        from,
+                        nonce,
+                        deadline: action.deadline,
+                        policy: self.replacement_policy.clone(),
+                        max_fee_per_gas: self.max_fee_per_gas,
+                        sent_at: None,
+                        bumps: 0,
+                        cancelled: false,
+                    };
+                    tokio::spawn(watched.run());
+                }
+                Ok(())
+            }
+            Err(e) => {
+                if let (Some(nonce_manager), Some((reservation, _))) =
+                    (&self.nonce_manager, reservation)
+                {
+                    let signer = reservation.signer();
+                    // Release the nonce for reuse before resyncing, so that releasing it
+                    // doesn't leave a stale gap in the resynced nonces.
+                    drop(reservation);
+                    if is_nonce_error(&e) {
+                        nonce_manager.resync(signer).await?;
+                    }
+                }
+                Err(e.into())
+            }
+        }
+    }
+}
+
+/// A sent transaction, watched until a transaction with its nonce is included.
+struct WatchedTx<M> {
+    client: Arc<M>,
+    tx: TypedTransaction,
+    from: Address,
+    nonce: U256,
+    deadline: Option<U64>,
+    policy: Option<ReplacementPolicy>,
+    max_fee_per_gas: Option<U256>,
+    /// Block at which the latest version of the transaction was seen or sent.
+    sent_at: Option<U64>,
+    bumps: u32,
+    cancelled: bool,
+}
+
+impl<M> WatchedTx<M>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    async fn run(mut self) {
+        loop {
+            tokio::time::sleep(WATCH_INTERVAL).await;
+            match self.check().await {
+                Ok(true) => return,
+                Ok(false) => {}
+                Err(e) => {
+                    error!("error watching tx with nonce {}: {}", self.nonce, e);
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Replace the transaction if needed. Returns `true` once a transaction with its
+    /// nonce is included.
+    async fn check(&mut self) -> Result<bool> {
+        let included = self
+            .client
+            .get_transaction_count(self.from, Some(BlockNumber::Latest.into()))
+            .await?;
+        if included > self.nonce {
+            return Ok(true);
+        }
+        let block_number = self.client.get_block_number().await?;
+        let sent_at = *self.sent_at.get_or_insert(block_number);
+        let bump_due = match &self.policy {
+            Some(policy) => {
+                self.bumps < policy.max_bumps && block_number >= sent_at + policy.bump_after_blocks
+            }
+            None => false,
+        };
+
+        let deadline_passed = matches!(self.deadline, Some(deadline) if block_number > deadline);
+        let cancelling = deadline_passed && !self.cancelled;
+        let mut replacement = if cancelling {
+            cancellation(&self.tx, self.from, self.nonce)
+        } else if bump_due {
+            self.tx.clone()
+        } else {
+            return Ok(false);
+        };
+
+        let percentage = self
+            .policy
+            .as_ref()
+            .map_or(ReplacementPolicy::default().bump_percentage, |policy| {
+                policy.bump_percentage
+            });
+        // A cancellation only pays for a transfer, so if the cap leaves no room for a
+        // bump, it goes over the cap by as little as nodes accept.
+        let bumped = bump_fees(&mut replacement, percentage, self.max_fee_per_gas)
+            || (cancelling && bump_fees(&mut replacement, MIN_BUMP_PERCENTAGE, None));
+        if !bumped {
+            // Keep watching, so that the transaction is still cancelled at its deadline.
+            info!(
+                "max fee per gas reached, no more bumps for tx with nonce {}",
+                self.nonce
+            );
+            self.bumps = self.policy.as_ref().map_or(0, |policy| policy.max_bumps);
+            return Ok(false);
+        }
+        if cancelling {
+            info!("cancelling tx with nonce {} after deadline", self.nonce);
+        } else {
+            info!(
+                "bumping fees of tx with nonce {}, bump {}",
+                self.nonce,
+                self.bumps + 1
+            );
+        }
+        self.client
+            .send_transaction(replacement.clone(), None)
+            .await
+            .context("Error sending replacement")?;
+        if cancelling {
+            self.cancelled = true;
+        } else {
+            self.bumps += 1;
+        }
+        self.tx = replacement;
+        self.sent_at = Some(block_number);
+        Ok(false)
+    }
+}
+
+/// Bump the fees of a transaction by `percentage`, rounding up, without going over
+/// `max_fee_per_gas`. Returns `false` if the capped fees are too low for nodes to
+/// accept the replacement.
+fn bump_fees(tx: &mut TypedTransaction, percentage: u64, max_fee_per_gas: Option<U256>) -> bool {
+    let bump = |fee: U256, percentage: u64| (fee * (100 + percentage) + 99) / 100;
+    let cap = |fee: U256| max_fee_per_gas.map_or(fee, |cap| fee.min(cap));
+    match tx {
+        TypedTransaction::Eip1559(tx) => {
+            let max_fee = tx.max_fee_per_gas.unwrap_or_default();
+            let max_priority_fee = tx.max_priority_fee_per_gas.unwrap_or_default();
+            let bumped_max_fee = cap(bump(max_fee, percentage));
+            let bumped_max_priority_fee = bump(max_priority_fee, percentage).min(bumped_max_fee);
+            if bumped_max_fee < bump(max_fee, MIN_BUMP_PERCENTAGE)
+                || bumped_max_priority_fee < bump(max_priority_fee, MIN_BUMP_PERCENTAGE)
+            {
+                return false;
+            }
+            tx.max_fee_per_gas = Some(bumped_max_fee);
+            tx.max_priority_fee_per_gas = Some(bumped_max_priority_fee);
+        }
+        _ => {
+            let gas_price = tx.gas_price().unwrap_or_default();
+            let bumped_gas_price = cap(bump(gas_price, percentage));
+            if bumped_gas_price < bump(gas_price, MIN_BUMP_PERCENTAGE) {
+                return false;
+            }
+            tx.set_gas_price(bumped_gas_price);
+        }
+    }
+    true
+}
+
+/// A zero value self-transfer with the same nonce and fees as `tx`, which replaces it.
+fn cancellation(tx: &TypedTransaction, from: Address, nonce: U256) -> TypedTransaction {
+    let mut cancellation: TypedTransaction = match tx {
+        TypedTransaction::Eip1559(tx) => Eip1559TransactionRequest {
+            max_fee_per_gas: tx.max_fee_per_gas,
+            max_priority_fee_per_gas: tx.max_priority_fee_per_gas,
+            ..Default::default()
+        }
+        .into(),
+        _ => TransactionRequest {
+            gas_price: tx.gas_price(),
+            ..Default::default()
+        }
+        .into(),
+    };
+    cancellation.set_from(from);
+    cancellation.set_to(from);
+    cancellation.set_value(U256::zero());
+    cancellation.set_nonce(nonce);
+    cancellation.set_gas(TRANSFER_GAS);
+    if let Some(chain_id) = tx.chain_id() {
+        cancellation.set_chain_id(chain_id);
+    }
+    cancellation
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::gas_bidder::CoinbaseTransferBidder;
+    use ethers::{
+        providers::{MockProvider, Provider},
+        types::{Block, H256},
+    };
+
+    /// Script the responses of a mocked provider, in the order they are requested.
+    fn respond(mock: &MockProvider, responses: Vec<serde_json::Value>) {
+        // The mock answers with the last pushed response first.
+        for response in responses.into_iter().rev() {
+            mock.push(response).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn reconciles_nonces_across_blocks() {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+        let nonce_manager = Arc::new(NonceManager::new(provider.clone()));
+        let executor = MempoolExecutor::new(provider).with_nonce_manager(nonce_manager.clone());
+        let sender = Address::repeat_byte(1);
+        let tx = TransactionRequest::new()
+            .from(sender)
+            .to(Address::repeat_byte(2))
+            .gas(21_000);
+        let value = |v: u64| serde_json::to_value(U256::from(v)).unwrap();
+        let hash = || serde_json::to_value(H256::random()).unwrap();
+
+        // Responses for one execution: the gas estimate, the gas price, the block
+        // number, the mined and pending nonces to reconcile with, and the hash of the
+        // sent transaction.
+        let mut responses = vec![];
+        // Block 10, the nonce manager is initialized at nonce 5.
+        responses.extend([
+            value(21_000),
+            value(1),
+            value(10),
+            value(5),
+            value(5),
+            value(5),
+            hash(),
+        ]);
+        // Block 11, nonce 5 was mined.
+        responses.extend([
+            value(21_000),
+            value(1),
+            value(11),
+            value(6),
+            value(6),
+            hash(),
+        ]);
+        // Block 12, nonce 6 was mined, and the sender sent two transactions elsewhere.
+        responses.extend([
+            value(21_000),
+            value(1),
+            value(12),
+            value(9),
+            value(9),
+            hash(),
+        ]);
+        respond(&mock, responses);
+
+        for _ in 0..3 {
+            executor
+                .execute(SubmitTxToMempool {
+                    tx: tx.clone().into(),
+                    gas_bid_info: None,
+                    deadline: None,
+                })
+                .await
+                .unwrap();
         }
-        action.tx.set_gas_price(bid_gas_price);
-        self.client.send_transaction(action.tx, None).await?;
-        Ok(())
+        // The executor used nonces 5, 6 and 9, without tracking mined ones forever.
+        assert_eq!(
+            nonce_manager.reserve(sender).await.unwrap().nonce(),
+            10.into()
+        );
+    }
+
+    #[tokio::test]
+    async fn forgets_nonces_of_dropped_transactions() {
+        let (provider, mock) = Provider::mocked();
+        let provider = Arc::new(provider);
+        let nonce_manager = Arc::new(NonceManager::new(provider.clone()));
+        let executor = MempoolExecutor::new(provider).with_nonce_manager(nonce_manager.clone());
+        let sender = Address::repeat_byte(1);
+        let value = |v: u64| serde_json::to_value(U256::from(v)).unwrap();
+
+        // The transaction is sent at block 10 with nonce 5, then dropped from the
+        // mempool, so the chain's nonces stay at 5.
+        respond(
+            &mock,
+            vec![
+                value(21_000),
+                value(1),
+                value(10),
+                value(5),
+                value(5),
+                value(5),
+                serde_json::to_value(H256::random()).unwrap(),
+                value(5),
+                value(5),
+                value(5),
+                value(5),
+            ],
+        );
+        executor
+            .execute(SubmitTxToMempool {
+                tx: TransactionRequest::new()
+                    .from(sender)
+                    .to(Address::repeat_byte(2))
+                    .gas(21_000)
+                    .into(),
+                gas_bid_info: None,
+                deadline: None,
+            })
+            .await
+            .unwrap();
+
+        // The nonce is held until it expires.
+        nonce_manager.reconcile(sender, 34.into()).await.unwrap();
+        assert_eq!(
+            nonce_manager.reserve(sender).await.unwrap().nonce(),
+            6.into()
+        );
+        nonce_manager.reconcile(sender, 35.into()).await.unwrap();
+        assert_eq!(
+            nonce_manager.reserve(sender).await.unwrap().nonce(),
+            5.into()
+        );
+    }
+
+    #[tokio::test]
+    async fn legacy_gas_bid_does_not_need_base_fee() {
+        let (provider, mock) = Provider::mocked();
+        // The latest block has no base fee, but isn't needed to price a legacy bid.
+        respond(
+            &mock,
+            vec![
+                serde_json::to_value(U256::from(100_000)).unwrap(),
+                serde_json::to_value(H256::random()).unwrap(),
+            ],
+        );
+        let executor = MempoolExecutor::new(Arc::new(provider));
+        let tx = TransactionRequest::new()
+            .from(Address::repeat_byte(1))
+            .to(Address::repeat_byte(2))
+            .gas(100_000);
+        executor
+            .execute(SubmitTxToMempool {
+                tx: tx.clone().into(),
+                gas_bid_info: Some(GasBidInfo {
+                    total_profit: U256::exp10(16),
+                    bid_percentage: 50,
+                }),
+                deadline: None,
+            })
+            .await
+            .unwrap();
+
+        let estimated: TypedTransaction = tx.clone().into();
+        mock.assert_request("eth_estimateGas", [&estimated])
+            .unwrap();
+        // Half of the break even gas price of 1e16 / 1e5.
+        let sent: TypedTransaction = tx.gas_price(50_000_000_000u64).into();
+        mock.assert_request("eth_sendTransaction", [&sent]).unwrap();
+    }
+
+    #[tokio::test]
+    async fn prices_eip1559_bid_from_base_fee() {
+        let (provider, mock) = Provider::mocked();
+        // Half full block, so the predicted base fee stays at 10 gwei.
+        let latest = Block::<H256> {
+            base_fee_per_gas: Some(10_000_000_000u64.into()),
+            gas_used: 15_000_000.into(),
+            gas_limit: 30_000_000.into(),
+            ..Default::default()
+        };
+        respond(
+            &mock,
+            vec![
+                serde_json::to_value(U256::from(100_000)).unwrap(),
+                serde_json::to_value(latest).unwrap(),
+                serde_json::to_value(H256::random()).unwrap(),
+            ],
+        );
+        let executor = MempoolExecutor::new(Arc::new(provider));
+        let tx = Eip1559TransactionRequest::new()
+            .from(Address::repeat_byte(1))
+            .to(Address::repeat_byte(2))
+            .gas(100_000);
+        executor
+            .execute(SubmitTxToMempool {
+                tx: tx.clone().into(),
+                gas_bid_info: Some(GasBidInfo {
+                    total_profit: U256::exp10(16),
+                    bid_percentage: 50,
+                }),
+                deadline: None,
+            })
+            .await
+            .unwrap();
+
+        let estimated: TypedTransaction = tx.clone().into();
+        mock.assert_request("eth_estimateGas", [&estimated])
+            .unwrap();
+        mock.assert_request(
+            "eth_getBlockByNumber",
+            [serde_json::json!("latest"), serde_json::json!(false)],
+        )
+        .unwrap();
+        // Half of the break even gas price of 1e16 / 1e5, the priority fee being what is
+        // left on top of the base fee.
+        let sent: TypedTransaction = tx
+            .max_fee_per_gas(50_000_000_000u64)
+            .max_priority_fee_per_gas(40_000_000_000u64)
+            .into();
+        mock.assert_request("eth_sendTransaction", [&sent]).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejects_coinbase_transfer_bids() {
+        let (provider, mock) = Provider::mocked();
+        let latest = Block::<H256> {
+            base_fee_per_gas: Some(10_000_000_000u64.into()),
+            gas_used: 15_000_000.into(),
+            gas_limit: 30_000_000.into(),
+            ..Default::default()
+        };
+        respond(
+            &mock,
+            vec![
+                serde_json::to_value(U256::from(100_000)).unwrap(),
+                serde_json::to_value(latest).unwrap(),
+            ],
+        );
+        let executor = MempoolExecutor::new(Arc::new(provider))
+            .with_gas_bidder(Box::new(CoinbaseTransferBidder { bid_percentage: 50 }));
+        let tx = Eip1559TransactionRequest::new()
+            .from(Address::repeat_byte(1))
+            .to(Address::repeat_byte(2))
+            .gas(100_000);
+        let error = executor
+            .execute(SubmitTxToMempool {
+                tx: tx.into(),
+                gas_bid_info: Some(GasBidInfo {
+                    total_profit: U256::exp10(16),
+                    bid_percentage: 50,
+                }),
+                deadline: None,
+            })
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("coinbase transfer"));
+    }
+
+    /// A watched EIP-1559 transaction with nonce 3, which wasn't seen yet.
+    fn watched_tx(
+        provider: Provider<MockProvider>,
+        policy: Option<ReplacementPolicy>,
+        deadline: Option<U64>,
+    ) -> WatchedTx<Provider<MockProvider>> {
+        let from = Address::repeat_byte(1);
+        let tx = Eip1559TransactionRequest::new()
+            .from(from)
+            .to(Address::repeat_byte(2))
+            .value(1)
+            .gas(100_000)
+            .nonce(3)
+            .max_fee_per_gas(100_000_000_000u64)
+            .max_priority_fee_per_gas(2_000_000_000u64);
+        WatchedTx {
+            client: Arc::new(provider),
+            tx: tx.into(),
+            from,
+            nonce: 3.into(),
+            deadline,
+            policy,
+            max_fee_per_gas: None,
+            sent_at: None,
+            bumps: 0,
+            cancelled: false,
+        }
+    }
+
+    /// Assert that the watcher polled the mined nonce of `from` and the block number.
+    fn assert_polled(mock: &MockProvider, from: Address) {
+        mock.assert_request(
+            "eth_getTransactionCount",
+            [
+                serde_json::to_value(from).unwrap(),
+                serde_json::json!("latest"),
+            ],
+        )
+        .unwrap();
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replaces_stuck_tx() {
+        let (provider, mock) = Provider::mocked();
+        let policy = ReplacementPolicy {
+            bump_after_blocks: 2,
+            bump_percentage: 10,
+            max_bumps: 3,
+        };
+        let mut watched = watched_tx(provider, Some(policy), None);
+        let value = |v: u64| serde_json::to_value(U256::from(v)).unwrap();
+        // Not mined at block 10, when it is first seen, nor at block 12.
+        respond(
+            &mock,
+            vec![
+                value(3),
+                value(10),
+                value(3),
+                value(12),
+                serde_json::to_value(H256::random()).unwrap(),
+            ],
+        );
+
+        assert!(!watched.check().await.unwrap());
+        assert_polled(&mock, watched.from);
+        assert!(!watched.check().await.unwrap());
+        assert_polled(&mock, watched.from);
+
+        // The same transaction was re-sent at the same nonce, with fees 10% higher.
+        let TypedTransaction::Eip1559(replacement) = &watched.tx else {
+            panic!("tx type changed");
+        };
+        assert_eq!(replacement.nonce, Some(3.into()));
+        assert_eq!(replacement.value, Some(1.into()));
+        assert_eq!(replacement.max_fee_per_gas, Some(110_000_000_000u64.into()));
+        assert_eq!(
+            replacement.max_priority_fee_per_gas,
+            Some(2_200_000_000u64.into())
+        );
+        mock.assert_request("eth_sendTransaction", [&watched.tx])
+            .unwrap();
+        assert_eq!((watched.bumps, watched.sent_at), (1, Some(12.into())));
+    }
+
+    #[tokio::test]
+    async fn cancels_tx_after_deadline() {
+        let (provider, mock) = Provider::mocked();
+        let mut watched = watched_tx(provider, None, Some(10.into()));
+        let value = |v: u64| serde_json::to_value(U256::from(v)).unwrap();
+        // Still pending after the deadline at block 11, then the cancellation is mined.
+        respond(
+            &mock,
+            vec![
+                value(3),
+                value(11),
+                serde_json::to_value(H256::random()).unwrap(),
+                value(4),
+            ],
+        );
+
+        assert!(!watched.check().await.unwrap());
+        assert_polled(&mock, watched.from);
+        let from = watched.from;
+        let TypedTransaction::Eip1559(cancellation) = &watched.tx else {
+            panic!("tx type changed");
+        };
+        assert_eq!(cancellation.to, Some(from.into()));
+        assert_eq!(cancellation.value, Some(U256::zero()));
+        assert_eq!(cancellation.nonce, Some(3.into()));
+        // Fees are bumped, so that the cancellation replaces the transaction.
+        assert_eq!(
+            cancellation.max_fee_per_gas,
+            Some(112_000_000_000u64.into())
+        );
+        mock.assert_request("eth_sendTransaction", [&watched.tx])
+            .unwrap();
+        assert!(watched.cancelled);
+
+        assert!(watched.check().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn cancels_tx_after_reaching_cap() {
+        let (provider, mock) = Provider::mocked();
+        let policy = ReplacementPolicy {
+            bump_after_blocks: 1,
+            bump_percentage: 10,
+            max_bumps: 3,
+        };
+        let mut watched = watched_tx(provider, Some(policy), Some(12.into()));
+        watched.max_fee_per_gas = Some(115_000_000_000u64.into());
+        let value = |v: u64| serde_json::to_value(U256::from(v)).unwrap();
+        // First seen at block 10, bumped at block 11 and cancelled at block 13.
+        respond(
+            &mock,
+            vec![
+                value(3),
+                value(10),
+                value(3),
+                value(11),
+                serde_json::to_value(H256::random()).unwrap(),
+                value(3),
+                value(12),
+                value(3),
+                value(13),
+                serde_json::to_value(H256::random()).unwrap(),
+            ],
+        );
+
+        assert!(!watched.check().await.unwrap());
+        assert!(!watched.check().await.unwrap());
+        let max_fee = |watched: &WatchedTx<_>| match &watched.tx {
+            TypedTransaction::Eip1559(tx) => tx.max_fee_per_gas.unwrap(),
+            _ => panic!("tx type changed"),
+        };
+        assert_eq!(max_fee(&watched), 110_000_000_000u64.into());
+
+        // Another 10% would exceed the cap, so the fees stay, and so does the watcher.
+        assert!(!watched.check().await.unwrap());
+        assert_eq!(watched.bumps, 3);
+        assert!(!watched.cancelled);
+
+        // The cancellation still replaces the transaction, with the minimum bump.
+        assert!(!watched.check().await.unwrap());
+        assert!(watched.cancelled);
+        assert_eq!(watched.tx.to_addr(), Some(&watched.from));
+        assert_eq!(max_fee(&watched), 121_000_000_000u64.into());
+    }
+
+    #[tokio::test]
+    async fn cancels_only_once_sent() {
+        let (provider, mock) = Provider::mocked();
+        let mut watched = watched_tx(provider, None, Some(10.into()));
+        let value = |v: u64| serde_json::to_value(U256::from(v)).unwrap();
+        // The node is unavailable when the cancellation is first sent.
+        respond(&mock, vec![value(3), value(11)]);
+
+        assert!(watched.check().await.is_err());
+        assert!(!watched.cancelled);
+    }
+
+    #[test]
+    fn bumps_fees_up_to_cap() {
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .max_fee_per_gas(100)
+            .max_priority_fee_per_gas(10)
+            .into();
+        assert!(bump_fees(&mut tx, 10, Some(U256::from(120))));
+        let TypedTransaction::Eip1559(bumped) = &tx else {
+            panic!("tx type changed");
+        };
+        assert_eq!(bumped.max_fee_per_gas, Some(110.into()));
+        assert_eq!(bumped.max_priority_fee_per_gas, Some(11.into()));
+        assert!(!bump_fees(&mut tx, 10, Some(U256::from(120))));
+        // A larger bump is capped, as long as it still clears the minimum bump.
+        assert!(bump_fees(&mut tx, 20, Some(U256::from(125))));
+        let TypedTransaction::Eip1559(bumped) = &tx else {
+            panic!("tx type changed");
+        };
+        assert_eq!(bumped.max_fee_per_gas, Some(125.into()));
+        assert_eq!(bumped.max_priority_fee_per_gas, Some(14.into()));
+
+        let mut tx: TypedTransaction = TransactionRequest::new().gas_price(15).into();
+        assert!(bump_fees(&mut tx, 10, None));
+        // Rounded up, so that the bump is at least 10%.
+        assert_eq!(tx.gas_price(), Some(17.into()));
+    }
+
+    #[test]
+    fn raises_bumps_to_minimum() {
+        let executor = MempoolExecutor::new(Arc::new(Provider::mocked().0))
+            .with_replacement_policy(ReplacementPolicy {
+                bump_percentage: 5,
+                ..Default::default()
+            });
+        let policy = executor.replacement_policy.unwrap();
+        assert_eq!(policy.bump_percentage, MIN_BUMP_PERCENTAGE);
+    }
+
+    #[test]
+    fn cancels_with_self_transfer() {
+        let from = Address::repeat_byte(1);
+        let tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .to(Address::repeat_byte(2))
+            .value(1)
+            .data(vec![1, 2, 3])
+            .max_fee_per_gas(100)
+            .chain_id(1)
+            .into();
+        let cancellation = cancellation(&tx, from, 7.into());
+        assert_eq!(cancellation.to_addr(), Some(&from));
+        assert_eq!(cancellation.value(), Some(&U256::zero()));
+        assert_eq!(cancellation.data(), None);
+        assert_eq!(cancellation.nonce(), Some(&7.into()));
+        assert_eq!(cancellation.chain_id(), Some(1.into()));
+        assert!(matches!(cancellation, TypedTransaction::Eip1559(_)));
     }
 }
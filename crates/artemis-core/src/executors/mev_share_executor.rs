@@ -1,25 +1,179 @@
-use crate::types::Executor;
-use anyhow::Result;
+use crate::{
+    types::Executor,
+    utilities::{
+        bundle_replacement::{CancelBundle, ReplacementUuids, SupersedeBundle},
+        gas_bidder::{GasBidder, PricedBundle},
+        inclusion_tracker::{InclusionTrackerHandle, TrackedBundle},
+    },
+};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
-use ethers::{signers::Signer, types::Chain};
+use ethers::{
+    signers::Signer,
+    types::{Chain, H256, U256},
+    utils::keccak256,
+};
 use futures::{stream, StreamExt};
-use matchmaker::{client::Client, types::BundleRequest};
-use tracing::{error, info};
+use matchmaker::{
+    client::{Client, RelayConfig},
+    error::RelayError,
+    types::{BundleRequest, BundleTx, SendBundleResponse, SimBundleOverrides},
+};
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// Number of times a bundle is sent when the matchmaker fails with retryable errors.
+const MAX_SEND_ATTEMPTS: u32 = 3;
 
 /// An executor that sends bundles to the MEV-share Matchmaker.
 pub struct MevshareExecutor<S> {
     matchmaker_client: Client<S>,
+
+    /// Tracker reporting whether sent bundles landed, with the strategy name to report under.
+    inclusion_tracker: Option<(InclusionTrackerHandle, String)>,
+
+    /// If set, bundles are simulated before being sent, and dropped if they fail or
+    /// their profit is below this minimum.
+    min_sim_profit: Option<U256>,
+
+    /// Replacement IDs of the superseding bundles sent for each key.
+    replacement_uuids: ReplacementUuids,
+
+    /// Bidder pricing [priced bundles](PricedBundle).
+    gas_bidder: Option<Box<dyn GasBidder>>,
 }
 
 /// List of bundles to send to the Matchmaker.
 pub type Bundles = Vec<BundleRequest>;
 
 impl<S: Signer + Clone + 'static> MevshareExecutor<S> {
-    pub fn new(signer: S, chain: Chain) -> Self {
-        Self {
-            matchmaker_client: Client::new(signer, chain),
+    /// Send bundles to the Flashbots matchmaker of the given chain.
+    pub fn new(signer: S, chain: Chain) -> Result<Self> {
+        Self::from_config(signer, RelayConfig::for_chain(chain)?)
+    }
+
+    /// Send bundles to the matchmaker configured by `config`.
+    pub fn from_config(signer: S, config: RelayConfig) -> Result<Self> {
+        Ok(Self {
+            matchmaker_client: Client::from_config(signer, config)?,
+            inclusion_tracker: None,
+            min_sim_profit: None,
+            replacement_uuids: ReplacementUuids::default(),
+            gas_bidder: None,
+        })
+    }
+
+    /// Price [priced bundles](PricedBundle) with `gas_bidder`.
+    pub fn with_gas_bidder(mut self, gas_bidder: Box<dyn GasBidder>) -> Self {
+        self.gas_bidder = Some(gas_bidder);
+        self
+    }
+
+    /// Simulate bundles before sending them, dropping those which fail or make less
+    /// than `min_profit`.
+    pub fn with_simulation(mut self, min_profit: U256) -> Self {
+        self.min_sim_profit = Some(min_profit);
+        self
+    }
+
+    /// Send a bundle, retrying retryable failures such as rate limiting with a linear
+    /// backoff.
+    async fn send_with_retries(
+        &self,
+        bundle: &BundleRequest,
+    ) -> Result<SendBundleResponse, RelayError> {
+        let mut attempt = 1;
+        loop {
+            match self.matchmaker_client.send_bundle(bundle).await {
+                Err(e) if e.is_retryable() && attempt < MAX_SEND_ATTEMPTS => {
+                    warn!("retrying bundle after error: {}", e);
+                    tokio::time::sleep(Duration::from_millis(100 * attempt as u64)).await;
+                    attempt += 1;
+                }
+                Err(e) if e.is_auth_error() => {
+                    error!(
+                        "Matchmaker rejected our credentials, check the relay signer: {}",
+                        e
+                    );
+                    return Err(e);
+                }
+                res => return res,
+            }
         }
     }
+
+    /// Whether a bundle simulates successfully with at least the minimum profit. Always
+    /// true if simulation is disabled.
+    async fn should_send(&self, bundle: &BundleRequest) -> bool {
+        let Some(min_profit) = self.min_sim_profit else {
+            return true;
+        };
+        match self
+            .matchmaker_client
+            .sim_bundle(bundle, &SimBundleOverrides::default())
+            .await
+        {
+            Ok(sim) if !sim.success => {
+                info!("dropping bundle failing simulation: {:?}", sim.error);
+                false
+            }
+            Ok(sim) if sim.profit < min_profit => {
+                info!(
+                    "dropping bundle with simulated profit {} below {}",
+                    sim.profit, min_profit
+                );
+                false
+            }
+            Ok(_) => true,
+            Err(e) => {
+                error!("Bundle simulation error: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Track sent bundles, reporting their outcomes under `strategy`.
+    pub fn with_inclusion_tracker(
+        mut self,
+        inclusion_tracker: InclusionTrackerHandle,
+        strategy: impl Into<String>,
+    ) -> Self {
+        self.inclusion_tracker = Some((inclusion_tracker, strategy.into()));
+        self
+    }
+
+    /// Start tracking a bundle accepted by the matchmaker. Only our own transactions
+    /// are tracked, as the transactions we backrun can land without the bundle. The
+    /// matchmaker has no bundle stats, so bundles which don't land are reported as
+    /// [missed](crate::utilities::inclusion_tracker::BundleOutcome::Missed) rather than
+    /// outbid or expired.
+    fn track(&self, bundle: &BundleRequest, bundle_hash: H256) {
+        let Some((inclusion_tracker, strategy)) = &self.inclusion_tracker else {
+            return;
+        };
+        let tx_hashes = own_tx_hashes(bundle);
+        inclusion_tracker.track(TrackedBundle {
+            strategy: strategy.clone(),
+            bundle_hash: Some(bundle_hash),
+            tx_hashes,
+            first_block: bundle.inclusion.block,
+            last_block: bundle.inclusion.max_block.unwrap_or(bundle.inclusion.block),
+            stats: None,
+        });
+    }
+}
+
+/// Hashes of the signed transactions of a bundle, including those of nested bundles.
+fn own_tx_hashes(bundle: &BundleRequest) -> Vec<H256> {
+    bundle
+        .body
+        .iter()
+        .flat_map(|tx| match tx {
+            BundleTx::Tx { tx, .. } => vec![H256::from(keccak256(tx))],
+            BundleTx::TxHash { .. } => vec![],
+            BundleTx::Bundle { bundle } => own_tx_hashes(bundle),
+        })
+        .collect()
 }
 
 #[async_trait]
@@ -27,16 +181,23 @@ impl<S: Signer + Clone + 'static> Executor<Bundles> for MevshareExecutor<S> {
     /// Send bundles to the matchmaker.
     async fn execute(&self, action: Bundles) -> Result<()> {
         let bodies = stream::iter(action)
-            .map(|bundle| {
-                let client = &self.matchmaker_client;
-                async move { client.send_bundle(&bundle).await }
+            .map(|bundle| async move {
+                if !self.should_send(&bundle).await {
+                    return None;
+                }
+                let response = self.send_with_retries(&bundle).await;
+                Some((bundle, response))
             })
-            .buffer_unordered(5);
+            .buffer_unordered(5)
+            .filter_map(|sent| async move { sent });
 
         bodies
-            .for_each(|b| async {
+            .for_each(|(bundle, b)| async move {
                 match b {
-                    Ok(b) => info!("Bundle response: {:?}", b),
+                    Ok(b) => {
+                        info!("Bundle response: {:?}", b);
+                        self.track(&bundle, b.bundle_hash);
+                    }
                     Err(e) => error!("Bundle error: {}", e),
                 }
             })
@@ -44,3 +205,60 @@ impl<S: Signer + Clone + 'static> Executor<Bundles> for MevshareExecutor<S> {
         Ok(())
     }
 }
+
+#[async_trait]
+impl<S: Signer + Clone + 'static> Executor<PricedBundle<BundleRequest>> for MevshareExecutor<S> {
+    /// Price a bundle with the gas bidder, and send the bundle the strategy builds from
+    /// the bid.
+    async fn execute(&self, action: PricedBundle<BundleRequest>) -> Result<()> {
+        let gas_bidder = self
+            .gas_bidder
+            .as_ref()
+            .ok_or_else(|| anyhow!("Priced bundle without a gas bidder"))?;
+        let bundle = action.build(gas_bidder.as_ref()).await?;
+        Executor::<Bundles>::execute(self, vec![bundle]).await
+    }
+}
+
+#[async_trait]
+impl<S: Signer + Clone + 'static> Executor<SupersedeBundle<BundleRequest>> for MevshareExecutor<S> {
+    /// Send a bundle to the matchmaker, replacing the last bundle sent for the same key.
+    async fn execute(&self, action: SupersedeBundle<BundleRequest>) -> Result<()> {
+        let inclusion = &action.bundle.inclusion;
+        self.replacement_uuids.prune(inclusion.block);
+        let replacement_uuid = self
+            .replacement_uuids
+            .get_or_insert(&action.key, inclusion.max_block.unwrap_or(inclusion.block));
+        let bundle = action.bundle.with_replacement_uuid(replacement_uuid);
+        if !self.should_send(&bundle).await {
+            return Ok(());
+        }
+        let response = self
+            .send_with_retries(&bundle)
+            .await
+            .map_err(|e| anyhow!("Error sending bundle for {}: {}", action.key, e))?;
+        info!(
+            "bundle {:?} for {} sent, replacing {}",
+            response.bundle_hash, action.key, replacement_uuid
+        );
+        self.track(&bundle, response.bundle_hash);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S: Signer + Clone + 'static> Executor<CancelBundle> for MevshareExecutor<S> {
+    /// Cancel the last bundle sent for a key.
+    async fn execute(&self, action: CancelBundle) -> Result<()> {
+        let Some(replacement_uuid) = self.replacement_uuids.remove(&action.key) else {
+            info!("no bundle to cancel for {}", action.key);
+            return Ok(());
+        };
+        self.matchmaker_client
+            .cancel_bundle(replacement_uuid)
+            .await
+            .map_err(|e| anyhow!("Error cancelling bundle for {}: {}", action.key, e))?;
+        info!("cancelled bundle for {}", action.key);
+        Ok(())
+    }
+}
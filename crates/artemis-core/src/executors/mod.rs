@@ -2,6 +2,9 @@
 //! executing them in different domains. For example, an executor might take a
 //! `SubmitTx` action and submit it to the mempool.
 
+/// This executor places and cancels orders on Binance.
+pub mod binance_order_executor;
+
 /// This executor submits transactions to the flashbots relay.
 pub mod flashbots_executor;
 
@@ -10,3 +13,15 @@ pub mod mempool_executor;
 
 /// This executor submits bundles to the flashbots matchmaker.
 pub mod mev_share_executor;
+
+/// This executor submits transactions to private transaction RPCs.
+pub mod private_tx_executor;
+
+/// This executor records actions to a durable log, optionally executing them too.
+pub mod recording_executor;
+
+/// This executor enforces risk limits on the actions of another executor.
+pub mod risk_manager_executor;
+
+/// This executor simulates actions against a local fork instead of sending them.
+pub mod simulated_executor;
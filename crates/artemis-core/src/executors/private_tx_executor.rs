@@ -0,0 +1,143 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::{
+    signers::Signer,
+    types::{transaction::eip2718::TypedTransaction, H256, U64},
+};
+use futures::future::join_all;
+use matchmaker::{
+    client::{Client, RelayConfig},
+    types::{PrivateTransactionPreferences, PrivateTransactionRequest},
+};
+use serde::Serialize;
+use tracing::{error, info};
+
+use crate::types::Executor;
+
+/// An executor that sends transactions privately to builders through private
+/// transaction RPCs, such as Flashbots Protect, so that they are not exposed in the
+/// public mempool. Transactions can be sent to several endpoints at once.
+pub struct PrivateTxExecutor<S> {
+    /// The endpoints transactions are sent to, by url.
+    endpoints: Vec<(String, Client<S>)>,
+
+    /// The signer to sign transactions before sending them.
+    tx_signer: S,
+
+    /// The signer to authenticate requests to the endpoints.
+    relay_signer: S,
+
+    /// Whether to ask endpoints to share transactions with all builders.
+    fast: bool,
+}
+
+/// A transaction to send privately. It must be fully populated, as it is signed as is.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubmitPrivateTx {
+    pub tx: TypedTransaction,
+    /// The last block the transaction can be included in.
+    pub max_block_number: Option<U64>,
+}
+
+/// Cancel a private transaction sent earlier.
+#[derive(Debug, Clone)]
+pub struct CancelPrivateTx {
+    pub tx_hash: H256,
+}
+
+impl<S: Signer + Clone + 'static> PrivateTxExecutor<S> {
+    /// Send transactions to the endpoint configured by `config`.
+    pub fn new(tx_signer: S, relay_signer: S, config: RelayConfig) -> Result<Self> {
+        Ok(Self {
+            endpoints: vec![(
+                config.url.clone(),
+                Client::from_config(relay_signer.clone(), config)?,
+            )],
+            tx_signer,
+            relay_signer,
+            fast: false,
+        })
+    }
+
+    /// Also send transactions to the endpoint configured by `config`.
+    pub fn with_endpoint(mut self, config: RelayConfig) -> Result<Self> {
+        let url = config.url.clone();
+        let client = Client::from_config(self.relay_signer.clone(), config)?;
+        self.endpoints.push((url, client));
+        Ok(self)
+    }
+
+    /// Ask endpoints to share transactions with all builders, for faster inclusion.
+    pub fn with_fast_mode(mut self, fast: bool) -> Self {
+        self.fast = fast;
+        self
+    }
+}
+
+#[async_trait]
+impl<S: Signer + Clone + 'static> Executor<SubmitPrivateTx> for PrivateTxExecutor<S> {
+    /// Sign a transaction and send it to every endpoint. Succeeds if any endpoint
+    /// accepted it.
+    async fn execute(&self, action: SubmitPrivateTx) -> Result<()> {
+        let signature = self.tx_signer.sign_transaction(&action.tx).await?;
+        let request = PrivateTransactionRequest {
+            tx: action.tx.rlp_signed(&signature),
+            max_block_number: action.max_block_number,
+            preferences: self
+                .fast
+                .then_some(PrivateTransactionPreferences { fast: true }),
+        };
+
+        let responses = join_all(
+            self.endpoints
+                .iter()
+                .map(|(_, client)| client.send_private_transaction(&request)),
+        )
+        .await;
+
+        let mut accepted = false;
+        for ((url, _), response) in self.endpoints.iter().zip(responses) {
+            match response {
+                Ok(tx_hash) => {
+                    info!("private tx {:?} sent to {}", tx_hash, url);
+                    accepted = true;
+                }
+                Err(e) => error!("error sending private tx to {}: {}", url, e),
+            }
+        }
+        if !accepted {
+            return Err(anyhow!("No endpoint accepted the private transaction"));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<S: Signer + Clone + 'static> Executor<CancelPrivateTx> for PrivateTxExecutor<S> {
+    /// Cancel a private transaction on every endpoint. Succeeds if any endpoint
+    /// cancelled it.
+    async fn execute(&self, action: CancelPrivateTx) -> Result<()> {
+        let responses = join_all(
+            self.endpoints
+                .iter()
+                .map(|(_, client)| client.cancel_private_transaction(action.tx_hash)),
+        )
+        .await;
+
+        let mut cancelled = false;
+        for ((url, _), response) in self.endpoints.iter().zip(responses) {
+            match response {
+                Ok(true) => cancelled = true,
+                Ok(false) => info!("private tx {:?} not cancelled by {}", action.tx_hash, url),
+                Err(e) => error!("error cancelling private tx on {}: {}", url, e),
+            }
+        }
+        if !cancelled {
+            return Err(anyhow!(
+                "No endpoint cancelled private transaction {:?}",
+                action.tx_hash
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufWriter, Write},
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use ethers::{providers::Middleware, types::U64};
+use futures::future::BoxFuture;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::types::Executor;
+
+/// An executor that writes each action to a durable log, so that strategies can be run
+/// in shadow mode before being promoted. It can wrap a real executor, in which case
+/// actions are both executed and recorded, along with the outcome of the execution.
+pub struct RecordingExecutor<A> {
+    /// Shared with the blocking tasks writing the records.
+    sink: Arc<RecordSink>,
+    /// Executor the actions are forwarded to, if any.
+    executor: Option<Box<dyn Executor<A>>>,
+    /// Fetches the current block number, if configured.
+    block_number: Option<Box<dyn Fn() -> BoxFuture<'static, Result<U64>> + Send + Sync>>,
+}
+
+/// Where action records are written.
+enum RecordSink {
+    /// One JSON record per line.
+    Jsonl(Mutex<BufWriter<File>>),
+    /// One row per record in the `actions` table.
+    Sqlite(Mutex<Connection>),
+}
+
+/// A recorded action.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActionRecord {
+    /// Unique ID of the action, also attached to the logs of its execution.
+    pub correlation_id: String,
+    pub timestamp: DateTime<Utc>,
+    /// Block number when the action was received, if the executor fetches it.
+    pub block_number: Option<U64>,
+    pub action: serde_json::Value,
+    /// Whether the action was forwarded to a real executor.
+    pub executed: bool,
+    /// Error returned by the real executor, if any.
+    pub error: Option<String>,
+}
+
+impl<A> RecordingExecutor<A> {
+    /// Record actions to a JSONL file, appending to it if it exists.
+    pub fn jsonl(path: impl AsRef<Path>) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self::with_sink(RecordSink::Jsonl(Mutex::new(
+            BufWriter::new(file),
+        ))))
+    }
+
+    /// Record actions to the `actions` table of a SQLite database, creating it if needed.
+    pub fn sqlite(path: impl AsRef<Path>) -> Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS actions (
+                correlation_id TEXT PRIMARY KEY,
+                timestamp TEXT NOT NULL,
+                block_number INTEGER,
+                action TEXT NOT NULL,
+                executed INTEGER NOT NULL,
+                error TEXT
+            )",
+            [],
+        )?;
+        Ok(Self::with_sink(RecordSink::Sqlite(Mutex::new(connection))))
+    }
+
+    fn with_sink(sink: RecordSink) -> Self {
+        Self {
+            sink: Arc::new(sink),
+            executor: None,
+            block_number: None,
+        }
+    }
+
+    /// Forward actions to `executor`, recording them along with the outcome.
+    pub fn with_executor(mut self, executor: Box<dyn Executor<A>>) -> Self {
+        self.executor = Some(executor);
+        self
+    }
+
+    /// Record the block number at which each action is received.
+    pub fn with_block_numbers<M>(mut self, client: Arc<M>) -> Self
+    where
+        M: Middleware + 'static,
+        M::Error: 'static,
+    {
+        self.block_number = Some(Box::new(move || {
+            let client = client.clone();
+            Box::pin(async move { Ok(client.get_block_number().await?) })
+        }));
+        self
+    }
+}
+
+impl RecordSink {
+    /// Write a record. This blocks on file or database I/O, so it must not run on the
+    /// async runtime.
+    fn write(&self, record: &ActionRecord) -> Result<()> {
+        match self {
+            Self::Jsonl(writer) => {
+                let mut writer = writer.lock().unwrap();
+                serde_json::to_writer(&mut *writer, record)?;
+                writer.write_all(b"\n")?;
+                writer.flush()?;
+            }
+            Self::Sqlite(connection) => {
+                connection.lock().unwrap().execute(
+                    "INSERT INTO actions
+                        (correlation_id, timestamp, block_number, action, executed, error)
+                        VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        record.correlation_id,
+                        record.timestamp.to_rfc3339(),
+                        record
+                            .block_number
+                            .map(|block_number| block_number.as_u64()),
+                        record.action.to_string(),
+                        record.executed,
+                        record.error,
+                    ],
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<A> Executor<A> for RecordingExecutor<A>
+where
+    A: Serialize + Send + Sync + 'static,
+{
+    /// Record an action, executing it first if a real executor is configured.
+    async fn execute(&self, action: A) -> Result<()> {
+        let correlation_id = Uuid::new_v4().to_string();
+        let timestamp = Utc::now();
+        let block_number = match &self.block_number {
+            Some(block_number) => match block_number().await {
+                Ok(block_number) => Some(block_number),
+                Err(e) => {
+                    error!("error fetching block number: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let value = serde_json::to_value(&action)?;
+
+        let (executed, result) = match &self.executor {
+            Some(executor) => {
+                info!("executing action {}", correlation_id);
+                (true, executor.execute(action).await)
+            }
+            None => (false, Ok(())),
+        };
+
+        let record = ActionRecord {
+            correlation_id,
+            timestamp,
+            block_number,
+            action: value,
+            executed,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        };
+        let sink = self.sink.clone();
+        tokio::task::spawn_blocking(move || sink.write(&record)).await??;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executors::mempool_executor::{GasBidInfo, SubmitTxToMempool};
+    use anyhow::anyhow;
+    use ethers::types::TransactionRequest;
+
+    #[derive(Serialize)]
+    struct Action {
+        amount: u64,
+    }
+
+    struct FailingExecutor;
+
+    #[async_trait]
+    impl Executor<Action> for FailingExecutor {
+        async fn execute(&self, _action: Action) -> Result<()> {
+            Err(anyhow!("relay unavailable"))
+        }
+    }
+
+    #[tokio::test]
+    async fn records_to_jsonl() {
+        let path = std::env::temp_dir().join(format!("actions-{}.jsonl", Uuid::new_v4()));
+        let executor = RecordingExecutor::jsonl(&path).unwrap();
+        executor.execute(Action { amount: 1 }).await.unwrap();
+        let executor = executor.with_executor(Box::new(FailingExecutor));
+        assert!(executor.execute(Action { amount: 2 }).await.is_err());
+
+        let records: Vec<ActionRecord> = std::fs::read_to_string(&path)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].action, serde_json::json!({ "amount": 1 }));
+        assert!(!records[0].executed);
+        assert!(records[1].executed);
+        assert_eq!(records[1].error.as_deref(), Some("relay unavailable"));
+        assert_ne!(records[0].correlation_id, records[1].correlation_id);
+    }
+
+    #[tokio::test]
+    async fn records_mempool_txs() {
+        let path = std::env::temp_dir().join(format!("actions-{}.jsonl", Uuid::new_v4()));
+        let executor = RecordingExecutor::jsonl(&path).unwrap();
+        let action = SubmitTxToMempool {
+            tx: TransactionRequest::new().value(1).into(),
+            gas_bid_info: Some(GasBidInfo {
+                total_profit: 100.into(),
+                bid_percentage: 50,
+            }),
+            deadline: None,
+        };
+        executor.execute(action).await.unwrap();
+
+        let record: ActionRecord =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(record.action["tx"]["value"], "0x1");
+        assert_eq!(record.action["gas_bid_info"]["bid_percentage"], 50);
+    }
+
+    #[tokio::test]
+    async fn records_to_sqlite() {
+        let path = std::env::temp_dir().join(format!("actions-{}.db", Uuid::new_v4()));
+        let executor = RecordingExecutor::sqlite(&path).unwrap();
+        executor.execute(Action { amount: 1 }).await.unwrap();
+        executor.execute(Action { amount: 2 }).await.unwrap();
+
+        let connection = Connection::open(&path).unwrap();
+        let actions: Vec<String> = connection
+            .prepare("SELECT action FROM actions ORDER BY timestamp")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(actions, vec![r#"{"amount":1}"#, r#"{"amount":2}"#]);
+    }
+}
@@ -0,0 +1,469 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, U256, U64},
+};
+use futures::future::BoxFuture;
+use tracing::error;
+
+use crate::{
+    executors::{flashbots_executor::FlashbotsBundle, mempool_executor::SubmitTxToMempool},
+    types::Executor,
+};
+
+/// Window of the per minute action limit.
+const MINUTE: Duration = Duration::from_secs(60);
+
+/// An executor that wraps another executor and enforces risk limits on the actions it
+/// forwards. When a limit trips, the kill switch is engaged: every action is rejected
+/// until the executor is [reset](RiskManagerExecutor::reset) manually.
+pub struct RiskManagerExecutor<A> {
+    executor: Box<dyn Executor<A>>,
+    limits: RiskLimits,
+    state: Mutex<RiskState>,
+    /// Fetches the current block number and the wallet balance, if configured.
+    wallet: Option<WalletFetcher>,
+}
+
+/// Fetches the current block number and the wallet balance.
+type WalletFetcher = Box<dyn Fn() -> BoxFuture<'static, Result<(U64, U256)>> + Send + Sync>;
+
+/// Limits enforced by a [RiskManagerExecutor](RiskManagerExecutor). Unset limits are
+/// not enforced.
+#[derive(Debug, Clone, Default)]
+pub struct RiskLimits {
+    /// Maximum number of actions in a single block. Requires a wallet to be set.
+    pub max_actions_per_block: Option<u32>,
+    /// Maximum number of actions in any 60 seconds.
+    pub max_actions_per_minute: Option<u32>,
+    /// Maximum value sent per UTC day.
+    pub max_value_per_day: Option<U256>,
+    /// Maximum gas spend per UTC day. Actions whose gas spend is unbounded are rejected.
+    pub max_gas_spend_per_day: Option<U256>,
+    /// Maximum number of failed executions in a row.
+    pub max_consecutive_failures: Option<u32>,
+    /// Minimum wallet balance to keep executing. Requires a wallet to be set.
+    pub min_balance: Option<U256>,
+}
+
+/// Maximum value and gas an action can spend.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ActionCost {
+    pub value: U256,
+    /// `U256::MAX` if the gas spend can't be bounded, e.g. for a transaction without a
+    /// gas limit or fee. Such actions are rejected when the gas spend is limited.
+    pub gas: U256,
+}
+
+impl ActionCost {
+    fn add(self, other: ActionCost) -> ActionCost {
+        ActionCost {
+            value: self.value.saturating_add(other.value),
+            gas: self.gas.saturating_add(other.gas),
+        }
+    }
+}
+
+/// Actions whose cost can be bounded before they are executed.
+pub trait Cost {
+    fn cost(&self) -> ActionCost;
+}
+
+/// Gas spend of a transaction, bounded by its gas limit and max fee if it sets both.
+fn tx_gas_spend(tx: &TypedTransaction) -> Option<U256> {
+    let fee = match tx {
+        TypedTransaction::Eip1559(tx) => tx.max_fee_per_gas,
+        _ => tx.gas_price(),
+    };
+    tx.gas()?.checked_mul(fee?)
+}
+
+/// Cost of a transaction, which is unbounded if it doesn't set its gas limit and fee.
+fn tx_cost(tx: &TypedTransaction) -> ActionCost {
+    ActionCost {
+        value: tx.value().copied().unwrap_or_default(),
+        gas: tx_gas_spend(tx).unwrap_or(U256::MAX),
+    }
+}
+
+impl Cost for SubmitTxToMempool {
+    /// A gas bid can spend up to its share of the total profit, whatever the gas price,
+    /// which bounds transactions that are priced by the executor.
+    fn cost(&self) -> ActionCost {
+        let gas = tx_gas_spend(&self.tx);
+        let bid = self
+            .gas_bid_info
+            .as_ref()
+            .map(|gas_bid_info| gas_bid_info.total_profit * gas_bid_info.bid_percentage / 100);
+        ActionCost {
+            value: self.tx.value().copied().unwrap_or_default(),
+            gas: match (gas, bid) {
+                (Some(gas), Some(bid)) => gas.max(bid),
+                (gas, bid) => gas.or(bid).unwrap_or(U256::MAX),
+            },
+        }
+    }
+}
+
+impl Cost for FlashbotsBundle {
+    fn cost(&self) -> ActionCost {
+        self.iter()
+            .map(tx_cost)
+            .fold(ActionCost::default(), ActionCost::add)
+    }
+}
+
+/// Usage tracked against the limits.
+#[derive(Debug, Default)]
+struct RiskState {
+    /// Reason the kill switch was engaged, if it was.
+    tripped: Option<String>,
+    /// Current block, and the number of actions in it.
+    block_actions: (U64, u32),
+    /// Times of the actions in the last minute.
+    recent_actions: VecDeque<Instant>,
+    /// Current UTC day, and the spend during it.
+    daily_spend: Option<(NaiveDate, ActionCost)>,
+    consecutive_failures: u32,
+}
+
+impl RiskState {
+    fn trip(&mut self, reason: String) -> anyhow::Error {
+        error!("risk limit tripped, stopping execution: {}", reason);
+        let error = anyhow!("risk limit tripped: {}", reason);
+        self.tripped = Some(reason);
+        error
+    }
+
+    /// Count an action against the limits, or engage the kill switch if it would
+    /// exceed one of them.
+    fn admit(
+        &mut self,
+        limits: &RiskLimits,
+        cost: ActionCost,
+        wallet: Option<(U64, U256)>,
+        now: Instant,
+        today: NaiveDate,
+    ) -> Result<()> {
+        if let Some(reason) = &self.tripped {
+            return Err(anyhow!("risk limit tripped: {}", reason));
+        }
+
+        if let Some((block_number, balance)) = wallet {
+            if let Some(min_balance) = limits.min_balance {
+                if balance < min_balance {
+                    return Err(self.trip(format!(
+                        "wallet balance {} is below {}",
+                        balance, min_balance
+                    )));
+                }
+            }
+            if self.block_actions.0 != block_number {
+                self.block_actions = (block_number, 0);
+            }
+            if let Some(max) = limits.max_actions_per_block {
+                if self.block_actions.1 >= max {
+                    return Err(self.trip(format!(
+                        "more than {} actions in block {}",
+                        max, block_number
+                    )));
+                }
+            }
+        }
+
+        while let Some(time) = self.recent_actions.front() {
+            if now.duration_since(*time) < MINUTE {
+                break;
+            }
+            self.recent_actions.pop_front();
+        }
+        if let Some(max) = limits.max_actions_per_minute {
+            if self.recent_actions.len() >= max as usize {
+                return Err(self.trip(format!("more than {} actions in a minute", max)));
+            }
+        }
+
+        if limits.max_gas_spend_per_day.is_some() && cost.gas == U256::MAX {
+            return Err(anyhow!(
+                "gas spend of action is unbounded, set its gas limit and fees"
+            ));
+        }
+        let spent = match self.daily_spend {
+            Some((day, spent)) if day == today => spent,
+            _ => ActionCost::default(),
+        };
+        let ActionCost { value, gas } = spent.add(cost);
+        if let Some(max) = limits.max_value_per_day {
+            if value > max {
+                return Err(self.trip(format!("daily value spend {} would exceed {}", value, max)));
+            }
+        }
+        if let Some(max) = limits.max_gas_spend_per_day {
+            if gas > max {
+                return Err(self.trip(format!("daily gas spend {} would exceed {}", gas, max)));
+            }
+        }
+
+        self.block_actions.1 += 1;
+        self.recent_actions.push_back(now);
+        self.daily_spend = Some((today, ActionCost { value, gas }));
+        Ok(())
+    }
+
+    fn record_outcome(&mut self, limits: &RiskLimits, succeeded: bool) {
+        if succeeded {
+            self.consecutive_failures = 0;
+            return;
+        }
+        self.consecutive_failures += 1;
+        if let Some(max) = limits.max_consecutive_failures {
+            if self.consecutive_failures >= max {
+                self.trip(format!(
+                    "{} consecutive failures",
+                    self.consecutive_failures
+                ));
+            }
+        }
+    }
+}
+
+impl<A> RiskManagerExecutor<A> {
+    pub fn new(executor: Box<dyn Executor<A>>, limits: RiskLimits) -> Self {
+        Self {
+            executor,
+            limits,
+            state: Mutex::new(RiskState::default()),
+            wallet: None,
+        }
+    }
+
+    /// Watch the balance of `wallet`, and the current block, for the block and balance
+    /// limits.
+    pub fn with_wallet<M>(mut self, client: Arc<M>, wallet: Address) -> Self
+    where
+        M: Middleware + 'static,
+        M::Error: 'static,
+    {
+        self.wallet = Some(Box::new(move || {
+            let client = client.clone();
+            Box::pin(async move {
+                let block_number = client.get_block_number().await?;
+                let balance = client
+                    .get_balance(wallet, Some(block_number.into()))
+                    .await?;
+                Ok((block_number, balance))
+            })
+        }));
+        self
+    }
+
+    /// Reason the kill switch was engaged, if it was.
+    pub fn tripped(&self) -> Option<String> {
+        self.state.lock().unwrap().tripped.clone()
+    }
+
+    /// Disengage the kill switch and resume execution. Usage counted against the
+    /// limits is kept, apart from consecutive failures.
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.tripped = None;
+        state.consecutive_failures = 0;
+    }
+}
+
+#[async_trait]
+impl<A> Executor<A> for RiskManagerExecutor<A>
+where
+    A: Cost + Send + Sync + 'static,
+{
+    /// Execute an action with the wrapped executor, if it is within the risk limits.
+    async fn execute(&self, action: A) -> Result<()> {
+        let wallet = match &self.wallet {
+            Some(wallet) if self.tripped().is_none() => Some(wallet().await?),
+            _ => None,
+        };
+        self.state.lock().unwrap().admit(
+            &self.limits,
+            action.cost(),
+            wallet,
+            Instant::now(),
+            Utc::now().date_naive(),
+        )?;
+
+        let result = self.executor.execute(action).await;
+        self.state
+            .lock()
+            .unwrap()
+            .record_outcome(&self.limits, result.is_ok());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::TransactionRequest;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct CountingExecutor {
+        calls: Arc<AtomicU32>,
+        fail: bool,
+    }
+
+    #[async_trait]
+    impl Executor<FlashbotsBundle> for CountingExecutor {
+        async fn execute(&self, _action: FlashbotsBundle) -> Result<()> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if self.fail {
+                return Err(anyhow!("bundle rejected"));
+            }
+            Ok(())
+        }
+    }
+
+    fn risk_manager(
+        fail: bool,
+        limits: RiskLimits,
+    ) -> (RiskManagerExecutor<FlashbotsBundle>, Arc<AtomicU32>) {
+        let calls = Arc::new(AtomicU32::new(0));
+        let executor = CountingExecutor {
+            calls: calls.clone(),
+            fail,
+        };
+        (RiskManagerExecutor::new(Box::new(executor), limits), calls)
+    }
+
+    fn bundle(value: u64) -> FlashbotsBundle {
+        vec![TransactionRequest::new()
+            .value(value)
+            .gas(21_000)
+            .gas_price(10)
+            .into()]
+    }
+
+    #[test]
+    fn bounds_gas_bids() {
+        let action = SubmitTxToMempool {
+            tx: TransactionRequest::new().value(5).into(),
+            gas_bid_info: Some(crate::executors::mempool_executor::GasBidInfo {
+                total_profit: 1_000.into(),
+                bid_percentage: 100,
+            }),
+            deadline: None,
+        };
+        assert_eq!(
+            action.cost(),
+            ActionCost {
+                value: 5.into(),
+                gas: 1_000.into()
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn rejects_unpriced_txs_when_gas_spend_is_limited() {
+        let unpriced = || vec![TransactionRequest::new().value(1).gas(21_000).into()];
+        assert_eq!(unpriced().cost().gas, U256::MAX);
+
+        let (unlimited, _) = risk_manager(false, RiskLimits::default());
+        unlimited.execute(unpriced()).await.unwrap();
+
+        let limits = RiskLimits {
+            max_gas_spend_per_day: Some(1_000_000.into()),
+            ..Default::default()
+        };
+        let (limited, calls) = risk_manager(false, limits);
+        assert!(limited.execute(unpriced()).await.is_err());
+        // Rejected without engaging the kill switch.
+        assert!(limited.tripped().is_none());
+        limited.execute(bundle(1)).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        // Without a gas bid, a mempool transaction left for the executor to price is
+        // unbounded too.
+        let action = SubmitTxToMempool {
+            tx: TransactionRequest::new().into(),
+            gas_bid_info: None,
+            deadline: None,
+        };
+        assert_eq!(action.cost().gas, U256::MAX);
+    }
+
+    #[tokio::test]
+    async fn trips_on_daily_value() {
+        let limits = RiskLimits {
+            max_value_per_day: Some(100.into()),
+            ..Default::default()
+        };
+        let (risk_manager, calls) = risk_manager(false, limits);
+        risk_manager.execute(bundle(60)).await.unwrap();
+        assert!(risk_manager.execute(bundle(60)).await.is_err());
+        // Stays tripped, even for actions within the limits.
+        assert!(risk_manager.execute(bundle(1)).await.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(risk_manager.tripped().unwrap().contains("value"));
+
+        risk_manager.reset();
+        risk_manager.execute(bundle(1)).await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn trips_on_consecutive_failures() {
+        let limits = RiskLimits {
+            max_consecutive_failures: Some(2),
+            ..Default::default()
+        };
+        let (risk_manager, calls) = risk_manager(true, limits);
+        for _ in 0..3 {
+            assert!(risk_manager.execute(bundle(0)).await.is_err());
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(risk_manager.tripped().is_some());
+    }
+
+    #[test]
+    fn limits_actions_per_block_and_minute() {
+        let limits = RiskLimits {
+            max_actions_per_block: Some(2),
+            max_actions_per_minute: Some(3),
+            ..Default::default()
+        };
+        let today = Utc::now().date_naive();
+        let start = Instant::now();
+        let wallet = |block: u64| Some((U64::from(block), U256::zero()));
+
+        let mut state = RiskState::default();
+        let cost = ActionCost::default();
+        state.admit(&limits, cost, wallet(1), start, today).unwrap();
+        state.admit(&limits, cost, wallet(1), start, today).unwrap();
+        assert!(state.admit(&limits, cost, wallet(1), start, today).is_err());
+
+        let mut state = RiskState::default();
+        for block in 1..=3 {
+            state
+                .admit(&limits, cost, wallet(block), start, today)
+                .unwrap();
+        }
+        assert!(state.admit(&limits, cost, wallet(4), start, today).is_err());
+
+        // Actions older than a minute no longer count.
+        let mut state = RiskState::default();
+        for block in 1..=3 {
+            state
+                .admit(&limits, cost, wallet(block), start, today)
+                .unwrap();
+        }
+        state
+            .admit(&limits, cost, wallet(4), start + MINUTE, today)
+            .unwrap();
+    }
+}
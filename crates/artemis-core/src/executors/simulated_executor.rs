@@ -0,0 +1,352 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, BlockNumber, H256, I256, U256, U64},
+};
+use serde_json::json;
+use tracing::info;
+
+use crate::{
+    executors::{flashbots_executor::FlashbotsBundle, mempool_executor::SubmitTxToMempool},
+    types::Executor,
+};
+
+/// An executor that never sends anything on chain. Actions are executed against a
+/// local Anvil fork instead, and their outcome is recorded in a [PnL report](PnlReport),
+/// so that strategies can be paper traded against live data.
+///
+/// Transactions are sent from their `from` address, or the bot address, by impersonating
+/// it, so no keys are needed. The fork is snapshotted before each action and reverted
+/// after it, so that every action runs against the chain state and not against the
+/// effects of earlier simulated actions.
+pub struct SimulatedExecutor<M> {
+    /// Client to the Anvil fork.
+    fork: Arc<M>,
+    /// The address whose balance change is the profit of an action.
+    bot: Address,
+    /// Upstream RPC url. When set, the fork is reset to the latest upstream block before
+    /// each action.
+    fork_url: Option<String>,
+    report: Arc<Mutex<PnlReport>>,
+}
+
+/// Outcome of a simulated action.
+#[derive(Debug, Clone)]
+pub struct SimulationRecord {
+    /// Block the action was simulated on top of.
+    pub block_number: U64,
+    /// Hashes of the transactions mined on the fork.
+    pub tx_hashes: Vec<H256>,
+    /// Gas used by the action.
+    pub gas_used: U256,
+    /// Revert reason, if the action reverted. Reverted actions are not mined, as
+    /// bundles which revert don't land and mempool transactions which revert fail gas
+    /// estimation.
+    pub revert_reason: Option<String>,
+    /// Change of the balance of the bot address, gas costs included.
+    pub balance_change: I256,
+}
+
+/// Report of the simulated actions.
+#[derive(Debug, Clone, Default)]
+pub struct PnlReport {
+    pub records: Vec<SimulationRecord>,
+}
+
+impl PnlReport {
+    /// Sum of the balance changes of the bot address.
+    pub fn total_pnl(&self) -> I256 {
+        self.records
+            .iter()
+            .fold(I256::zero(), |total, record| total + record.balance_change)
+    }
+
+    /// Sum of the gas used by all actions.
+    pub fn total_gas_used(&self) -> U256 {
+        self.records
+            .iter()
+            .fold(U256::zero(), |total, record| total + record.gas_used)
+    }
+
+    /// Number of actions which reverted.
+    pub fn reverted(&self) -> usize {
+        self.records
+            .iter()
+            .filter(|record| record.revert_reason.is_some())
+            .count()
+    }
+}
+
+impl fmt::Display for PnlReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} actions, {} reverted, {} gas used, pnl {} wei",
+            self.records.len(),
+            self.reverted(),
+            self.total_gas_used(),
+            self.total_pnl()
+        )?;
+        for record in &self.records {
+            match &record.revert_reason {
+                Some(reason) => writeln!(f, "block {}: reverted: {}", record.block_number, reason)?,
+                None => writeln!(
+                    f,
+                    "block {}: {} gas used, pnl {} wei",
+                    record.block_number, record.gas_used, record.balance_change
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<M: Middleware> SimulatedExecutor<M> {
+    pub fn new(fork: Arc<M>, bot: Address) -> Self {
+        Self {
+            fork,
+            bot,
+            fork_url: None,
+            report: Arc::new(Mutex::new(PnlReport::default())),
+        }
+    }
+
+    /// Reset the fork to the latest block of `fork_url` before each action.
+    pub fn with_fork_url(mut self, fork_url: impl Into<String>) -> Self {
+        self.fork_url = Some(fork_url.into());
+        self
+    }
+
+    /// Report of the actions simulated so far.
+    pub fn report(&self) -> PnlReport {
+        self.report.lock().unwrap().clone()
+    }
+}
+
+impl<M> SimulatedExecutor<M>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    /// Simulate transactions mined together in the next block, and record the outcome.
+    pub async fn simulate(&self, txs: Vec<TypedTransaction>) -> Result<SimulationRecord> {
+        let provider = self.fork.provider();
+        if let Some(fork_url) = &self.fork_url {
+            provider
+                .request::<_, ()>(
+                    "anvil_reset",
+                    [json!({ "forking": { "jsonRpcUrl": fork_url } })],
+                )
+                .await?;
+        }
+        let snapshot: U256 = provider.request("evm_snapshot", ()).await?;
+        let record = self.mine(txs).await;
+        provider
+            .request::<_, bool>("evm_revert", [snapshot])
+            .await?;
+
+        let record = record?;
+        info!(
+            "simulated action at block {}: gas used {}, pnl {}, revert {:?}",
+            record.block_number, record.gas_used, record.balance_change, record.revert_reason
+        );
+        self.report.lock().unwrap().records.push(record.clone());
+        Ok(record)
+    }
+
+    async fn mine(&self, txs: Vec<TypedTransaction>) -> Result<SimulationRecord> {
+        let provider = self.fork.provider();
+        let block_number = self.fork.get_block_number().await?;
+        let balance_before = self.fork.get_balance(self.bot, None).await?;
+        let mut record = SimulationRecord {
+            block_number,
+            tx_hashes: vec![],
+            gas_used: U256::zero(),
+            revert_reason: None,
+            balance_change: I256::zero(),
+        };
+
+        // Queue the transactions without mining them, so they land in the same block.
+        let automine: bool = provider.request("anvil_getAutomine", ()).await?;
+        provider
+            .request::<_, ()>("evm_setAutomine", [false])
+            .await?;
+        let queued = self.queue(txs, &mut record).await;
+        let mined = if queued.is_ok() && record.revert_reason.is_none() {
+            provider
+                .request::<_, String>("evm_mine", ())
+                .await
+                .map(|_| ())
+        } else {
+            Ok(())
+        };
+        // Restore automine even if mining failed, so later simulations aren't affected.
+        provider
+            .request::<_, ()>("evm_setAutomine", [automine])
+            .await?;
+        queued?;
+        mined?;
+        if record.revert_reason.is_some() {
+            // The bundle doesn't land, so drop the transactions queued before the revert.
+            for tx_hash in record.tx_hashes.drain(..) {
+                provider
+                    .request::<_, Option<H256>>("anvil_dropTransaction", [tx_hash])
+                    .await?;
+            }
+            return Ok(record);
+        }
+
+        for tx_hash in &record.tx_hashes {
+            let receipt = self
+                .fork
+                .get_transaction_receipt(*tx_hash)
+                .await?
+                .ok_or_else(|| anyhow!("tx {:?} was not mined", tx_hash))?;
+            record.gas_used += receipt.gas_used.unwrap_or_default();
+            if receipt.status == Some(U64::zero()) {
+                record.revert_reason = Some(format!("tx {:?} reverted", tx_hash));
+            }
+        }
+        let balance_after = self.fork.get_balance(self.bot, None).await?;
+        record.balance_change = I256::from_raw(balance_after) - I256::from_raw(balance_before);
+        Ok(record)
+    }
+
+    async fn record_revert(&self, revert_reason: String) -> Result<()> {
+        let block_number = self.fork.get_block_number().await?;
+        self.report.lock().unwrap().records.push(SimulationRecord {
+            block_number,
+            tx_hashes: vec![],
+            gas_used: U256::zero(),
+            revert_reason: Some(revert_reason),
+            balance_change: I256::zero(),
+        });
+        Ok(())
+    }
+
+    /// Send each transaction to the fork, stopping at the first one which reverts.
+    async fn queue(&self, txs: Vec<TypedTransaction>, record: &mut SimulationRecord) -> Result<()> {
+        let provider = self.fork.provider();
+        for mut tx in txs {
+            let from = *tx.from().unwrap_or(&self.bot);
+            tx.set_from(from);
+            if let Err(e) = self.fork.call(&tx, Some(BlockNumber::Pending.into())).await {
+                record.revert_reason = Some(e.to_string());
+                return Ok(());
+            }
+            provider
+                .request::<_, ()>("anvil_impersonateAccount", [from])
+                .await?;
+            let tx_hash = self.fork.send_transaction(tx, None).await?.tx_hash();
+            provider
+                .request::<_, ()>("anvil_stopImpersonatingAccount", [from])
+                .await?;
+            record.tx_hashes.push(tx_hash);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<M> Executor<SubmitTxToMempool> for SimulatedExecutor<M>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    /// Simulate a mempool transaction. The gas bid, if any, is applied as a gas price.
+    async fn execute(&self, mut action: SubmitTxToMempool) -> Result<()> {
+        if let Some(gas_bid_info) = &action.gas_bid_info {
+            let gas_usage = match self.fork.estimate_gas(&action.tx, None).await {
+                Ok(gas_usage) => gas_usage,
+                Err(e) => {
+                    self.record_revert(e.to_string()).await?;
+                    return Ok(());
+                }
+            };
+            action.tx.set_gas(gas_usage);
+            action
+                .tx
+                .set_gas_price(gas_bid_info.bid_gas_price(gas_usage));
+        }
+        self.simulate(vec![action.tx]).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl<M> Executor<FlashbotsBundle> for SimulatedExecutor<M>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    /// Simulate a Flashbots bundle, with all transactions mined in the same block.
+    async fn execute(&self, action: FlashbotsBundle) -> Result<()> {
+        self.simulate(action).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::Provider;
+
+    #[tokio::test]
+    async fn restores_automine_when_mining_fails() {
+        let (provider, mock) = Provider::mocked();
+        let bot = Address::repeat_byte(1);
+        // Responses in reverse order: the snapshot, the block number, the balance, the
+        // automine setting, disabling automine, the failed mine, restoring automine,
+        // and the revert. Mining fails as its response isn't a block hash.
+        mock.push(true).unwrap();
+        mock.push(()).unwrap();
+        mock.push(0).unwrap();
+        mock.push(()).unwrap();
+        mock.push(true).unwrap();
+        mock.push(U256::exp10(18)).unwrap();
+        mock.push(U64::from(10)).unwrap();
+        mock.push(U256::one()).unwrap();
+
+        let executor = SimulatedExecutor::new(Arc::new(provider), bot);
+        assert!(executor.simulate(vec![]).await.is_err());
+
+        mock.assert_request("evm_snapshot", ()).unwrap();
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        mock.assert_request("eth_getBalance", [json!(bot), json!("latest")])
+            .unwrap();
+        mock.assert_request("anvil_getAutomine", ()).unwrap();
+        mock.assert_request("evm_setAutomine", [false]).unwrap();
+        mock.assert_request("evm_mine", ()).unwrap();
+        mock.assert_request("evm_setAutomine", [true]).unwrap();
+        mock.assert_request("evm_revert", [U256::one()]).unwrap();
+    }
+
+    #[test]
+    fn sums_report() {
+        let record =
+            |gas_used: u64, balance_change: i64, revert_reason: Option<&str>| SimulationRecord {
+                block_number: 1.into(),
+                tx_hashes: vec![],
+                gas_used: gas_used.into(),
+                revert_reason: revert_reason.map(String::from),
+                balance_change: balance_change.into(),
+            };
+        let report = PnlReport {
+            records: vec![
+                record(100_000, 5_000, None),
+                record(50_000, -2_000, None),
+                record(0, 0, Some("execution reverted")),
+            ],
+        };
+        assert_eq!(report.total_pnl(), I256::from(3_000));
+        assert_eq!(report.total_gas_used(), U256::from(150_000));
+        assert_eq!(report.reverted(), 1);
+        assert!(report.to_string().starts_with("3 actions, 1 reverted"));
+    }
+}
@@ -0,0 +1,84 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use ethers::types::U64;
+use uuid::Uuid;
+
+/// A bundle superseding the previous bundle sent for the same key, e.g. because a new
+/// block changed the opportunity it captures. Relays replace the previous bundle.
+#[derive(Debug, Clone)]
+pub struct SupersedeBundle<B> {
+    /// Identifies the opportunity the bundle captures.
+    pub key: String,
+    pub bundle: B,
+}
+
+/// Cancel the last bundle sent for a key, e.g. because the opportunity is gone.
+#[derive(Debug, Clone)]
+pub struct CancelBundle {
+    pub key: String,
+}
+
+/// Replacement IDs of the bundles sent for each key, with the last block the bundles
+/// target. Keys whose bundles can no longer land are [pruned](ReplacementUuids::prune).
+#[derive(Debug, Default)]
+pub struct ReplacementUuids {
+    uuids: Mutex<HashMap<String, (Uuid, U64)>>,
+}
+
+impl ReplacementUuids {
+    /// Replacement ID of the bundles sent for `key`, assigning a new one to new keys.
+    /// `last_block` is the last block the bundle about to be sent targets.
+    pub fn get_or_insert(&self, key: &str, last_block: U64) -> Uuid {
+        let mut uuids = self.uuids.lock().unwrap();
+        let (uuid, until) = uuids
+            .entry(key.to_string())
+            .or_insert_with(|| (Uuid::new_v4(), last_block));
+        *until = (*until).max(last_block);
+        *uuid
+    }
+
+    /// Forget the replacement ID of the bundles sent for `key`, returning it if any.
+    pub fn remove(&self, key: &str) -> Option<Uuid> {
+        self.uuids.lock().unwrap().remove(key).map(|(uuid, _)| uuid)
+    }
+
+    /// Forget the keys whose bundles all target blocks before `block_number`.
+    pub fn prune(&self, block_number: U64) {
+        self.uuids
+            .lock()
+            .unwrap()
+            .retain(|_, (_, last_block)| *last_block >= block_number);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_uuids_per_key() {
+        let uuids = ReplacementUuids::default();
+        let first = uuids.get_or_insert("weth-usdc", 10.into());
+        assert_eq!(uuids.get_or_insert("weth-usdc", 10.into()), first);
+        assert_ne!(uuids.get_or_insert("weth-dai", 10.into()), first);
+
+        assert_eq!(uuids.remove("weth-usdc"), Some(first));
+        assert_eq!(uuids.remove("weth-usdc"), None);
+        assert_ne!(uuids.get_or_insert("weth-usdc", 10.into()), first);
+    }
+
+    #[test]
+    fn prunes_passed_bundles() {
+        let uuids = ReplacementUuids::default();
+        let expiring = uuids.get_or_insert("weth-usdc", 10.into());
+        let superseded = uuids.get_or_insert("weth-dai", 10.into());
+        // The last bundle sent for the key targets a later block.
+        assert_eq!(uuids.get_or_insert("weth-dai", 12.into()), superseded);
+
+        uuids.prune(10.into());
+        assert_eq!(uuids.uuids.lock().unwrap().len(), 2);
+        uuids.prune(11.into());
+        assert_ne!(uuids.get_or_insert("weth-usdc", 11.into()), expiring);
+        assert_eq!(uuids.remove("weth-dai"), Some(superseded));
+    }
+}
@@ -0,0 +1,381 @@
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Transaction, U256},
+};
+
+use crate::{executors::mempool_executor::GasBidInfo, utilities::txpool::pending_transactions};
+
+/// Everything a [GasBidder](GasBidder) knows about the transaction it bids for.
+#[derive(Debug, Clone)]
+pub struct BidRequest<'a> {
+    pub tx: &'a TypedTransaction,
+    /// Estimated gas used by the transaction.
+    pub gas_usage: U256,
+    /// Predicted base fee of the block the transaction targets.
+    pub base_fee: U256,
+    /// Total profit expected from the opportunity, if known.
+    pub total_profit: Option<U256>,
+}
+
+/// A gas bid. Legacy transactions use the max fee as their gas price.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasBid {
+    pub max_fee_per_gas: U256,
+    pub max_priority_fee_per_gas: U256,
+    /// Amount to pay the block builder directly, for bundles which pay through a
+    /// coinbase transfer instead of gas. The strategy is responsible for including
+    /// the transfer in its transactions.
+    pub coinbase_transfer: U256,
+}
+
+impl GasBid {
+    /// Most the bid can cost, if the full max fee is paid.
+    pub fn max_cost(&self, gas_usage: U256) -> U256 {
+        gas_usage * self.max_fee_per_gas + self.coinbase_transfer
+    }
+
+    /// Fail if paying the bid would leave less than `min_retained_profit` of the profit.
+    pub fn ensure_retained_profit(
+        &self,
+        request: &BidRequest<'_>,
+        min_retained_profit: U256,
+    ) -> Result<()> {
+        let Some(total_profit) = request.total_profit else {
+            return Ok(());
+        };
+        let cost = self.max_cost(request.gas_usage);
+        if total_profit < cost + min_retained_profit {
+            return Err(anyhow!(
+                "Bid costing {} leaves less than {} of the profit {}",
+                cost,
+                min_retained_profit,
+                total_profit
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Decides how much gas a transaction bids.
+#[async_trait]
+pub trait GasBidder: Send + Sync {
+    async fn bid(&self, request: &BidRequest<'_>) -> Result<GasBid>;
+}
+
+/// A bundle which the strategy builds once the executor's bidder has priced it, so that
+/// the strategy can pay the bid, including its coinbase transfer.
+pub struct PricedBundle<B> {
+    /// Transaction the bid is for, e.g. the one competing with other searchers.
+    pub tx: TypedTransaction,
+    /// Estimated gas used by the bundle.
+    pub gas_usage: U256,
+    /// Predicted base fee of the block the bundle targets.
+    pub base_fee: U256,
+    /// Total profit expected from the opportunity, if known.
+    pub total_profit: Option<U256>,
+    /// Builds the bundle paying the bid.
+    pub build: Box<dyn FnOnce(GasBid) -> Result<B> + Send>,
+}
+
+impl<B> PricedBundle<B> {
+    /// Price the bundle with `bidder`, and build it.
+    pub async fn build(self, bidder: &dyn GasBidder) -> Result<B> {
+        let request = BidRequest {
+            tx: &self.tx,
+            gas_usage: self.gas_usage,
+            base_fee: self.base_fee,
+            total_profit: self.total_profit,
+        };
+        let bid = bidder.bid(&request).await?;
+        (self.build)(bid)
+    }
+}
+
+/// Bids a fixed priority fee on top of the base fee.
+#[derive(Debug, Clone)]
+pub struct FixedPriorityFeeBidder {
+    pub priority_fee: U256,
+}
+
+#[async_trait]
+impl GasBidder for FixedPriorityFeeBidder {
+    async fn bid(&self, request: &BidRequest<'_>) -> Result<GasBid> {
+        Ok(GasBid {
+            max_fee_per_gas: request.base_fee + self.priority_fee,
+            max_priority_fee_per_gas: self.priority_fee,
+            coinbase_transfer: U256::zero(),
+        })
+    }
+}
+
+/// Bids a percentage of the total profit as gas, the priority fee being what is left
+/// on top of the base fee.
+#[derive(Debug, Clone)]
+pub struct ProfitPercentageBidder {
+    pub bid_percentage: u64,
+}
+
+#[async_trait]
+impl GasBidder for ProfitPercentageBidder {
+    async fn bid(&self, request: &BidRequest<'_>) -> Result<GasBid> {
+        let total_profit = request
+            .total_profit
+            .ok_or_else(|| anyhow!("Profit percentage bid without a total profit"))?;
+        let bid_gas_price = GasBidInfo {
+            total_profit,
+            bid_percentage: self.bid_percentage,
+        }
+        .bid_gas_price(request.gas_usage);
+        if bid_gas_price < request.base_fee {
+            return Err(anyhow!(
+                "Bid gas price {} is below the base fee {}",
+                bid_gas_price,
+                request.base_fee
+            ));
+        }
+        Ok(GasBid {
+            max_fee_per_gas: bid_gas_price,
+            max_priority_fee_per_gas: bid_gas_price - request.base_fee,
+            coinbase_transfer: U256::zero(),
+        })
+    }
+}
+
+/// Outbids the pending transactions sent by others to the same contract, by a
+/// percentage of the highest competing priority fee.
+pub struct CompetitiveBidder<M> {
+    client: Arc<M>,
+    /// Priority fee to bid when there is no competition.
+    pub min_priority_fee: U256,
+    /// Percentage to outbid the highest competing priority fee by.
+    pub outbid_percentage: u64,
+    /// Percentage of the total profit the bid can spend at most, if the profit is known.
+    pub max_bid_percentage: u64,
+}
+
+impl<M> CompetitiveBidder<M> {
+    pub fn new(client: Arc<M>, min_priority_fee: U256) -> Self {
+        Self {
+            client,
+            min_priority_fee,
+            outbid_percentage: 10,
+            max_bid_percentage: 90,
+        }
+    }
+
+    /// Set the percentage to outbid the highest competing priority fee by.
+    pub fn with_outbid_percentage(mut self, outbid_percentage: u64) -> Self {
+        self.outbid_percentage = outbid_percentage;
+        self
+    }
+
+    /// Set the percentage of the total profit the bid can spend at most.
+    pub fn with_max_bid_percentage(mut self, max_bid_percentage: u64) -> Self {
+        self.max_bid_percentage = max_bid_percentage;
+        self
+    }
+}
+
+/// Priority fee a pending transaction pays at a given base fee.
+fn priority_fee(tx: &Transaction, base_fee: U256) -> U256 {
+    match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+        (Some(max_fee), Some(max_priority_fee)) => {
+            max_priority_fee.min(max_fee.saturating_sub(base_fee))
+        }
+        _ => tx.gas_price.unwrap_or_default().saturating_sub(base_fee),
+    }
+}
+
+#[async_trait]
+impl<M> GasBidder for CompetitiveBidder<M>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    async fn bid(&self, request: &BidRequest<'_>) -> Result<GasBid> {
+        let to = request
+            .tx
+            .to_addr()
+            .copied()
+            .ok_or_else(|| anyhow!("Competitive bid for a transaction without a recipient"))?;
+        let from = request.tx.from().copied();
+        let pending = pending_transactions(self.client.as_ref()).await?;
+        let competing = pending
+            .iter()
+            .filter(|tx| tx.to == Some(to) && Some(tx.from) != from)
+            .map(|tx| priority_fee(tx, request.base_fee))
+            .max();
+
+        let priority_fee = match competing {
+            Some(competing) => {
+                (competing * (100 + self.outbid_percentage) / 100 + 1).max(self.min_priority_fee)
+            }
+            None => self.min_priority_fee,
+        };
+        let max_fee_per_gas = request.base_fee + priority_fee;
+        if let Some(total_profit) = request.total_profit {
+            let max_spend = total_profit * self.max_bid_percentage / 100;
+            if max_fee_per_gas * request.gas_usage > max_spend {
+                return Err(anyhow!(
+                    "Outbidding priority fee {} would spend more than {}",
+                    priority_fee,
+                    max_spend
+                ));
+            }
+        }
+        Ok(GasBid {
+            max_fee_per_gas,
+            max_priority_fee_per_gas: priority_fee,
+            coinbase_transfer: U256::zero(),
+        })
+    }
+}
+
+/// Bids for bundles through a coinbase transfer of a percentage of the profit, net of
+/// the gas paid at the base fee. Transactions pay no priority fee.
+#[derive(Debug, Clone)]
+pub struct CoinbaseTransferBidder {
+    pub bid_percentage: u64,
+}
+
+#[async_trait]
+impl GasBidder for CoinbaseTransferBidder {
+    async fn bid(&self, request: &BidRequest<'_>) -> Result<GasBid> {
+        let total_profit = request
+            .total_profit
+            .ok_or_else(|| anyhow!("Coinbase transfer bid without a total profit"))?;
+        let gas_cost = request.gas_usage * request.base_fee;
+        let bid = total_profit * self.bid_percentage / 100;
+        if bid < gas_cost {
+            return Err(anyhow!(
+                "Bid {} doesn't cover the gas cost {}",
+                bid,
+                gas_cost
+            ));
+        }
+        Ok(GasBid {
+            max_fee_per_gas: request.base_fee,
+            max_priority_fee_per_gas: U256::zero(),
+            coinbase_transfer: bid - gas_cost,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        providers::Provider,
+        types::{Address, TransactionRequest, TxpoolContent},
+    };
+
+    fn request(tx: &TypedTransaction, total_profit: Option<u64>) -> BidRequest<'_> {
+        BidRequest {
+            tx,
+            gas_usage: 100_000.into(),
+            base_fee: 10.into(),
+            total_profit: total_profit.map(U256::from),
+        }
+    }
+
+    #[tokio::test]
+    async fn bids_from_profit() {
+        let tx = TransactionRequest::new().into();
+
+        let bid = FixedPriorityFeeBidder {
+            priority_fee: 2.into(),
+        };
+        let bid = bid.bid(&request(&tx, None)).await.unwrap();
+        assert_eq!(
+            (bid.max_fee_per_gas, bid.max_priority_fee_per_gas),
+            (12.into(), 2.into())
+        );
+
+        // Half of a 4_000_000 profit over 100_000 gas is a gas price of 20.
+        let bidder = ProfitPercentageBidder { bid_percentage: 50 };
+        let bid = bidder.bid(&request(&tx, Some(4_000_000))).await.unwrap();
+        assert_eq!(
+            (bid.max_fee_per_gas, bid.max_priority_fee_per_gas),
+            (20.into(), 10.into())
+        );
+        assert!(bidder.bid(&request(&tx, Some(1_000_000))).await.is_err());
+
+        // Half of the profit, minus 1_000_000 paid at the base fee.
+        let bidder = CoinbaseTransferBidder { bid_percentage: 50 };
+        let bid = bidder.bid(&request(&tx, Some(4_000_000))).await.unwrap();
+        assert_eq!(bid.coinbase_transfer, 1_000_000.into());
+        assert_eq!(bid.max_cost(100_000.into()), 2_000_000.into());
+    }
+
+    #[test]
+    fn enforces_retained_profit() {
+        let tx = TransactionRequest::new().into();
+        let bid = GasBid {
+            max_fee_per_gas: 20.into(),
+            ..Default::default()
+        };
+        let request = request(&tx, Some(4_000_000));
+        assert!(bid
+            .ensure_retained_profit(&request, 2_000_000.into())
+            .is_ok());
+        assert!(bid
+            .ensure_retained_profit(&request, 2_000_001.into())
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn outbids_competing_txs() {
+        let (provider, mock) = Provider::mocked();
+        let contract = Address::repeat_byte(1);
+        let competing = Transaction {
+            from: Address::repeat_byte(3),
+            to: Some(contract),
+            max_fee_per_gas: Some(100.into()),
+            max_priority_fee_per_gas: Some(50.into()),
+            ..Default::default()
+        };
+        let unrelated = Transaction {
+            from: Address::repeat_byte(4),
+            to: Some(Address::repeat_byte(2)),
+            gas_price: Some(1_000.into()),
+            ..Default::default()
+        };
+        let mut txpool = TxpoolContent::default();
+        for tx in [competing, unrelated] {
+            txpool
+                .pending
+                .entry(tx.from)
+                .or_default()
+                .insert("0".to_string(), tx);
+        }
+        mock.push(txpool.clone()).unwrap();
+        mock.push(txpool).unwrap();
+
+        let bidder = CompetitiveBidder::new(Arc::new(provider), 1.into());
+        let tx = TransactionRequest::new().to(contract).into();
+        let bid = bidder.bid(&request(&tx, Some(100_000_000))).await.unwrap();
+        assert_eq!(bid.max_priority_fee_per_gas, 56.into());
+        assert_eq!(bid.max_fee_per_gas, 66.into());
+
+        // Outbidding would spend more than 90% of the profit.
+        assert!(bidder.bid(&request(&tx, Some(7_000_000))).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn builds_bundles_paying_the_bid() {
+        let bundle = PricedBundle {
+            tx: TransactionRequest::new().into(),
+            gas_usage: 100_000.into(),
+            base_fee: 10.into(),
+            total_profit: Some(4_000_000.into()),
+            build: Box::new(|bid: GasBid| Ok(bid.coinbase_transfer)),
+        };
+        let bidder = CoinbaseTransferBidder { bid_percentage: 50 };
+        let coinbase_transfer = bundle.build(&bidder).await.unwrap();
+        assert_eq!(coinbase_transfer, 1_000_000.into());
+    }
+}
@@ -0,0 +1,371 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use async_trait::async_trait;
+use ethers::{
+    providers::{Middleware, PubsubClient},
+    signers::Signer,
+    types::{H256, U64},
+};
+use ethers_flashbots::FlashbotsMiddleware;
+use futures::{stream, StreamExt};
+use tracing::{error, info};
+
+use crate::types::{Collector, CollectorStream};
+
+/// Tracks bundles after they are sent, and reports whether they landed once their
+/// target blocks have passed. Executors register the bundles they send through a
+/// [handle](InclusionTracker::handle), and the tracker is run as a
+/// [collector](Collector) of [bundle reports](BundleReport), so that strategies can
+/// react to outcomes.
+pub struct InclusionTracker<M> {
+    provider: Arc<M>,
+    handle: InclusionTrackerHandle,
+}
+
+/// A handle to register sent bundles with an [InclusionTracker](InclusionTracker).
+/// Clones share the tracked bundles and metrics.
+#[derive(Clone, Default)]
+pub struct InclusionTrackerHandle {
+    pending: Arc<Mutex<Vec<TrackedBundle>>>,
+    metrics: Arc<Mutex<HashMap<String, InclusionMetrics>>>,
+}
+
+impl InclusionTrackerHandle {
+    /// Start tracking a sent bundle.
+    pub fn track(&self, bundle: TrackedBundle) {
+        self.pending.lock().unwrap().push(bundle);
+    }
+
+    /// Number of bundles whose outcome isn't known yet.
+    pub fn pending(&self) -> usize {
+        self.pending.lock().unwrap().len()
+    }
+
+    /// Aggregate outcomes of each strategy, by name.
+    pub fn metrics(&self) -> HashMap<String, InclusionMetrics> {
+        self.metrics.lock().unwrap().clone()
+    }
+}
+
+/// A source of relay-side bundle stats, used to tell outbid bundles from bundles
+/// which were never considered by builders.
+#[async_trait]
+pub trait BundleStatsSource: Send + Sync {
+    /// Whether the relay forwarded the bundle to builders for `block_number`.
+    async fn sent_to_builders(&self, bundle_hash: H256, block_number: U64) -> Result<bool>;
+}
+
+#[async_trait]
+impl<M, S> BundleStatsSource for FlashbotsMiddleware<M, S>
+where
+    M: Middleware + 'static,
+    M::Error: 'static,
+    S: Signer + 'static,
+{
+    async fn sent_to_builders(&self, bundle_hash: H256, block_number: U64) -> Result<bool> {
+        let stats = self.get_bundle_stats(bundle_hash, block_number).await?;
+        Ok(stats.is_sent_to_miners)
+    }
+}
+
+/// A bundle sent by an executor.
+pub struct TrackedBundle {
+    /// Name of the strategy the bundle was sent for, used to aggregate metrics.
+    pub strategy: String,
+    /// Hash of the bundle returned by the relay, if any.
+    pub bundle_hash: Option<H256>,
+    /// Hashes of the transactions of the bundle which must land for it to count as included.
+    pub tx_hashes: Vec<H256>,
+    /// First block the bundle targets.
+    pub first_block: U64,
+    /// Last block the bundle targets.
+    pub last_block: U64,
+    /// Where to query bundle stats, if the relay supports it. Without stats, bundles
+    /// which don't land are reported as [missed](BundleOutcome::Missed).
+    pub stats: Option<Arc<dyn BundleStatsSource>>,
+}
+
+/// Outcome of a tracked bundle.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleOutcome {
+    /// The bundle landed in a block.
+    Included { block_number: U64 },
+    /// The bundle was sent to builders but didn't land in any of its target blocks.
+    Outbid,
+    /// The bundle didn't land, and there is no sign builders considered it.
+    Expired,
+    /// The bundle didn't land, and there are no stats to tell whether it was outbid.
+    Missed,
+}
+
+/// Report of the outcome of a tracked bundle.
+#[derive(Debug, Clone)]
+pub struct BundleReport {
+    pub strategy: String,
+    pub bundle_hash: Option<H256>,
+    pub tx_hashes: Vec<H256>,
+    pub outcome: BundleOutcome,
+}
+
+/// Aggregate bundle outcomes of a strategy.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InclusionMetrics {
+    pub included: u64,
+    pub outbid: u64,
+    pub expired: u64,
+    pub missed: u64,
+}
+
+impl InclusionMetrics {
+    /// Share of the bundles with an outcome which were included.
+    pub fn inclusion_rate(&self) -> Option<f64> {
+        let total = self.included + self.outbid + self.expired + self.missed;
+        (total > 0).then(|| self.included as f64 / total as f64)
+    }
+
+    /// Share of the bundles which didn't land that were outbid, among those whose
+    /// stats were checked.
+    pub fn outbid_rate(&self) -> Option<f64> {
+        let total = self.outbid + self.expired;
+        (total > 0).then(|| self.outbid as f64 / total as f64)
+    }
+
+    fn record(&mut self, outcome: &BundleOutcome) {
+        match outcome {
+            BundleOutcome::Included { .. } => self.included += 1,
+            BundleOutcome::Outbid => self.outbid += 1,
+            BundleOutcome::Expired => self.expired += 1,
+            BundleOutcome::Missed => self.missed += 1,
+        }
+    }
+}
+
+impl<M> InclusionTracker<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self {
+            provider,
+            handle: InclusionTrackerHandle::default(),
+        }
+    }
+
+    /// A handle for executors to register the bundles they send.
+    pub fn handle(&self) -> InclusionTrackerHandle {
+        self.handle.clone()
+    }
+}
+
+impl<M> InclusionTracker<M>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    /// Check tracked bundles against a new block, and report the bundles which
+    /// landed in it or whose target blocks have all passed.
+    pub async fn process_block(&self, block_number: U64) -> Result<Vec<BundleReport>> {
+        let landed: HashSet<H256> = self
+            .provider
+            .get_block(block_number)
+            .await?
+            .map(|block| block.transactions.into_iter().collect())
+            .unwrap_or_default();
+
+        let done: Vec<TrackedBundle> = {
+            let mut pending = self.handle.pending.lock().unwrap();
+            let (done, still_pending) = pending.drain(..).partition(|bundle| {
+                is_included(bundle, &landed) || bundle.last_block < block_number
+            });
+            *pending = still_pending;
+            done
+        };
+
+        let mut reports = vec![];
+        for bundle in done {
+            let outcome = if is_included(&bundle, &landed) {
+                BundleOutcome::Included { block_number }
+            } else {
+                self.missed_outcome(&bundle).await
+            };
+            self.handle
+                .metrics
+                .lock()
+                .unwrap()
+                .entry(bundle.strategy.clone())
+                .or_default()
+                .record(&outcome);
+            info!(
+                "bundle {:?} of {}: {:?}",
+                bundle.bundle_hash, bundle.strategy, outcome
+            );
+            reports.push(BundleReport {
+                strategy: bundle.strategy,
+                bundle_hash: bundle.bundle_hash,
+                tx_hashes: bundle.tx_hashes,
+                outcome,
+            });
+        }
+        Ok(reports)
+    }
+
+    /// Outcome of a bundle whose target blocks have passed without it being seen,
+    /// e.g. because blocks were skipped.
+    async fn missed_outcome(&self, bundle: &TrackedBundle) -> BundleOutcome {
+        if let Some(tx_hash) = bundle.tx_hashes.first() {
+            match self.provider.get_transaction_receipt(*tx_hash).await {
+                Ok(Some(receipt)) => {
+                    if let Some(block_number) = receipt.block_number {
+                        if block_number >= bundle.first_block && block_number <= bundle.last_block {
+                            return BundleOutcome::Included { block_number };
+                        }
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => error!("error fetching receipt of {:?}: {}", tx_hash, e),
+            }
+        }
+
+        let (Some(stats), Some(bundle_hash)) = (&bundle.stats, bundle.bundle_hash) else {
+            return BundleOutcome::Missed;
+        };
+        let mut block_number = bundle.first_block;
+        while block_number <= bundle.last_block {
+            match stats.sent_to_builders(bundle_hash, block_number).await {
+                Ok(true) => return BundleOutcome::Outbid,
+                Ok(false) => {}
+                Err(e) => error!("error fetching stats of bundle {:?}: {}", bundle_hash, e),
+            }
+            block_number += U64::one();
+        }
+        BundleOutcome::Expired
+    }
+}
+
+/// Bundles are atomic, so a bundle landed if all of its transactions did.
+fn is_included(bundle: &TrackedBundle, landed: &HashSet<H256>) -> bool {
+    !bundle.tx_hashes.is_empty() && bundle.tx_hashes.iter().all(|hash| landed.contains(hash))
+}
+
+/// Implementation of the [Collector](Collector) trait for the [InclusionTracker](InclusionTracker).
+/// This implementation uses the [PubsubClient](PubsubClient) to subscribe to new blocks.
+#[async_trait]
+impl<M> Collector<BundleReport> for InclusionTracker<M>
+where
+    M: Middleware,
+    M::Provider: PubsubClient,
+    M::Error: 'static,
+{
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, BundleReport>> {
+        let stream = self.provider.subscribe_blocks().await?;
+        let stream = stream
+            .filter_map(|block| async move { block.number })
+            .then(move |block_number| async move {
+                match self.process_block(block_number).await {
+                    Ok(reports) => reports,
+                    Err(e) => {
+                        error!("error tracking bundles at block {}: {}", block_number, e);
+                        vec![]
+                    }
+                }
+            })
+            .flat_map(stream::iter);
+        Ok(Box::pin(stream))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        providers::Provider,
+        types::{Block, TransactionReceipt},
+    };
+
+    fn bundle(strategy: &str, tx_hash: H256, first_block: u64, last_block: u64) -> TrackedBundle {
+        TrackedBundle {
+            strategy: strategy.to_string(),
+            bundle_hash: None,
+            tx_hashes: vec![tx_hash],
+            first_block: first_block.into(),
+            last_block: last_block.into(),
+            stats: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn reports_outcomes() {
+        let (provider, mock) = Provider::mocked();
+        let tracker = InclusionTracker::new(Arc::new(provider));
+        let handle = tracker.handle();
+        let landed = H256::repeat_byte(1);
+        let missed = H256::repeat_byte(2);
+        handle.track(bundle("arb", landed, 10, 11));
+        handle.track(bundle("arb", missed, 10, 10));
+
+        // Block 10 includes the first bundle, the second one can still land.
+        mock.push(Block::<H256> {
+            transactions: vec![landed],
+            ..Default::default()
+        })
+        .unwrap();
+        let reports = tracker.process_block(10.into()).await.unwrap();
+        assert_eq!(reports.len(), 1);
+        assert_eq!(
+            reports[0].outcome,
+            BundleOutcome::Included {
+                block_number: 10.into()
+            }
+        );
+        assert_eq!(handle.pending(), 1);
+
+        // The second bundle didn't land by block 11. Responses are popped in reverse.
+        mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+        mock.push(Block::<H256>::default()).unwrap();
+        let reports = tracker.process_block(11.into()).await.unwrap();
+        assert_eq!(reports[0].outcome, BundleOutcome::Missed);
+        assert_eq!(handle.pending(), 0);
+
+        let metrics = &handle.metrics()["arb"];
+        assert_eq!((metrics.included, metrics.missed), (1, 1));
+        assert_eq!(metrics.inclusion_rate(), Some(0.5));
+        // Without stats, the missed bundle can't count towards the outbid rate.
+        assert_eq!(metrics.outbid_rate(), None);
+    }
+
+    /// Stats reporting every bundle as sent to builders, or none of them.
+    struct SentToBuilders(bool);
+
+    #[async_trait]
+    impl BundleStatsSource for SentToBuilders {
+        async fn sent_to_builders(&self, _: H256, _: U64) -> Result<bool> {
+            Ok(self.0)
+        }
+    }
+
+    #[tokio::test]
+    async fn tells_outbid_bundles_from_stats() {
+        let (provider, mock) = Provider::mocked();
+        let tracker = InclusionTracker::new(Arc::new(provider));
+        let handle = tracker.handle();
+        for sent in [true, false] {
+            handle.track(TrackedBundle {
+                bundle_hash: Some(H256::random()),
+                stats: Some(Arc::new(SentToBuilders(sent))),
+                ..bundle("arb", H256::random(), 10, 10)
+            });
+        }
+
+        mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+        mock.push::<Option<TransactionReceipt>, _>(None).unwrap();
+        mock.push(Block::<H256>::default()).unwrap();
+        let reports = tracker.process_block(11.into()).await.unwrap();
+        let outcomes: Vec<_> = reports.into_iter().map(|report| report.outcome).collect();
+        assert_eq!(
+            outcomes,
+            vec![BundleOutcome::Outbid, BundleOutcome::Expired]
+        );
+        assert_eq!(handle.metrics()["arb"].outbid_rate(), Some(0.5));
+    }
+}
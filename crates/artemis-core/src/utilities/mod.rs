@@ -1,4 +1,19 @@
 //! Utilities for working with Artemis.
 
+/// This module implements keyed replacement and cancellation of bundles.
+pub mod bundle_replacement;
+
+/// This module implements pluggable gas bidding strategies.
+pub mod gas_bidder;
+
+/// This module implements a tracker reporting whether sent bundles landed.
+pub mod inclusion_tracker;
+
+/// This module implements a nonce manager shared across executors.
+pub mod nonce_manager;
+
 /// This module implements state overriding middleware.
 pub mod state_override_middleware;
+
+/// This module reads pending transactions from the node's mempool.
+pub mod txpool;
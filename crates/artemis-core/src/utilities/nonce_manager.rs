@@ -0,0 +1,324 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockNumber, U256, U64},
+};
+use tracing::warn;
+
+/// Tracks the nonces of each signer, so that several executors, or several actions
+/// in flight at once, never use the same nonce. Share it between executors with an
+/// [Arc](Arc).
+///
+/// Nonces are handed out as [reservations](NonceReservation). A reservation which is
+/// dropped without being [committed](NonceReservation::commit), e.g. because sending
+/// the transaction failed, releases its nonce so that it is reused by the next
+/// reservation.
+pub struct NonceManager<M> {
+    provider: Arc<M>,
+    signers: Arc<Mutex<HashMap<Address, SignerNonces>>>,
+}
+
+/// Nonce state of a single signer.
+#[derive(Debug, Default)]
+struct SignerNonces {
+    /// Next nonce to hand out once released nonces are used up.
+    next: U256,
+    /// Nonces held by live reservations.
+    reserved: BTreeSet<U256>,
+    /// Nonces of sent transactions, with the last block they can be included in, if any.
+    in_flight: BTreeMap<U256, Option<U64>>,
+    /// Nonces below `next` which were given back, and must be used first to avoid gaps.
+    released: BTreeSet<U256>,
+}
+
+impl SignerNonces {
+    fn reserve(&mut self) -> U256 {
+        let nonce = match self.released.pop_first() {
+            Some(nonce) => nonce,
+            None => {
+                while self.reserved.contains(&self.next) || self.in_flight.contains_key(&self.next)
+                {
+                    self.next += U256::one();
+                }
+                let nonce = self.next;
+                self.next += U256::one();
+                nonce
+            }
+        };
+        self.reserved.insert(nonce);
+        nonce
+    }
+
+    fn release(&mut self, nonce: U256) {
+        if !self.reserved.remove(&nonce) || nonce >= self.next {
+            return;
+        }
+        self.released.insert(nonce);
+        // Shrink back instead of leaving released nonces at the top.
+        while !self.next.is_zero() && self.released.remove(&(self.next - U256::one())) {
+            self.next -= U256::one();
+        }
+    }
+
+    fn commit(&mut self, nonce: U256, valid_until: Option<U64>) {
+        self.reserved.remove(&nonce);
+        self.in_flight.insert(nonce, valid_until);
+    }
+
+    /// Drop nonces which are confirmed or whose transactions can no longer be included.
+    fn forget_settled(&mut self, confirmed: U256, block_number: U64) {
+        self.in_flight.retain(|nonce, valid_until| {
+            let expired = matches!(valid_until, Some(valid_until) if *valid_until <= block_number);
+            *nonce >= confirmed && !expired
+        });
+        self.released.retain(|nonce| *nonce >= confirmed);
+    }
+
+    /// Drop settled nonces, and realign with the chain when nothing is outstanding.
+    fn reconcile(&mut self, confirmed: U256, pending: U256, block_number: U64) {
+        self.forget_settled(confirmed, block_number);
+        if pending > self.next {
+            warn!(
+                "nonce mismatch, resyncing from {} to {}",
+                self.next, pending
+            );
+            self.next = pending;
+            self.released.clear();
+        } else if self.in_flight.is_empty() && self.reserved.is_empty() {
+            self.next = pending;
+            self.released.clear();
+        }
+    }
+
+    /// Drop settled nonces, and restart from the chain's pending nonce. Nonces of
+    /// transactions which can still be included, such as bundles whose target block
+    /// hasn't passed, stay in flight so that they aren't handed out again.
+    fn resync(&mut self, confirmed: U256, pending: U256, block_number: U64) {
+        self.forget_settled(confirmed, block_number);
+        self.next = pending;
+        self.released.clear();
+    }
+}
+
+impl<M> NonceManager<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self {
+            provider,
+            signers: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl<M> NonceManager<M>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    /// Reserve the next nonce of `signer`, fetching it from chain on first use.
+    pub async fn reserve(&self, signer: Address) -> Result<NonceReservation> {
+        let known = self.signers.lock().unwrap().contains_key(&signer);
+        if !known {
+            let pending = self.pending_nonce(signer).await?;
+            self.signers
+                .lock()
+                .unwrap()
+                .entry(signer)
+                .or_insert_with(|| SignerNonces {
+                    next: pending,
+                    ..Default::default()
+                });
+        }
+        let nonce = self
+            .signers
+            .lock()
+            .unwrap()
+            .get_mut(&signer)
+            .expect("signer is initialized")
+            .reserve();
+        Ok(NonceReservation {
+            signers: self.signers.clone(),
+            signer,
+            nonce,
+            committed: false,
+        })
+    }
+
+    /// Reconcile the nonces of `signer` with the chain at `block_number`. Nonces of
+    /// included transactions, and of transactions that expired before the block, are
+    /// forgotten, and the next nonce is resynced if the chain is ahead, e.g. because
+    /// the signer was used outside of Artemis.
+    pub async fn reconcile(&self, signer: Address, block_number: U64) -> Result<()> {
+        let confirmed = self
+            .provider
+            .get_transaction_count(signer, Some(BlockNumber::Latest.into()))
+            .await?;
+        let pending = self.pending_nonce(signer).await?;
+        if let Some(nonces) = self.signers.lock().unwrap().get_mut(&signer) {
+            nonces.reconcile(confirmed, pending, block_number);
+        }
+        Ok(())
+    }
+
+    /// Reset the nonces of `signer` from chain, e.g. after a node rejected a
+    /// transaction with a nonce error. Outstanding reservations are kept, and so are
+    /// the nonces of sent transactions which can still be included.
+    pub async fn resync(&self, signer: Address) -> Result<()> {
+        let block_number = self.provider.get_block_number().await?;
+        let confirmed = self
+            .provider
+            .get_transaction_count(signer, Some(BlockNumber::Latest.into()))
+            .await?;
+        let pending = self.pending_nonce(signer).await?;
+        if let Some(nonces) = self.signers.lock().unwrap().get_mut(&signer) {
+            nonces.resync(confirmed, pending, block_number);
+        }
+        Ok(())
+    }
+
+    async fn pending_nonce(&self, signer: Address) -> Result<U256> {
+        Ok(self
+            .provider
+            .get_transaction_count(signer, Some(BlockNumber::Pending.into()))
+            .await?)
+    }
+}
+
+/// Whether an error returned when sending a transaction is caused by a wrong nonce,
+/// in which case the nonces of the signer should be [resynced](NonceManager::resync).
+pub fn is_nonce_error(error: &impl std::fmt::Display) -> bool {
+    let error = error.to_string().to_lowercase();
+    error.contains("nonce too low") || error.contains("nonce too high")
+}
+
+/// A nonce reserved for a single transaction. Dropping the reservation without
+/// committing it releases the nonce.
+#[derive(Debug)]
+pub struct NonceReservation {
+    signers: Arc<Mutex<HashMap<Address, SignerNonces>>>,
+    signer: Address,
+    nonce: U256,
+    committed: bool,
+}
+
+impl NonceReservation {
+    pub fn nonce(&self) -> U256 {
+        self.nonce
+    }
+
+    pub fn signer(&self) -> Address {
+        self.signer
+    }
+
+    /// Mark the nonce as used by a sent transaction. `valid_until` is the last block
+    /// the transaction can be included in, e.g. the target block of a bundle, after
+    /// which the nonce can be reused. Without one, the nonce is held until a
+    /// transaction with it is included, so mempool transactions, which can be dropped,
+    /// should set a block after which they are assumed to be.
+    pub fn commit(mut self, valid_until: Option<U64>) {
+        if let Some(nonces) = self.signers.lock().unwrap().get_mut(&self.signer) {
+            nonces.commit(self.nonce, valid_until);
+        }
+        self.committed = true;
+    }
+}
+
+impl Drop for NonceReservation {
+    fn drop(&mut self) {
+        if self.committed {
+            return;
+        }
+        if let Ok(mut signers) = self.signers.lock() {
+            if let Some(nonces) = signers.get_mut(&self.signer) {
+                nonces.release(self.nonce);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::Provider;
+
+    #[tokio::test]
+    async fn reserves_and_releases_nonces() {
+        let (provider, mock) = Provider::mocked();
+        mock.push(U256::from(5)).unwrap();
+        let manager = NonceManager::new(Arc::new(provider));
+        let signer = Address::repeat_byte(1);
+
+        let a = manager.reserve(signer).await.unwrap();
+        let b = manager.reserve(signer).await.unwrap();
+        let c = manager.reserve(signer).await.unwrap();
+        assert_eq!(
+            (a.nonce(), b.nonce(), c.nonce()),
+            (5.into(), 6.into(), 7.into())
+        );
+
+        // A failed send in the middle leaves a gap, which is filled first.
+        a.commit(None);
+        drop(b);
+        assert_eq!(manager.reserve(signer).await.unwrap().nonce(), 6.into());
+
+        // Releasing the top nonce shrinks back.
+        drop(c);
+        let d = manager.reserve(signer).await.unwrap();
+        assert_eq!(d.nonce(), 6.into());
+        d.commit(Some(10.into()));
+        assert_eq!(manager.reserve(signer).await.unwrap().nonce(), 7.into());
+    }
+
+    #[test]
+    fn reconciles_with_chain() {
+        let mut nonces = SignerNonces {
+            next: 8.into(),
+            ..Default::default()
+        };
+        nonces.in_flight.insert(5.into(), None);
+        nonces.in_flight.insert(6.into(), Some(10.into()));
+        nonces.in_flight.insert(7.into(), Some(10.into()));
+
+        // Nonce 5 was included, the bundle with 6 and 7 wasn't.
+        nonces.reconcile(6.into(), 6.into(), 11.into());
+        assert!(nonces.in_flight.is_empty());
+        assert_eq!(nonces.next, 6.into());
+
+        // The signer was used elsewhere.
+        nonces.reconcile(6.into(), 9.into(), 12.into());
+        assert_eq!(nonces.next, 9.into());
+    }
+
+    #[test]
+    fn only_nonce_errors_resync() {
+        assert!(is_nonce_error(&"nonce too low"));
+        assert!(is_nonce_error(&"Nonce too high"));
+        assert!(!is_nonce_error(&"already known"));
+        assert!(!is_nonce_error(&"replacement transaction underpriced"));
+    }
+
+    #[test]
+    fn resync_keeps_live_bundle_nonces() {
+        let mut nonces = SignerNonces {
+            next: 9.into(),
+            ..Default::default()
+        };
+        nonces.in_flight.insert(4.into(), None);
+        nonces.in_flight.insert(5.into(), Some(10.into()));
+        nonces.in_flight.insert(6.into(), Some(12.into()));
+        nonces.released.insert(8.into());
+
+        // Nonce 4 was included, and the bundle with 5 expired at block 10.
+        nonces.resync(5.into(), 5.into(), 11.into());
+        assert_eq!(nonces.in_flight.keys().collect::<Vec<_>>(), vec![&6.into()]);
+        assert_eq!(nonces.next, 5.into());
+
+        // The bundle targeting block 12 still holds nonce 6.
+        assert_eq!(nonces.reserve(), 5.into());
+        assert_eq!(nonces.reserve(), 7.into());
+    }
+}
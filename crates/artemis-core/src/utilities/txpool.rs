@@ -0,0 +1,84 @@
+use anyhow::Result;
+use ethers::{
+    providers::Middleware,
+    types::{BlockNumber, Transaction},
+};
+use tracing::debug;
+
+/// Pending transactions of the node's mempool, read with `txpool_content`. Nodes which
+/// don't expose the txpool namespace only show their own pending block, so the
+/// transactions of the pending block are used for them instead.
+pub async fn pending_transactions<M>(provider: &M) -> Result<Vec<Transaction>>
+where
+    M: Middleware,
+    M::Error: 'static,
+{
+    match provider.txpool_content().await {
+        Ok(content) => Ok(content
+            .pending
+            .into_values()
+            .flat_map(|txs| txs.into_values())
+            .collect()),
+        Err(e) => {
+            debug!(
+                "txpool_content is unavailable, using the pending block: {}",
+                e
+            );
+            Ok(provider
+                .get_block_with_txs(BlockNumber::Pending)
+                .await?
+                .map(|block| block.transactions)
+                .unwrap_or_default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        providers::Provider,
+        types::{Address, Block, TxpoolContent, H256},
+    };
+    use std::collections::BTreeMap;
+
+    #[tokio::test]
+    async fn reads_the_txpool() {
+        let (provider, mock) = Provider::mocked();
+        let tx = Transaction {
+            hash: H256::repeat_byte(1),
+            ..Default::default()
+        };
+        let content = TxpoolContent {
+            pending: BTreeMap::from([(
+                Address::repeat_byte(1),
+                BTreeMap::from([("0".to_string(), tx.clone())]),
+            )]),
+            queued: BTreeMap::new(),
+        };
+        mock.push(content).unwrap();
+        let txs = pending_transactions(&provider).await.unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].hash, tx.hash);
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_the_pending_block() {
+        let (provider, mock) = Provider::mocked();
+        let tx = Transaction {
+            hash: H256::repeat_byte(2),
+            ..Default::default()
+        };
+        let block = Block {
+            transactions: vec![tx.clone()],
+            ..Default::default()
+        };
+        // The mock answers with the last pushed response first. `txpool_content` fails,
+        // as its response isn't a txpool.
+        mock.push(block).unwrap();
+        mock.push(false).unwrap();
+        let txs = pending_transactions(&provider).await.unwrap();
+        assert_eq!(txs.len(), 1);
+        assert_eq!(txs[0].hash, tx.hash);
+    }
+}
@@ -1,16 +1,53 @@
 use artemis_core::{
-    collectors::{block_collector::BlockCollector, mempool_collector::MempoolCollector},
-    executors::mempool_executor::{MempoolExecutor, SubmitTxToMempool},
-    types::{Collector, Executor},
+    collectors::{
+        binance_depth_collector::{BinanceDepthCollector, BinanceDepthEvent},
+        block_collector::{BlockCollector, NewBlock},
+        fee_market_collector::FeeMarketCollector,
+        mempool_collector::MempoolCollector,
+        mevshare_collector::MevShareCollector,
+        timer_collector::{Schedule, TimerCollector},
+    },
+    executors::{
+        binance_order_executor::{BinanceOrder, BinanceOrderExecutor},
+        mempool_executor::{GasBidInfo, MempoolExecutor, SubmitTxToMempool},
+        mev_share_executor::MevshareExecutor,
+        private_tx_executor::{CancelPrivateTx, PrivateTxExecutor, SubmitPrivateTx},
+        simulated_executor::SimulatedExecutor,
+    },
+    types::{Collector, CollectorStream, Executor},
+    utilities::bundle_replacement::{CancelBundle, SupersedeBundle},
+};
+use async_trait::async_trait;
+use binance::{
+    client::BinanceApiConfig,
+    types::{NewOrder, OrderId, Side},
 };
 use ethers::providers::StreamExt;
 use ethers::{
+    core::rand::thread_rng,
     providers::{Middleware, Provider, Ws},
-    types::{BlockNumber, TransactionRequest, U256},
+    signers::{LocalWallet, Signer},
+    types::{
+        Address, BlockNumber, Eip1559TransactionRequest, TransactionRequest, H256, I256, U256,
+    },
     utils::{Anvil, AnvilInstance},
 };
-use std::{sync::Arc, time::Duration};
-use tokio::time::sleep;
+use futures::SinkExt;
+use matchmaker::{
+    client::RelayConfig,
+    types::{BundleRequest, BundleTx},
+};
+use std::{str::FromStr, sync::Arc, time::Duration};
+use test_support::{
+    mock_http::{HttpRequest, MockHttp},
+    mock_relay::{MockRelay, MockResponse},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    time::{sleep, timeout},
+};
+use tokio_tungstenite::tungstenite::Message;
 
 /// Spawns Anvil and instantiates an Http provider.
 pub async fn spawn_anvil() -> (Provider<Ws>, AnvilInstance) {
@@ -38,6 +75,23 @@ async fn test_block_collector_sends_blocks() {
     assert_eq!(block_a.hash, block_b.hash.unwrap());
 }
 
+/// Test that fee market collector emits a snapshot for each block.
+#[tokio::test]
+async fn test_fee_market_collector_sends_snapshots() {
+    let (provider, _anvil) = spawn_anvil().await;
+    let provider = Arc::new(provider);
+    let fee_market_collector = FeeMarketCollector::new(provider.clone()).with_history_blocks(1);
+    let fee_market_stream = fee_market_collector.get_event_stream().await.unwrap();
+    let fee_market = fee_market_stream.into_future().await.0.unwrap();
+    let block = provider
+        .get_block(fee_market.block_number)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(fee_market.base_fee, block.base_fee_per_gas.unwrap());
+    assert_eq!(fee_market.priority_fees.len(), 5);
+}
+
 /// Test that mempool collector correctly emits blocks.
 #[tokio::test]
 async fn test_mempool_collector_sends_txs() {
@@ -78,6 +132,7 @@ async fn test_mempool_executor_sends_tx_simple() {
     let action = SubmitTxToMempool {
         tx: tx.into(),
         gas_bid_info: None,
+        deadline: None,
     };
     mempool_executor.execute(action).await.unwrap();
     //Sleep to seconds so that the tx has time to be mined
@@ -85,3 +140,406 @@ async fn test_mempool_executor_sends_tx_simple() {
     let tx = provider.get_transaction_count(account, None).await.unwrap();
     assert_eq!(tx, 1.into());
 }
+
+/// Test that the mempool executor prices EIP-1559 txs from the gas bid
+#[tokio::test]
+async fn test_mempool_executor_sends_eip1559_tx() {
+    let (provider, _anvil) = spawn_anvil().await;
+    let provider = Arc::new(provider);
+    let max_fee_per_gas = U256::from(50_000_000_000u64);
+    let mempool_executor =
+        MempoolExecutor::new(provider.clone()).with_max_fee_per_gas(max_fee_per_gas);
+
+    let account = provider.get_accounts().await.unwrap()[0];
+    let tx = Eip1559TransactionRequest::new()
+        .to(account)
+        .from(account)
+        .value(42u64);
+    let action = SubmitTxToMempool {
+        tx: tx.into(),
+        // Bid far above the cap, so that the max fee is capped.
+        gas_bid_info: Some(GasBidInfo {
+            total_profit: U256::exp10(18),
+            bid_percentage: 50,
+        }),
+        deadline: None,
+    };
+    mempool_executor.execute(action).await.unwrap();
+    sleep(Duration::from_secs(2)).await;
+    let block = provider
+        .get_block_with_txs(BlockNumber::Latest)
+        .await
+        .unwrap()
+        .unwrap();
+    let tx = block.transactions.last().unwrap();
+    assert_eq!(tx.transaction_type, Some(2u64.into()));
+    // The bid leaves far more than the cap on top of the base fee, so both fees are capped.
+    assert_eq!(tx.max_fee_per_gas, Some(max_fee_per_gas));
+    assert_eq!(tx.max_priority_fee_per_gas, Some(max_fee_per_gas));
+}
+
+/// Test that the simulated executor records the outcome of actions without keeping them
+#[tokio::test]
+async fn test_simulated_executor_reports_pnl() {
+    let (provider, _anvil) = spawn_anvil().await;
+    let provider = Arc::new(provider);
+    let accounts = provider.get_accounts().await.unwrap();
+    let (bot, recipient) = (accounts[0], accounts[1]);
+    let simulated_executor = SimulatedExecutor::new(provider.clone(), bot);
+
+    let value = U256::exp10(18);
+    let gas_price = U256::from(10_000_000_000u64);
+    let tx = TransactionRequest::new()
+        .to(recipient)
+        .from(bot)
+        .value(value)
+        .gas(21_000)
+        .gas_price(gas_price);
+    let action = SubmitTxToMempool {
+        tx: tx.into(),
+        gas_bid_info: None,
+        deadline: None,
+    };
+    simulated_executor.execute(action).await.unwrap();
+
+    let report = simulated_executor.report();
+    let record = &report.records[0];
+    assert_eq!(record.revert_reason, None);
+    assert_eq!(record.gas_used, 21_000.into());
+    let cost = value + gas_price * 21_000;
+    assert_eq!(record.balance_change, -I256::from_raw(cost));
+    // The fork was reverted after the simulation.
+    let nonce = provider.get_transaction_count(bot, None).await.unwrap();
+    assert_eq!(nonce, 0.into());
+}
+
+/// Spawns a mock Binance websocket server which replays `messages` to each connection.
+async fn spawn_mock_binance_ws(messages: Vec<&'static str>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+            for msg in &messages {
+                ws.send(Message::Text(msg.to_string())).await.unwrap();
+            }
+            // Keep the connection open so that the collector does not resync.
+            while ws.next().await.is_some() {}
+        }
+    });
+    format!("ws://{}", addr)
+}
+
+/// Test that the private tx executor signs requests and sends txs to every endpoint
+#[tokio::test]
+async fn test_private_tx_executor_sends_to_all_endpoints() {
+    let tx_hash = H256::repeat_byte(0x11);
+    let relays = [MockRelay::spawn().await, MockRelay::spawn().await];
+    for relay in &relays {
+        relay.respond(
+            "eth_sendPrivateTransaction",
+            MockResponse::Result(serde_json::json!(tx_hash)),
+        );
+    }
+    let signer = LocalWallet::new(&mut thread_rng());
+    let config = |relay: &MockRelay| RelayConfig::new(relay.url());
+    let executor = PrivateTxExecutor::new(signer.clone(), signer.clone(), config(&relays[0]))
+        .unwrap()
+        .with_endpoint(config(&relays[1]))
+        .unwrap()
+        .with_fast_mode(true);
+
+    let tx = TransactionRequest::new()
+        .to(Address::random())
+        .value(1)
+        .nonce(0)
+        .gas(21_000)
+        .gas_price(1)
+        .chain_id(1);
+    let action = SubmitPrivateTx {
+        tx: tx.into(),
+        max_block_number: Some(100.into()),
+    };
+    executor.execute(action).await.unwrap();
+
+    for relay in &relays {
+        let requests = relay.requests();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, "eth_sendPrivateTransaction");
+        assert_eq!(requests[0].signer, Some(signer.address()));
+        assert_eq!(requests[0].params[0]["maxBlockNumber"], "0x64");
+        assert_eq!(requests[0].params[0]["preferences"]["fast"], true);
+    }
+}
+
+/// Test that the private tx executor fails when no endpoint cancels the tx
+#[tokio::test]
+async fn test_private_tx_executor_cancels() {
+    let cancelling = MockRelay::spawn().await;
+    cancelling.respond(
+        "eth_cancelPrivateTransaction",
+        MockResponse::Result(serde_json::json!(true)),
+    );
+    let refusing = MockRelay::spawn().await;
+    refusing.respond(
+        "eth_cancelPrivateTransaction",
+        MockResponse::Result(serde_json::json!(false)),
+    );
+    let signer = LocalWallet::new(&mut thread_rng());
+
+    let config = |relay: &MockRelay| RelayConfig::new(relay.url());
+    let executor =
+        PrivateTxExecutor::new(signer.clone(), signer.clone(), config(&refusing)).unwrap();
+    let action = CancelPrivateTx {
+        tx_hash: H256::random(),
+    };
+    assert!(executor.execute(action.clone()).await.is_err());
+    let requests = refusing.requests();
+    assert_eq!(requests[0].method, "eth_cancelPrivateTransaction");
+    assert_eq!(requests[0].signer, Some(signer.address()));
+    assert_eq!(
+        requests[0].params[0]["txHash"],
+        serde_json::json!(action.tx_hash)
+    );
+
+    let executor = executor.with_endpoint(config(&cancelling)).unwrap();
+    executor.execute(action).await.unwrap();
+    assert_eq!(cancelling.requests()[0].signer, Some(signer.address()));
+}
+
+/// Test that the binance depth collector syncs the local book from a snapshot and
+/// diff-depth updates, and forwards top of book updates.
+#[tokio::test]
+async fn test_binance_depth_collector_syncs_book() {
+    let ws_url = spawn_mock_binance_ws(vec![
+        // Stale update, already included in the snapshot.
+        r#"{"stream":"ethusdc@depth@100ms","data":{"e":"depthUpdate","E":1,"s":"ETHUSDC","U":95,"u":100,"b":[["2000.00","0"]],"a":[]}}"#,
+        r#"{"stream":"ethusdc@depth@100ms","data":{"e":"depthUpdate","E":2,"s":"ETHUSDC","U":99,"u":102,"b":[["2000.00","0"]],"a":[["2002.00","3"]]}}"#,
+        r#"{"stream":"ethusdc@bookTicker","data":{"u":102,"s":"ETHUSDC","b":"1999.00","B":"2","a":"2001.00","A":"1"}}"#,
+        r#"{"stream":"ethusdc@depth@100ms","data":{"e":"depthUpdate","E":3,"s":"ETHUSDC","U":103,"u":104,"b":[["1998.00","5"]],"a":[]}}"#,
+    ])
+    .await;
+    let rest = MockHttp::spawn(|_: &HttpRequest| {
+        (
+            200,
+            r#"{"lastUpdateId":100,"bids":[["2000.00","1"],["1999.00","2"]],"asks":[["2001.00","1"]]}"#
+                .to_string(),
+        )
+    })
+    .await;
+
+    let collector = BinanceDepthCollector::new("ETHUSDC").with_endpoints(ws_url, rest.url());
+    let mut stream = collector.get_event_stream().await.unwrap();
+
+    let d = |val: &str| rust_decimal::Decimal::from_str(val).unwrap();
+    let mut saw_top_of_book = false;
+    let depth = timeout(Duration::from_secs(5), async {
+        while let Some(event) = stream.next().await {
+            match event {
+                BinanceDepthEvent::TopOfBook(top) => {
+                    assert_eq!(top.bid_price, d("1999"));
+                    saw_top_of_book = true;
+                }
+                BinanceDepthEvent::Depth(depth) if depth.last_update_id == 104 => return depth,
+                BinanceDepthEvent::Depth(_) => {}
+            }
+        }
+        panic!("stream ended");
+    })
+    .await
+    .unwrap();
+
+    assert!(saw_top_of_book);
+    assert_eq!(depth.bids, vec![(d("1999"), d("2")), (d("1998"), d("5"))]);
+    assert_eq!(depth.asks, vec![(d("2001"), d("1")), (d("2002"), d("3"))]);
+}
+
+/// Test that the binance order executor places and cancels orders through the API
+#[tokio::test]
+async fn test_binance_order_executor_places_and_cancels() {
+    let binance = MockHttp::spawn(|request: &HttpRequest| {
+        let status = match request.method.as_str() {
+            "POST" => "NEW",
+            _ => "CANCELED",
+        };
+        let order = format!(
+            r#"{{"symbol":"ETHUSDC","orderId":28,"clientOrderId":"arb-1","price":"2000.00","origQty":"1.5","executedQty":"0","cummulativeQuoteQty":"0","status":"{}","timeInForce":"GTC","type":"LIMIT","side":"BUY"}}"#,
+            status
+        );
+        (200, order)
+    })
+    .await;
+    let executor = BinanceOrderExecutor::new(
+        BinanceApiConfig::new("api-key", "secret-key").with_base_url(binance.url()),
+    );
+
+    let d = |val: &str| rust_decimal::Decimal::from_str(val).unwrap();
+    let order =
+        NewOrder::limit("ETHUSDC", Side::Buy, d("1.5"), d("2000")).with_client_order_id("arb-1");
+    executor.execute(BinanceOrder::Place(order)).await.unwrap();
+    executor
+        .execute(BinanceOrder::Cancel {
+            symbol: "ETHUSDC".to_string(),
+            order_id: OrderId::ClientOrderId("arb-1".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let requests = binance.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(
+        (requests[0].method.as_str(), requests[0].path.as_str()),
+        ("POST", "/api/v3/order")
+    );
+    for param in [
+        "symbol=ETHUSDC",
+        "side=BUY",
+        "price=2000",
+        "newClientOrderId=arb-1",
+    ] {
+        assert!(requests[0].query.contains(param));
+    }
+    assert_eq!(
+        (requests[1].method.as_str(), requests[1].path.as_str()),
+        ("DELETE", "/api/v3/order")
+    );
+    assert!(requests[1].query.contains("origClientOrderId=arb-1"));
+    for request in &requests {
+        assert_eq!(request.header("x-mbx-apikey"), Some("api-key"));
+        assert!(request.query.contains("&signature="));
+    }
+}
+
+/// A block source which replays a fixed list of block numbers.
+struct MockBlocks(Vec<u64>);
+
+#[async_trait]
+impl Collector<NewBlock> for MockBlocks {
+    async fn get_event_stream(&self) -> anyhow::Result<CollectorStream<'_, NewBlock>> {
+        let blocks = self.0.iter().map(|number| NewBlock {
+            hash: H256::zero(),
+            number: (*number).into(),
+        });
+        Ok(Box::pin(futures::stream::iter(blocks)))
+    }
+}
+
+/// Test that the timer collector multiplexes interval and block schedules.
+#[tokio::test]
+async fn test_timer_collector_emits_named_ticks() {
+    let collector = TimerCollector::new()
+        .with_schedule(
+            "requote",
+            Schedule::Interval {
+                period: Duration::from_millis(50),
+                aligned: false,
+            },
+        )
+        .with_schedule(
+            "balances",
+            Schedule::Blocks {
+                every: 2,
+                delay: Duration::ZERO,
+            },
+        )
+        .with_blocks(Box::new(MockBlocks(vec![1, 2, 3, 4])));
+    let stream = collector.get_event_stream().await.unwrap();
+
+    let ticks = timeout(Duration::from_secs(5), stream.take(5).collect::<Vec<_>>())
+        .await
+        .unwrap();
+    let block_ticks: Vec<_> = ticks
+        .iter()
+        .filter(|tick| tick.name == "balances")
+        .map(|tick| tick.block_number.unwrap().as_u64())
+        .collect();
+    assert_eq!(block_ticks, vec![2, 4]);
+    assert!(ticks.iter().any(|tick| tick.name == "requote"));
+}
+
+/// Spawns a mock MEV-Share SSE server which sends `event` on each connection, then
+/// closes it.
+async fn spawn_mock_sse(event: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let mut buf = [0u8; 4096];
+            let _ = stream.read(&mut buf).await.unwrap();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\ndata: {}\n\n",
+                event
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+        }
+    });
+    format!("http://{}", addr)
+}
+
+/// Test that the mev-share collector reconnects when the stream disconnects.
+#[tokio::test]
+async fn test_mevshare_collector_reconnects() {
+    let url = spawn_mock_sse(
+        r#"{"hash":"0x0000000000000000000000000000000000000000000000000000000000000001","txs":null,"logs":null}"#,
+    )
+    .await;
+    let collector = MevShareCollector::new(url);
+    let stream = collector.get_event_stream().await.unwrap();
+    let events = timeout(Duration::from_secs(10), stream.take(2).collect::<Vec<_>>())
+        .await
+        .unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].hash, H256::from_low_u64_be(1));
+}
+
+/// Test that the mev-share collector returns an error when the endpoint is down.
+#[tokio::test]
+async fn test_mevshare_collector_propagates_connection_errors() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let collector = MevShareCollector::new(url);
+    assert!(collector.get_event_stream().await.is_err());
+}
+
+/// Test that the mev-share executor supersedes bundles by key, retries rate limited
+/// bundles, and cancels superseded bundles
+#[tokio::test]
+async fn test_mev_share_executor_supersedes_bundles() {
+    let relay = MockRelay::spawn().await;
+    let signer = LocalWallet::new(&mut thread_rng());
+    let config = RelayConfig::new(relay.url());
+    let executor = MevshareExecutor::from_config(signer.clone(), config).unwrap();
+
+    let bundle = |block: u64| SupersedeBundle {
+        key: "weth-usdc".to_string(),
+        bundle: BundleRequest::make_simple(
+            block.into(),
+            vec![BundleTx::TxHash {
+                hash: H256::random(),
+            }],
+        ),
+    };
+    relay.respond_once("mev_sendBundle", MockResponse::HttpStatus(429));
+    executor.execute(bundle(100)).await.unwrap();
+    executor.execute(bundle(101)).await.unwrap();
+
+    let bundles = relay.bundles();
+    assert_eq!(bundles.len(), 3);
+    let replacement_uuid = bundles[0]["replacementUuid"].clone();
+    assert!(replacement_uuid.is_string());
+    assert_eq!(bundles[2]["replacementUuid"], replacement_uuid);
+    assert_eq!(bundles[2]["inclusion"]["block"], "0x65");
+
+    executor
+        .execute(CancelBundle {
+            key: "weth-usdc".to_string(),
+        })
+        .await
+        .unwrap();
+    let requests = relay.requests();
+    let cancel = requests.last().unwrap();
+    assert_eq!(cancel.method, "eth_cancelBundle");
+    assert_eq!(cancel.signer, Some(signer.address()));
+    assert_eq!(cancel.params[0]["replacementUuid"], replacement_uuid);
+}
@@ -0,0 +1,335 @@
+use std::{
+    sync::atomic::{AtomicI64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, StatusCode};
+use serde::de::DeserializeOwned;
+use sha2::Sha256;
+
+use crate::{
+    constants::{BINANCE_API_URL, DEFAULT_RECV_WINDOW, ORDER_PATH, TIME_PATH},
+    types::{BinanceApiError, ErrorPayload, NewOrder, Order, OrderId, ServerTime},
+};
+
+/// A partial implementation of the Binance spot API, supporting orders.
+#[derive(Debug)]
+pub struct BinanceClient {
+    client: Client,
+    cfg: BinanceApiConfig,
+    /// Difference between the server clock and the local clock, in milliseconds.
+    time_offset: AtomicI64,
+}
+
+/// Configuration for the Binance API client.
+#[derive(Debug, Clone)]
+pub struct BinanceApiConfig {
+    pub api_key: String,
+    /// Secret key used to sign requests.
+    pub secret_key: String,
+    pub base_url: String,
+    /// Number of milliseconds after its timestamp a request is valid for.
+    pub recv_window: u64,
+}
+
+impl BinanceApiConfig {
+    /// Configuration for the Binance spot API, with the default recv window.
+    pub fn new(api_key: impl Into<String>, secret_key: impl Into<String>) -> Self {
+        Self {
+            api_key: api_key.into(),
+            secret_key: secret_key.into(),
+            base_url: BINANCE_API_URL.to_string(),
+            recv_window: DEFAULT_RECV_WINDOW,
+        }
+    }
+
+    /// Use another API, e.g. the spot testnet.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
+    /// Set the number of milliseconds after its timestamp a request is valid for.
+    pub fn with_recv_window(mut self, recv_window: u64) -> Self {
+        self.recv_window = recv_window;
+        self
+    }
+}
+
+impl BinanceClient {
+    /// Create a new client with the given configuration.
+    pub fn new(cfg: BinanceApiConfig) -> Self {
+        Self {
+            client: Client::new(),
+            cfg,
+            time_offset: AtomicI64::new(0),
+        }
+    }
+
+    /// Place an order.
+    pub async fn place_order(&self, order: &NewOrder) -> Result<Order, BinanceApiError> {
+        let mut params = to_params(order);
+        params.push(("newOrderRespType".to_string(), "RESULT".to_string()));
+        self.signed(Method::POST, ORDER_PATH, params).await
+    }
+
+    /// Cancel an open order.
+    pub async fn cancel_order(
+        &self,
+        symbol: &str,
+        order_id: &OrderId,
+    ) -> Result<Order, BinanceApiError> {
+        self.signed(Method::DELETE, ORDER_PATH, order_params(symbol, order_id))
+            .await
+    }
+
+    /// Query the status of an order.
+    pub async fn query_order(
+        &self,
+        symbol: &str,
+        order_id: &OrderId,
+    ) -> Result<Order, BinanceApiError> {
+        self.signed(Method::GET, ORDER_PATH, order_params(symbol, order_id))
+            .await
+    }
+
+    /// Align request timestamps with the server clock. Returns the offset of the server
+    /// clock, in milliseconds.
+    pub async fn sync_time(&self) -> Result<i64, BinanceApiError> {
+        let url = format!("{}{}", self.cfg.base_url, TIME_PATH);
+        let server_time = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<ServerTime>()
+            .await?;
+        let offset = server_time.server_time - now_millis();
+        self.time_offset.store(offset, Ordering::Relaxed);
+        Ok(offset)
+    }
+
+    /// Send a signed request. Requests rejected because of clock drift are retried once,
+    /// after syncing with the server clock.
+    async fn signed<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        params: Vec<(String, String)>,
+    ) -> Result<T, BinanceApiError> {
+        match self.send_signed(method.clone(), path, &params).await {
+            Err(BinanceApiError::TimestampOutOfWindow(_)) => {
+                self.sync_time().await?;
+                self.send_signed(method, path, &params).await
+            }
+            res => res,
+        }
+    }
+
+    async fn send_signed<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        path: &str,
+        params: &[(String, String)],
+    ) -> Result<T, BinanceApiError> {
+        let timestamp = now_millis() + self.time_offset.load(Ordering::Relaxed);
+        let mut query = params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, encode(value)))
+            .collect::<Vec<_>>();
+        query.push(format!("recvWindow={}", self.cfg.recv_window));
+        query.push(format!("timestamp={}", timestamp));
+        let query = query.join("&");
+        let signature = sign(&self.cfg.secret_key, &query);
+        let url = format!(
+            "{}{}?{}&signature={}",
+            self.cfg.base_url, path, query, signature
+        );
+
+        let res = self
+            .client
+            .request(method, url)
+            .header("X-MBX-APIKEY", &self.cfg.api_key)
+            .send()
+            .await?;
+        let status = res.status();
+        if status.is_success() {
+            return Ok(res.json::<T>().await?);
+        }
+        let body = res.text().await?;
+        match serde_json::from_str::<ErrorPayload>(&body) {
+            Ok(payload) => Err(BinanceApiError::from_code(payload.code, payload.msg)),
+            Err(_) if status == StatusCode::TOO_MANY_REQUESTS || status.as_u16() == 418 => {
+                Err(BinanceApiError::RateLimited(body))
+            }
+            Err(_) => Err(BinanceApiError::Api {
+                code: status.as_u16().into(),
+                msg: body,
+            }),
+        }
+    }
+}
+
+/// Helper function to convert an order to request parameters.
+fn to_params(order: &NewOrder) -> Vec<(String, String)> {
+    let value = serde_json::to_value(order).expect("orders serialize to json");
+    value
+        .as_object()
+        .expect("orders serialize to objects")
+        .iter()
+        .map(|(key, value)| match value {
+            serde_json::Value::String(value) => (key.clone(), value.clone()),
+            value => (key.clone(), value.to_string()),
+        })
+        .collect()
+}
+
+/// Helper function to build the parameters identifying an order.
+fn order_params(symbol: &str, order_id: &OrderId) -> Vec<(String, String)> {
+    let id = match order_id {
+        OrderId::Id(id) => ("orderId".to_string(), id.to_string()),
+        OrderId::ClientOrderId(id) => ("origClientOrderId".to_string(), id.clone()),
+    };
+    vec![("symbol".to_string(), symbol.to_string()), id]
+}
+
+/// Helper function to sign a query string with HMAC-SHA256.
+fn sign(secret_key: &str, query: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret_key.as_bytes())
+        .expect("HMAC accepts keys of any size");
+    mac.update(query.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Helper function to percent-encode a query parameter value.
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time is after the epoch")
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{OrderStatus, Side};
+    use rust_decimal::Decimal;
+    use std::{
+        str::FromStr,
+        sync::{Arc, Mutex},
+    };
+    use test_support::mock_http::{HttpRequest, MockHttp};
+
+    const SECRET_KEY: &str = "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j";
+
+    const ORDER: &str = r#"{"symbol":"ETHUSDC","orderId":28,"clientOrderId":"arb-1","price":"2000.00","origQty":"1.5","executedQty":"1.5","cummulativeQuoteQty":"3000.00","status":"FILLED","timeInForce":"IOC","type":"LIMIT","side":"BUY"}"#;
+
+    /// Spawns a mock Binance API. Each request is checked for a valid signature and
+    /// answered by `handler` with a status and a body, from its method, path and query.
+    async fn spawn_mock_binance<F>(handler: F) -> MockHttp
+    where
+        F: Fn(&str, &str, &str) -> (u16, String) + Send + Sync + 'static,
+    {
+        MockHttp::spawn(move |request: &HttpRequest| {
+            if request.path == TIME_PATH {
+                return (200, format!(r#"{{"serverTime":{}}}"#, now_millis()));
+            }
+            let (unsigned, signature) = request.query.rsplit_once("&signature=").unwrap();
+            assert_eq!(sign(SECRET_KEY, unsigned), signature);
+            assert_eq!(request.header("x-mbx-apikey"), Some("api-key"));
+            handler(&request.method, &request.path, unsigned)
+        })
+        .await
+    }
+
+    fn client(base_url: &str) -> BinanceClient {
+        BinanceClient::new(BinanceApiConfig::new("api-key", SECRET_KEY).with_base_url(base_url))
+    }
+
+    #[test]
+    fn signs_requests() {
+        // Example from the Binance API documentation.
+        let query = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
+        assert_eq!(
+            sign(SECRET_KEY, query),
+            "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+        );
+    }
+
+    #[tokio::test]
+    async fn places_and_cancels_orders() {
+        let binance = spawn_mock_binance(|method, _, query| match method {
+            "POST" => {
+                assert!(query.contains("timeInForce=IOC"));
+                assert!(query.contains("price=2000.00"));
+                (200, ORDER.to_string())
+            }
+            _ => (
+                400,
+                r#"{"code":-2011,"msg":"Unknown order sent."}"#.to_string(),
+            ),
+        })
+        .await;
+        let client = client(binance.url());
+
+        let order = NewOrder::ioc(
+            "ETHUSDC",
+            Side::Buy,
+            Decimal::from_str("1.5").unwrap(),
+            Decimal::from_str("2000.00").unwrap(),
+        )
+        .with_client_order_id("arb-1");
+        let order = client.place_order(&order).await.unwrap();
+        assert_eq!(order.status, OrderStatus::Filled);
+
+        let cancelled = client
+            .cancel_order("ETHUSDC", &OrderId::ClientOrderId("arb-1".into()))
+            .await;
+        assert!(matches!(cancelled, Err(BinanceApiError::UnknownOrder(_))));
+        assert_eq!(binance.requests()[1].method, "DELETE");
+    }
+
+    #[tokio::test]
+    async fn resyncs_clock_on_timestamp_errors() {
+        let attempts = Arc::new(Mutex::new(0));
+        let counter = attempts.clone();
+        let binance = spawn_mock_binance(move |_, _, _| {
+            let mut attempts = counter.lock().unwrap();
+            *attempts += 1;
+            match *attempts {
+                1 => (
+                    400,
+                    r#"{"code":-1021,"msg":"Timestamp for this request is outside of the recvWindow."}"#
+                        .to_string(),
+                ),
+                _ => (200, ORDER.to_string()),
+            }
+        })
+        .await;
+
+        let order = client(binance.url())
+            .query_order("ETHUSDC", &OrderId::Id(28))
+            .await;
+        assert_eq!(order.unwrap().order_id, 28);
+        let requests = binance.requests();
+        assert_eq!(requests.len(), 3);
+        assert_eq!(
+            (requests[1].method.as_str(), requests[1].path.as_str()),
+            ("GET", TIME_PATH)
+        );
+    }
+}
@@ -0,0 +1,11 @@
+/// Base URL of the Binance spot API.
+pub const BINANCE_API_URL: &str = "https://api.binance.com";
+
+/// Path of the order endpoint, used to place, cancel and query orders.
+pub const ORDER_PATH: &str = "/api/v3/order";
+
+/// Path of the server time endpoint.
+pub const TIME_PATH: &str = "/api/v3/time";
+
+/// Default number of milliseconds after its timestamp a signed request is valid for.
+pub const DEFAULT_RECV_WINDOW: u64 = 5000;
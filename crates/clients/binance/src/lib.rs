@@ -0,0 +1,19 @@
+#![warn(unused_crate_dependencies)]
+#![deny(unused_must_use, rust_2018_idioms)]
+#![doc(test(
+    no_crate_inject,
+    attr(deny(warnings, rust_2018_idioms), allow(dead_code, unused_variables))
+))]
+//! A partial implementation of the Binance spot REST API, supporting placing,
+//! cancelling and querying orders with
+//! [signed requests](https://binance-docs.github.io/apidocs/spot/en/#signed-trade-user_data-and-margin-endpoint-security).
+//! This is useful for strategies trading the CEX leg of CEX-DEX opportunities.
+
+/// This module contains the core client implementation.
+pub mod client;
+
+/// This module contains constants used by the client.
+mod constants;
+
+/// This module contains the core type definitions for the client.
+pub mod types;
@@ -0,0 +1,249 @@
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Side of an order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Side {
+    Buy,
+    Sell,
+}
+
+/// Type of an order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderType {
+    Limit,
+    Market,
+    StopLoss,
+    StopLossLimit,
+    TakeProfit,
+    TakeProfitLimit,
+    LimitMaker,
+}
+
+/// How long a limit order stays active.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeInForce {
+    /// Good til cancelled.
+    #[serde(rename = "GTC")]
+    Gtc,
+    /// Immediate or cancel: fill what can be filled now, and cancel the rest.
+    #[serde(rename = "IOC")]
+    Ioc,
+    /// Fill or kill: fill the whole order now, or cancel it.
+    #[serde(rename = "FOK")]
+    Fok,
+}
+
+/// Status of an order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum OrderStatus {
+    New,
+    PartiallyFilled,
+    Filled,
+    Canceled,
+    PendingCancel,
+    Rejected,
+    Expired,
+    ExpiredInMatch,
+}
+
+/// An order to place.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewOrder {
+    pub symbol: String,
+    pub side: Side,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_in_force: Option<TimeInForce>,
+    /// Quantity of the base asset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quantity: Option<Decimal>,
+    /// Quantity of the quote asset to spend or receive, for market orders.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quote_order_qty: Option<Decimal>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price: Option<Decimal>,
+    /// ID of the order, generated by Binance if unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_client_order_id: Option<String>,
+}
+
+impl NewOrder {
+    /// A limit order, active until filled or cancelled.
+    pub fn limit(symbol: impl Into<String>, side: Side, quantity: Decimal, price: Decimal) -> Self {
+        Self {
+            symbol: symbol.into(),
+            side,
+            order_type: OrderType::Limit,
+            time_in_force: Some(TimeInForce::Gtc),
+            quantity: Some(quantity),
+            quote_order_qty: None,
+            price: Some(price),
+            new_client_order_id: None,
+        }
+    }
+
+    /// An immediate or cancel limit order, which never rests on the book.
+    pub fn ioc(symbol: impl Into<String>, side: Side, quantity: Decimal, price: Decimal) -> Self {
+        Self {
+            time_in_force: Some(TimeInForce::Ioc),
+            ..Self::limit(symbol, side, quantity, price)
+        }
+    }
+
+    /// A market order for a quantity of the base asset.
+    pub fn market(symbol: impl Into<String>, side: Side, quantity: Decimal) -> Self {
+        Self {
+            symbol: symbol.into(),
+            side,
+            order_type: OrderType::Market,
+            time_in_force: None,
+            quantity: Some(quantity),
+            quote_order_qty: None,
+            price: None,
+            new_client_order_id: None,
+        }
+    }
+
+    /// Set the ID of the order, to query or cancel it without waiting for the response.
+    pub fn with_client_order_id(mut self, client_order_id: impl Into<String>) -> Self {
+        self.new_client_order_id = Some(client_order_id.into());
+        self
+    }
+}
+
+/// Identifies an order, by the ID assigned by Binance or by the client.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OrderId {
+    Id(u64),
+    ClientOrderId(String),
+}
+
+/// An order, as returned when placing, cancelling or querying it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Order {
+    pub symbol: String,
+    pub order_id: u64,
+    pub client_order_id: String,
+    pub price: Decimal,
+    pub orig_qty: Decimal,
+    pub executed_qty: Decimal,
+    pub cummulative_quote_qty: Decimal,
+    pub status: OrderStatus,
+    pub time_in_force: TimeInForce,
+    #[serde(rename = "type")]
+    pub order_type: OrderType,
+    pub side: Side,
+}
+
+/// Server time, in milliseconds since the epoch.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ServerTime {
+    pub(crate) server_time: i64,
+}
+
+/// Error payload returned by the Binance API.
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct ErrorPayload {
+    pub(crate) code: i64,
+    pub(crate) msg: String,
+}
+
+/// Error returned by the Binance API.
+#[derive(Debug, Error)]
+pub enum BinanceApiError {
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    /// The request timestamp is outside of the recv window, e.g. because the local clock
+    /// drifted from the server's.
+    #[error("timestamp outside of the recv window: {0}")]
+    TimestampOutOfWindow(String),
+    /// The API key or signature is invalid.
+    #[error("unauthorized: {0}")]
+    Unauthorized(String),
+    /// Too many requests, or the IP was banned for ignoring rate limits.
+    #[error("rate limited: {0}")]
+    RateLimited(String),
+    /// The order was rejected, e.g. because of an insufficient balance.
+    #[error("order rejected: {0}")]
+    OrderRejected(String),
+    /// The order to cancel or query doesn't exist.
+    #[error("unknown order: {0}")]
+    UnknownOrder(String),
+    /// The request has missing, malformed or invalid parameters.
+    #[error("invalid request ({code}): {msg}")]
+    InvalidRequest { code: i64, msg: String },
+    /// Any other error returned by the API.
+    #[error("binance error ({code}): {msg}")]
+    Api { code: i64, msg: String },
+}
+
+impl BinanceApiError {
+    /// Map an error code returned by the API to a typed error.
+    pub fn from_code(code: i64, msg: String) -> Self {
+        match code {
+            -1021 => Self::TimestampOutOfWindow(msg),
+            -1002 | -1022 | -2014 | -2015 => Self::Unauthorized(msg),
+            -1003 | -1015 => Self::RateLimited(msg),
+            -2010 => Self::OrderRejected(msg),
+            -2011 | -2013 => Self::UnknownOrder(msg),
+            -1199..=-1100 => Self::InvalidRequest { code, msg },
+            _ => Self::Api { code, msg },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn can_deserialize_order() {
+        let order = r#"{
+            "symbol": "ETHUSDC",
+            "orderId": 28,
+            "orderListId": -1,
+            "clientOrderId": "6gCrw2kRUAF9CvJDGP16IP",
+            "transactTime": 1507725176595,
+            "price": "2000.00000000",
+            "origQty": "10.00000000",
+            "executedQty": "4.00000000",
+            "cummulativeQuoteQty": "8000.00000000",
+            "status": "PARTIALLY_FILLED",
+            "timeInForce": "IOC",
+            "type": "LIMIT",
+            "side": "SELL"
+        }"#;
+        let order: Order = serde_json::from_str(order).unwrap();
+        assert_eq!(order.order_id, 28);
+        assert_eq!(order.status, OrderStatus::PartiallyFilled);
+        assert_eq!(order.time_in_force, TimeInForce::Ioc);
+        assert_eq!(order.executed_qty, Decimal::from_str("4").unwrap());
+    }
+
+    #[test]
+    fn maps_error_codes() {
+        assert!(matches!(
+            BinanceApiError::from_code(-1021, String::new()),
+            BinanceApiError::TimestampOutOfWindow(_)
+        ));
+        assert!(matches!(
+            BinanceApiError::from_code(-1121, String::new()),
+            BinanceApiError::InvalidRequest { code: -1121, .. }
+        ));
+        assert!(matches!(
+            BinanceApiError::from_code(-2013, String::new()),
+            BinanceApiError::UnknownOrder(_)
+        ));
+    }
+}
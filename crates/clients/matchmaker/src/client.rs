@@ -1,54 +1,274 @@
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
-use ethers::{signers::Signer, types::Chain};
+use ethers::{
+    signers::Signer,
+    types::{Chain, H256},
+};
 
+use http::header::{HeaderMap, HeaderName, HeaderValue};
 use jsonrpsee::core::client::ClientT;
-use jsonrpsee::core::Error as RpcError;
 use jsonrpsee::http_client::{transport::HttpBackend, HttpClient, HttpClientBuilder};
+use jsonrpsee::rpc_params;
 
 use tower::ServiceBuilder;
+use uuid::Uuid;
 
 use crate::{
+    error::{ClientError, RelayError},
     flashbots_signer::{FlashbotsSigner, FlashbotsSignerLayer},
-    types::{BundleRequest, SendBundleResponse},
+    types::{
+        BundleRequest, CancelBundleRequest, CancelPrivateTransactionRequest, EthBundleRequest,
+        PrivateTransactionRequest, SendBundleResponse, SimBundleOverrides, SimBundleResponse,
+    },
 };
 
+/// Default timeout of requests to the relay
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// Matchmaker client to interact with MEV-share
 pub struct Client<S> {
     /// Underlying HTTP client
     pub http_client: HttpClient<FlashbotsSigner<S, HttpBackend>>,
 }
 
-impl<S: Signer + Clone + 'static> Client<S> {
-    /// Create a new client with the given signer and chain
-    pub fn new(signer: S, chain: Chain) -> Self {
+/// Configuration of the relay a client sends requests to
+#[derive(Clone, Debug)]
+pub struct RelayConfig {
+    /// Url of the relay
+    pub url: String,
+    /// Timeout of requests to the relay
+    pub timeout: Duration,
+    /// Extra headers sent with every request, by name
+    pub headers: Vec<(String, String)>,
+}
+
+impl RelayConfig {
+    /// Configuration of a relay at the given url
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            timeout: DEFAULT_REQUEST_TIMEOUT,
+            headers: vec![],
+        }
+    }
+
+    /// Configuration of the Flashbots relay of the given chain
+    pub fn for_chain(chain: Chain) -> Result<Self, ClientError> {
         let url = match chain {
             Chain::Mainnet => "https://relay.flashbots.net:443",
             Chain::Goerli => "https://relay-goerli.flashbots.net:443",
-            _ => panic!("Unsupported chain"),
+            Chain::Sepolia => "https://relay-sepolia.flashbots.net:443",
+            _ => return Err(ClientError::UnsupportedChain(chain)),
         };
-        Self::from_url(signer, url)
+        Ok(Self::new(url))
+    }
+
+    /// Set the timeout of requests to the relay
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
     }
 
-    /// Create a new client with the given signer and url
-    pub fn from_url(signer: S, url: &str) -> Self {
+    /// Send an extra header with every request, e.g. to authenticate with the relay
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Helper function to validate the extra headers
+    fn header_map(&self) -> Result<HeaderMap, ClientError> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let invalid = |reason: String| ClientError::InvalidHeader {
+                name: name.clone(),
+                reason,
+            };
+            let header_name =
+                HeaderName::from_bytes(name.as_bytes()).map_err(|e| invalid(e.to_string()))?;
+            let header_value = HeaderValue::from_str(value).map_err(|e| invalid(e.to_string()))?;
+            headers.insert(header_name, header_value);
+        }
+        Ok(headers)
+    }
+}
+
+impl<S: Signer + Clone + 'static> Client<S> {
+    /// Create a new client for the Flashbots relay of the given chain
+    pub fn new(signer: S, chain: Chain) -> Result<Self, ClientError> {
+        Self::from_config(signer, RelayConfig::for_chain(chain)?)
+    }
+
+    /// Create a new client with the given signer and url, and the default configuration
+    pub fn from_url(signer: S, url: &str) -> Result<Self, ClientError> {
+        Self::from_config(signer, RelayConfig::new(url))
+    }
+
+    /// Create a new client with the given signer and relay configuration
+    pub fn from_config(signer: S, config: RelayConfig) -> Result<Self, ClientError> {
         let signing_middleware = FlashbotsSignerLayer::new(Arc::new(signer));
 
         let service_builder = ServiceBuilder::new().layer(signing_middleware);
 
         let http_client = HttpClientBuilder::default()
+            .request_timeout(config.timeout)
+            .set_headers(config.header_map()?)
             .set_middleware(service_builder)
-            .build(url)
-            .unwrap();
+            .build(&config.url)
+            .map_err(|source| ClientError::Build {
+                url: config.url.clone(),
+                source,
+            })?;
 
-        Self { http_client }
+        Ok(Self { http_client })
     }
 
     /// Send a bundle to the matchmaker
     pub async fn send_bundle(
         &self,
         bundle: &BundleRequest,
-    ) -> Result<SendBundleResponse, RpcError> {
-        self.http_client.request("mev_sendBundle", [bundle]).await
+    ) -> Result<SendBundleResponse, RelayError> {
+        Ok(self.http_client.request("mev_sendBundle", [bundle]).await?)
+    }
+
+    /// Send a bundle of signed transactions to a relay, with `eth_sendBundle`
+    pub async fn send_eth_bundle(
+        &self,
+        bundle: &EthBundleRequest,
+    ) -> Result<SendBundleResponse, RelayError> {
+        Ok(self.http_client.request("eth_sendBundle", [bundle]).await?)
+    }
+
+    /// Cancel the bundle sent with a replacement ID
+    pub async fn cancel_bundle(&self, replacement_uuid: Uuid) -> Result<(), RelayError> {
+        self.http_client
+            .request::<serde_json::Value, _>(
+                "eth_cancelBundle",
+                [CancelBundleRequest { replacement_uuid }],
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Simulate a bundle, with overrides of the block it is simulated in
+    pub async fn sim_bundle(
+        &self,
+        bundle: &BundleRequest,
+        overrides: &SimBundleOverrides,
+    ) -> Result<SimBundleResponse, RelayError> {
+        Ok(self
+            .http_client
+            .request("mev_simBundle", rpc_params![bundle, overrides])
+            .await?)
+    }
+
+    /// Send a signed transaction privately to builders. Returns the transaction hash.
+    pub async fn send_private_transaction(
+        &self,
+        request: &PrivateTransactionRequest,
+    ) -> Result<H256, RelayError> {
+        Ok(self
+            .http_client
+            .request("eth_sendPrivateTransaction", [request])
+            .await?)
+    }
+
+    /// Stop sending a private transaction. Returns whether it was cancelled.
+    pub async fn cancel_private_transaction(&self, tx_hash: H256) -> Result<bool, RelayError> {
+        Ok(self
+            .http_client
+            .request(
+                "eth_cancelPrivateTransaction",
+                [CancelPrivateTransactionRequest { tx_hash }],
+            )
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::BundleTx;
+    use ethers::{
+        signers::LocalWallet,
+        types::{Bytes, U64},
+    };
+    use test_support::mock_relay::{MockRelay, MockResponse};
+
+    #[test]
+    fn builds_clients_without_panicking() {
+        let signer = LocalWallet::new(&mut ethers::core::rand::thread_rng());
+        for chain in [Chain::Mainnet, Chain::Goerli, Chain::Sepolia] {
+            assert!(Client::new(signer.clone(), chain).is_ok());
+        }
+        assert!(matches!(
+            Client::new(signer.clone(), Chain::Optimism),
+            Err(ClientError::UnsupportedChain(Chain::Optimism))
+        ));
+        assert!(matches!(
+            Client::from_config(signer.clone(), RelayConfig::new("not a url")),
+            Err(ClientError::Build { .. })
+        ));
+
+        let config = RelayConfig::new("http://127.0.0.1:8545")
+            .with_timeout(Duration::from_secs(2))
+            .with_header("X-Api-Key", "key");
+        assert!(Client::from_config(signer.clone(), config).is_ok());
+        let config =
+            RelayConfig::new("http://127.0.0.1:8545").with_header("X-Api-Key", "bad\nvalue");
+        assert!(matches!(
+            Client::from_config(signer, config),
+            Err(ClientError::InvalidHeader { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn sends_signed_bundles() {
+        let relay = MockRelay::spawn().await;
+        let signer = LocalWallet::new(&mut ethers::core::rand::thread_rng());
+        let config = RelayConfig::new(relay.url());
+        let client = Client::from_config(signer.clone(), config).unwrap();
+
+        let tx = BundleTx::Tx {
+            tx: Bytes::from(vec![0x02, 0xf8]),
+            can_revert: false,
+        };
+        let bundle = BundleRequest::make_simple(U64::from(100), vec![tx]);
+        client.send_bundle(&bundle).await.unwrap();
+
+        let requests = relay.requests();
+        assert_eq!(requests[0].method, "mev_sendBundle");
+        assert_eq!(requests[0].signer, Some(signer.address()));
+        assert_eq!(relay.bundles()[0]["body"][0]["tx"], "0x02f8");
+    }
+
+    #[tokio::test]
+    async fn decodes_scripted_errors() {
+        let relay = MockRelay::spawn().await;
+        let signer = LocalWallet::new(&mut ethers::core::rand::thread_rng());
+        let config = RelayConfig::new(relay.url());
+        let client = Client::from_config(signer, config).unwrap();
+        let bundle = BundleRequest::make_simple(U64::from(100), vec![]);
+
+        relay.respond_once(
+            "mev_sendBundle",
+            MockResponse::Error {
+                code: -32000,
+                message: "block number too old".to_string(),
+            },
+        );
+        relay.respond_once("mev_sendBundle", MockResponse::HttpStatus(429));
+        assert!(matches!(
+            client.send_bundle(&bundle).await,
+            Err(RelayError::BlockTooOld(_))
+        ));
+        assert!(matches!(
+            client.send_bundle(&bundle).await,
+            Err(RelayError::RateLimited(_))
+        ));
+        assert!(client.send_bundle(&bundle).await.is_ok());
+        assert!(matches!(
+            client.cancel_private_transaction(H256::zero()).await,
+            Err(RelayError::UnknownMethod(_))
+        ));
     }
 }
@@ -0,0 +1,208 @@
+use ethers::types::Chain;
+use jsonrpsee::core::Error as RpcError;
+use jsonrpsee::http_client::transport::Error as TransportError;
+use thiserror::Error;
+
+/// JSON-RPC error code for methods the relay doesn't implement.
+const METHOD_NOT_FOUND_CODE: i32 = -32601;
+
+/// Error creating a client.
+#[derive(Debug, Error)]
+pub enum ClientError {
+    /// There is no relay preset for the chain.
+    #[error("no relay preset for chain {0}, configure the relay url instead")]
+    UnsupportedChain(Chain),
+    /// An extra header has an invalid name or value.
+    #[error("invalid header {name}: {reason}")]
+    InvalidHeader {
+        /// Name of the header.
+        name: String,
+        /// Why the header is invalid.
+        reason: String,
+    },
+    /// The HTTP client couldn't be built, e.g. because of an invalid url.
+    #[error("failed to build a client for {url}: {source}")]
+    Build {
+        /// Url of the relay.
+        url: String,
+        /// Why the client couldn't be built.
+        source: RpcError,
+    },
+}
+
+/// Error returned by a relay, decoded from its JSON-RPC error codes and messages.
+#[derive(Debug, Error)]
+pub enum RelayError {
+    /// Too many requests were sent to the relay.
+    #[error("rate limited by the relay: {0}")]
+    RateLimited(String),
+    /// The relay rejected the `X-Flashbots-Signature` of the request.
+    #[error("invalid signature: {0}")]
+    InvalidSignature(String),
+    /// The block the bundle targets has already passed.
+    #[error("bundle block is too old: {0}")]
+    BlockTooOld(String),
+    /// The block the bundle targets is too far in the future.
+    #[error("bundle block is too far in the future: {0}")]
+    FutureBlock(String),
+    /// A transaction of the bundle couldn't be decoded.
+    #[error("malformed transaction: {0}")]
+    MalformedTx(String),
+    /// The relay doesn't implement the method.
+    #[error("unknown method: {0}")]
+    UnknownMethod(String),
+    /// Any other error returned by the relay.
+    #[error("relay error ({code}): {message}")]
+    Rpc {
+        /// JSON-RPC error code.
+        code: i32,
+        /// JSON-RPC error message.
+        message: String,
+    },
+    /// The request didn't get a valid response from the relay.
+    #[error(transparent)]
+    Transport(RpcError),
+}
+
+impl RelayError {
+    /// Whether sending the request again may succeed: the relay rate limited it, timed
+    /// out, couldn't be reached or failed with a server error. Errors in the request or
+    /// the response, such as invalid params or parse errors, aren't retryable.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited(_) => true,
+            Self::Transport(RpcError::RequestTimeout | RpcError::RestartNeeded(_)) => true,
+            Self::Transport(RpcError::Transport(transport)) => matches!(
+                transport.downcast_ref::<TransportError>(),
+                None | Some(TransportError::Http(_))
+                    | Some(TransportError::RequestFailure { status_code: 500.. })
+            ),
+            _ => false,
+        }
+    }
+
+    /// Whether the relay rejected the credentials of the request, which retrying won't fix.
+    pub fn is_auth_error(&self) -> bool {
+        matches!(self, Self::InvalidSignature(_))
+    }
+
+    /// Decode a JSON-RPC error returned by the relay. Relays share few error codes, so
+    /// most errors are recognized by their message.
+    pub fn from_rpc_error(code: i32, message: String) -> Self {
+        let lowercase = message.to_lowercase();
+        if code == METHOD_NOT_FOUND_CODE || lowercase.contains("method not found") {
+            Self::UnknownMethod(message)
+        } else if code == 429
+            || lowercase.contains("rate limit")
+            || lowercase.contains("too many requests")
+        {
+            Self::RateLimited(message)
+        } else if lowercase.contains("decode")
+            || lowercase.contains("malformed")
+            || lowercase.contains("invalid transaction")
+            || lowercase.contains("rlp")
+        {
+            // Checked before signatures, as a transaction can have an invalid signature.
+            Self::MalformedTx(message)
+        } else if lowercase.contains("signature") {
+            Self::InvalidSignature(message)
+        } else if lowercase.contains("too old")
+            || lowercase.contains("in the past")
+            || lowercase.contains("block has passed")
+        {
+            Self::BlockTooOld(message)
+        } else if lowercase.contains("future") || lowercase.contains("too far") {
+            Self::FutureBlock(message)
+        } else {
+            Self::Rpc { code, message }
+        }
+    }
+}
+
+impl From<RpcError> for RelayError {
+    fn from(error: RpcError) -> Self {
+        match error {
+            RpcError::Call(error) => {
+                Self::from_rpc_error(error.code(), error.message().to_string())
+            }
+            RpcError::Transport(ref transport) => {
+                match transport.downcast_ref::<TransportError>() {
+                    Some(TransportError::RequestFailure { status_code: 429 }) => {
+                        Self::RateLimited(error.to_string())
+                    }
+                    Some(TransportError::RequestFailure {
+                        status_code: 401 | 403,
+                    }) => Self::InvalidSignature(error.to_string()),
+                    _ => Self::Transport(error),
+                }
+            }
+            error => Self::Transport(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::types::ErrorObject;
+
+    #[test]
+    fn decodes_relay_errors() {
+        let decode = |code, message: &str| RelayError::from_rpc_error(code, message.to_string());
+        assert!(matches!(
+            decode(-32601, "the method mev_foo does not exist/is not available"),
+            RelayError::UnknownMethod(_)
+        ));
+        assert!(decode(-32000, "rate limit exceeded").is_retryable());
+        assert!(decode(-32000, "Too Many Requests").is_retryable());
+        assert!(decode(429, "slow down").is_retryable());
+        assert!(!decode(-32000, "too many transactions in bundle").is_retryable());
+        assert!(decode(-32600, "error in signature check 0xabc").is_auth_error());
+        assert!(matches!(
+            decode(-32000, "block number too old"),
+            RelayError::BlockTooOld(_)
+        ));
+        assert!(matches!(
+            decode(-32000, "block number too far in the future"),
+            RelayError::FutureBlock(_)
+        ));
+        assert!(matches!(
+            decode(-32602, "unable to decode txs"),
+            RelayError::MalformedTx(_)
+        ));
+        assert!(matches!(
+            decode(-32000, "invalid transaction: invalid signature"),
+            RelayError::MalformedTx(_)
+        ));
+        assert!(matches!(
+            decode(-32602, "unable to decode signature of tx"),
+            RelayError::MalformedTx(_)
+        ));
+        assert!(matches!(
+            decode(-32000, "internal error"),
+            RelayError::Rpc { code: -32000, .. }
+        ));
+    }
+
+    #[test]
+    fn decodes_rpc_errors() {
+        let error = RpcError::Call(ErrorObject::owned(-32601, "method not found", None::<()>));
+        assert!(matches!(
+            RelayError::from(error),
+            RelayError::UnknownMethod(_)
+        ));
+
+        let error = RpcError::Transport(TransportError::RequestFailure { status_code: 403 }.into());
+        assert!(RelayError::from(error).is_auth_error());
+        assert!(RelayError::from(RpcError::RequestTimeout).is_retryable());
+
+        let error = RpcError::Transport(TransportError::RequestFailure { status_code: 503 }.into());
+        assert!(RelayError::from(error).is_retryable());
+        let error = RpcError::Transport(TransportError::RequestFailure { status_code: 400 }.into());
+        assert!(!RelayError::from(error).is_retryable());
+        let error = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        assert!(!RelayError::from(RpcError::ParseError(error)).is_retryable());
+        let error = RpcError::Call(ErrorObject::owned(-32602, "invalid params", None::<()>));
+        assert!(!RelayError::from(error).is_retryable());
+    }
+}
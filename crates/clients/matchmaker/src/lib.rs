@@ -25,7 +25,7 @@
 //! let tx_signer = LocalWallet::new(&mut thread_rng());
 //!
 //! // Set up the client
-//! let matchmaker_client = Client::new(fb_signer, Chain::Mainnet);
+//! let matchmaker_client = Client::new(fb_signer, Chain::Mainnet).unwrap();
 //!
 //! // Hash of the transaction we are trying to backrun
 //! let tx_hash = H256::random();
@@ -54,6 +54,8 @@
 
 /// Core client implementation
 pub mod client;
+/// Errors returned by relays
+pub mod error;
 mod flashbots_signer;
 /// Core type definitions for the client
 pub mod types;
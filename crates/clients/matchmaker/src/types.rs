@@ -1,5 +1,6 @@
-use ethers::types::{Bytes, H256, U64};
+use ethers::types::{Address, BlockNumber, Bytes, Log, H256, U256, U64};
 use serde::{Deserialize, Serialize};
+use uuid::Uuid;
 
 /// A bundle of transactions to send to the matchmaker.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
@@ -11,6 +12,19 @@ pub struct BundleRequest {
     pub inclusion: Inclusion,
     /// The transactions to include in the bundle.
     pub body: Vec<BundleTx>,
+    /// Requirements for the bundle to be included in the block.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub validity: Option<Validity>,
+    /// Preferences on what data should be shared about the bundle and its transactions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub privacy: Option<Privacy>,
+    /// Metadata about the bundle, used by the matchmaker.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<Metadata>,
+    /// ID shared by the bundles replacing each other. A bundle replaces the previous
+    /// bundle sent with the same ID, and can be cancelled with `eth_cancelBundle`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement_uuid: Option<Uuid>,
 }
 
 /// Data used by block builders to check if the bundle should be considered for inclusion.
@@ -42,6 +56,83 @@ pub enum BundleTx {
         /// If true, the transaction can revert without the bundle being considered invalid.
         can_revert: bool,
     },
+    /// A nested bundle.
+    Bundle {
+        /// The bundle, which can itself contain bundles.
+        bundle: Box<BundleRequest>,
+    },
+}
+
+/// Requirements for the bundle to be included in the block.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Validity {
+    /// Refunds owed to the senders of the transactions in the bundle body, which the
+    /// backrunners of the bundle must pay.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub refund: Vec<Refund>,
+    /// How to split the refunds owed to the bundle sender between addresses.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub refund_config: Vec<RefundConfig>,
+}
+
+/// A refund owed to the sender of a transaction in the bundle body.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Refund {
+    /// Index of the transaction in the bundle body.
+    pub body_idx: u64,
+    /// Minimum percentage of the bundle profit to refund.
+    pub percent: u64,
+}
+
+/// Share of the refunds owed to the bundle sender, to pay to an address.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefundConfig {
+    /// Address to pay the refund to.
+    pub address: Address,
+    /// Percentage of the refund to pay to the address.
+    pub percent: u64,
+}
+
+/// Preferences on what data should be shared about the bundle and its transactions.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Privacy {
+    /// Data about the bundle to share with searchers. Nothing is shared if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hints: Vec<PrivacyHint>,
+    /// Names of the builders the bundle may be sent to. Only Flashbots if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub builders: Vec<String>,
+}
+
+/// Data about a bundle which can be shared with searchers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PrivacyHint {
+    /// The calldata of the transactions.
+    Calldata,
+    /// The contract addresses the transactions call.
+    ContractAddress,
+    /// The logs emitted by the transactions.
+    Logs,
+    /// The function selectors of the transactions.
+    FunctionSelector,
+    /// The hash of the bundle, or of the transaction for single transaction bundles.
+    Hash,
+    /// The hashes of the transactions.
+    TxHash,
+}
+
+/// Metadata about the bundle, used by the matchmaker.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Metadata {
+    /// Identifies where the bundle comes from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin_id: Option<String>,
 }
 
 /// Response from the matchmaker after sending a bundle.
@@ -49,7 +140,134 @@ pub enum BundleTx {
 #[serde(rename_all = "camelCase")]
 pub struct SendBundleResponse {
     /// Hash of the bundle bodies.
-    bundle_hash: H256,
+    pub bundle_hash: H256,
+}
+
+/// A bundle of signed transactions to send to a relay, with `eth_sendBundle`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EthBundleRequest {
+    /// Bytes of the signed transactions.
+    pub txs: Vec<Bytes>,
+    /// The block the bundle is valid for.
+    pub block_number: U64,
+    /// The earliest timestamp the bundle is valid at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_timestamp: Option<u64>,
+    /// The latest timestamp the bundle is valid at.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_timestamp: Option<u64>,
+    /// Hashes of the transactions which can revert without the bundle being considered
+    /// invalid.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub reverting_tx_hashes: Vec<H256>,
+    /// ID shared by the bundles replacing each other. A bundle replaces the previous
+    /// bundle sent with the same ID, and can be cancelled with `eth_cancelBundle`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub replacement_uuid: Option<Uuid>,
+}
+
+/// Request to cancel the bundle sent with a replacement ID, with `eth_cancelBundle`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelBundleRequest {
+    /// Replacement ID of the bundle to cancel.
+    pub replacement_uuid: Uuid,
+}
+
+/// Overrides of the block a bundle is simulated in, with `mev_simBundle`. The
+/// simulation runs on top of the latest block when unset.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimBundleOverrides {
+    /// The block to simulate on top of.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_block: Option<BlockNumber>,
+    /// Number of the simulated block. Defaults to the parent block number + 1.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<U64>,
+    /// Coinbase of the simulated block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coinbase: Option<Address>,
+    /// Timestamp of the simulated block. Defaults to the parent block timestamp + 12.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<U64>,
+    /// Gas limit of the simulated block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<U64>,
+    /// Base fee of the simulated block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_fee: Option<U256>,
+    /// Timeout of the simulation, in seconds.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+/// Response from the matchmaker after simulating a bundle.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimBundleResponse {
+    /// Whether the bundle executed without unexpected reverts.
+    pub success: bool,
+    /// Why the simulation failed, if it did.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// The block the bundle was simulated on top of.
+    pub state_block: U64,
+    /// Profit of the bundle per unit of gas.
+    pub mev_gas_price: U256,
+    /// Profit of the bundle, paid to the coinbase.
+    pub profit: U256,
+    /// Part of the profit which can be refunded to the senders of the transactions.
+    pub refundable_value: U256,
+    /// Gas used by the bundle.
+    pub gas_used: U64,
+    /// Logs emitted by the transactions of the bundle, in order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub logs: Option<Vec<SimBundleLogs>>,
+}
+
+/// Logs emitted by a bundle body item: either a transaction or a nested bundle.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimBundleLogs {
+    /// Logs of a transaction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_logs: Option<Vec<Log>>,
+    /// Logs of the items of a nested bundle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_logs: Option<Vec<SimBundleLogs>>,
+}
+
+/// A signed transaction to send privately to builders, with `eth_sendPrivateTransaction`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivateTransactionRequest {
+    /// Bytes of the signed transaction.
+    pub tx: Bytes,
+    /// The last block the transaction can be included in. Defaults to 25 blocks after
+    /// the current block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_block_number: Option<U64>,
+    /// Preferences on how the transaction is sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub preferences: Option<PrivateTransactionPreferences>,
+}
+
+/// Preferences on how a private transaction is sent.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrivateTransactionPreferences {
+    /// Send the transaction to all registered builders instead of only to Flashbots.
+    pub fast: bool,
+}
+
+/// Request to stop sending a private transaction, with `eth_cancelPrivateTransaction`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelPrivateTransactionRequest {
+    /// Hash of the private transaction to cancel.
+    pub tx_hash: H256,
 }
 
 /// The version of the MEV-share API to use.
@@ -79,9 +297,31 @@ impl BundleRequest {
                 max_block,
             },
             body: transactions,
+            validity: None,
+            privacy: None,
+            metadata: None,
+            replacement_uuid: None,
         }
     }
 
+    /// Replace the previous bundle sent with the same replacement ID.
+    pub fn with_replacement_uuid(mut self, replacement_uuid: Uuid) -> Self {
+        self.replacement_uuid = Some(replacement_uuid);
+        self
+    }
+
+    /// Set the refunds and refund configuration of the bundle.
+    pub fn with_validity(mut self, validity: Validity) -> Self {
+        self.validity = Some(validity);
+        self
+    }
+
+    /// Set the hints shared about the bundle and the builders it is sent to.
+    pub fn with_privacy(mut self, privacy: Privacy) -> Self {
+        self.privacy = Some(privacy);
+        self
+    }
+
     /// Helper function to create a simple bundle request with sensible defaults (bundle is valid for the next 5 blocks).
     pub fn make_simple(block_num: U64, transactions: Vec<BundleTx>) -> Self {
         // bundle is valid for 5 blocks
@@ -97,7 +337,35 @@ impl BundleRequest {
 
 #[cfg(test)]
 mod tests {
-    use crate::types::BundleRequest;
+    use crate::types::{
+        BundleRequest, BundleTx, CancelBundleRequest, EthBundleRequest, PrivacyHint,
+        PrivateTransactionPreferences, PrivateTransactionRequest, SimBundleOverrides,
+        SimBundleResponse,
+    };
+    use ethers::types::{BlockNumber, Bytes, U256, U64};
+    use serde_json::Value;
+    use uuid::Uuid;
+
+    /// Check that the bundles deserialize, and serialize back to the same JSON.
+    fn assert_round_trip(str: &str) -> Vec<BundleRequest> {
+        let bundles: Vec<BundleRequest> = serde_json::from_str(str).unwrap();
+        let expected: Value = serde_json::from_str(str).unwrap();
+        assert_eq!(serde_json::to_value(&bundles).unwrap(), expected);
+        bundles
+    }
+
+    #[test]
+    fn can_serialize_private_transaction() {
+        let request = PrivateTransactionRequest {
+            tx: Bytes::from(vec![0x02, 0xf8]),
+            max_block_number: Some(U64::from(100)),
+            preferences: Some(PrivateTransactionPreferences { fast: true }),
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"tx":"0x02f8","maxBlockNumber":"0x64","preferences":{"fast":true}}"#
+        );
+    }
 
     #[test]
     fn can_deserialize() {
@@ -116,4 +384,159 @@ mod tests {
         let res: Result<Vec<BundleRequest>, _> = serde_json::from_str(str);
         assert!(res.is_ok());
     }
+
+    #[test]
+    fn can_round_trip_full_bundle() {
+        let str = r#"
+        [{
+            "version": "v0.1",
+            "inclusion": {
+                "block": "0x8b8da8",
+                "maxBlock": "0x8b8dab"
+            },
+            "body": [
+                {
+                    "hash": "0xe5a9b9b33c2cd5b0f1b8b7f4a0e01d31e1c4a28f3a9b1e0e4b8b2c0c5c1b0a9d"
+                },
+                {
+                    "tx": "0x02f86b0180843b9aca00852ecc889a0082520894c87037874aed04e51c29f582394217a0a2b89d808080c080a0a463985c616dd8ee17d7ef9112af4e6e06a27b071525b42182fe7b0b5c8b4925a00af5ca177ffef2ff28449292505d41be578bebb77110dfc09361d2fb56998260",
+                    "canRevert": false
+                }
+            ],
+            "validity": {
+                "refund": [{ "bodyIdx": 0, "percent": 90 }],
+                "refundConfig": [
+                    { "address": "0xd77f375a33b1109e82f3c46a30537f1e019708eb", "percent": 100 }
+                ]
+            },
+            "privacy": {
+                "hints": ["calldata", "contract_address", "logs", "function_selector", "hash", "tx_hash"],
+                "builders": ["flashbots", "rsync"]
+            },
+            "metadata": {
+                "originId": "artemis"
+            }
+        }]
+        "#;
+        let bundles = assert_round_trip(str);
+        let privacy = bundles[0].privacy.as_ref().unwrap();
+        assert_eq!(privacy.hints[1], PrivacyHint::ContractAddress);
+        assert_eq!(bundles[0].validity.as_ref().unwrap().refund[0].percent, 90);
+    }
+
+    #[test]
+    fn can_round_trip_nested_bundle() {
+        let str = r#"
+        [{
+            "version": "v0.1",
+            "inclusion": {
+                "block": "0x8b8da8"
+            },
+            "body": [
+                {
+                    "bundle": {
+                        "version": "v0.1",
+                        "inclusion": {
+                            "block": "0x8b8da8"
+                        },
+                        "body": [{
+                            "hash": "0xe5a9b9b33c2cd5b0f1b8b7f4a0e01d31e1c4a28f3a9b1e0e4b8b2c0c5c1b0a9d"
+                        }],
+                        "validity": {
+                            "refund": [{ "bodyIdx": 0, "percent": 50 }]
+                        }
+                    }
+                },
+                {
+                    "tx": "0x02f8",
+                    "canRevert": true
+                }
+            ],
+            "privacy": {
+                "hints": ["hash"]
+            }
+        }]
+        "#;
+        let bundles = assert_round_trip(str);
+        assert!(
+            matches!(&bundles[0].body[0], BundleTx::Bundle { bundle } if bundle.body.len() == 1)
+        );
+    }
+
+    #[test]
+    fn can_serialize_sim_overrides() {
+        let overrides = SimBundleOverrides {
+            parent_block: Some(BlockNumber::Number(U64::from(100))),
+            timestamp: Some(U64::from(1_690_000_000)),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&overrides).unwrap(),
+            r#"{"parentBlock":"0x64","timestamp":"0x64bb5a80"}"#
+        );
+    }
+
+    #[test]
+    fn can_deserialize_sim_response() {
+        let str = r#"
+        {
+            "success": true,
+            "stateBlock": "0x8b8da8",
+            "mevGasPrice": "0x74c7906005",
+            "profit": "0x4bc800904fc000",
+            "refundableValue": "0x4bc800904fc000",
+            "gasUsed": "0xa620",
+            "logs": [
+                {},
+                {
+                    "bundleLogs": [{
+                        "txLogs": [{
+                            "address": "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+                            "topics": [
+                                "0xe1fffcc4923d04b559f4d29a8bfc6cda04eb5b0d3c460751c2402c5c5cc9109c"
+                            ],
+                            "data": "0x00000000000000000000000000000000000000000000000000000000000003e8"
+                        }]
+                    }]
+                }
+            ]
+        }
+        "#;
+        let res: SimBundleResponse = serde_json::from_str(str).unwrap();
+        assert!(res.success);
+        assert_eq!(res.profit, U256::from(0x4bc800904fc000u64));
+        assert_eq!(res.gas_used, U64::from(0xa620));
+        let logs = res.logs.unwrap();
+        let nested = logs[1].bundle_logs.as_ref().unwrap();
+        assert_eq!(nested[0].tx_logs.as_ref().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn can_serialize_replacement_uuids() {
+        let uuid = Uuid::parse_str("3f7c5b5e-4b5c-4d8a-9a4e-1b2c3d4e5f60").unwrap();
+        let bundle = EthBundleRequest {
+            txs: vec![Bytes::from(vec![0x02, 0xf8])],
+            block_number: U64::from(100),
+            replacement_uuid: Some(uuid),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_string(&bundle).unwrap(),
+            r#"{"txs":["0x02f8"],"blockNumber":"0x64","replacementUuid":"3f7c5b5e-4b5c-4d8a-9a4e-1b2c3d4e5f60"}"#
+        );
+        assert_eq!(
+            serde_json::to_string(&CancelBundleRequest {
+                replacement_uuid: uuid
+            })
+            .unwrap(),
+            r#"{"replacementUuid":"3f7c5b5e-4b5c-4d8a-9a4e-1b2c3d4e5f60"}"#
+        );
+
+        let bundle = BundleRequest::make_simple(U64::from(100), vec![]).with_replacement_uuid(uuid);
+        let bundle = serde_json::to_value(bundle).unwrap();
+        assert_eq!(
+            bundle["replacementUuid"],
+            "3f7c5b5e-4b5c-4d8a-9a4e-1b2c3d4e5f60"
+        );
+    }
 }
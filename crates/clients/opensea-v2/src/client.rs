@@ -1,17 +1,25 @@
+use ethers::types::{Chain, H160};
 use reqwest::{
     header::{self, HeaderMap},
-    Client, ClientBuilder,
+    Client, ClientBuilder, RequestBuilder,
 };
+use serde::de::DeserializeOwned;
 
 use crate::{
-    constants::FULFILL_LISTING_URL,
-    types::{FulfillListingRequest, FulfillListingResponse, OpenSeaApiError},
+    constants::OPENSEA_API_URL,
+    types::{
+        chain_name, CollectionStats, ErrorResponse, FulfillListingRequest, FulfillListingResponse,
+        FulfillOfferRequest, FulfillOfferResponse, ListingsResponse, NftResponse, OffersResponse,
+        OpenSeaApiError, OrdersResponse, PostOrderRequest, PostOrderResponse,
+    },
 };
 
-//. A partial implementation of the OpenSea API v2, supporting the fulfill listing endpoint.
+/// A client for the OpenSea API v2, covering the endpoints used to find, fulfill and
+/// post orders.
 #[derive(Debug, Clone)]
 pub struct OpenSeaV2Client {
     client: Client,
+    base_url: String,
 }
 
 /// Configuration for the OpenSea API client.
@@ -33,7 +41,16 @@ impl OpenSeaV2Client {
         builder = builder.default_headers(headers);
         let client = builder.build().unwrap();
 
-        Self { client }
+        Self {
+            client,
+            base_url: OPENSEA_API_URL.to_string(),
+        }
+    }
+
+    /// Send requests to the given url instead of the OpenSea API, e.g. a proxy.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
     }
 
     /// Call the fulfill listing endpoint, which returns the arguments necessary
@@ -42,23 +59,163 @@ impl OpenSeaV2Client {
         &self,
         req: FulfillListingRequest,
     ) -> Result<FulfillListingResponse, OpenSeaApiError> {
-        let res = self
-            .client
-            .post(FULFILL_LISTING_URL)
-            .json(&req)
-            .send()
-            .await?
-            .json::<FulfillListingResponse>()
-            .await?;
-        Ok(res)
+        let url = format!("{}/listings/fulfillment_data", self.base_url);
+        send(self.client.post(url).json(&req)).await
+    }
+
+    /// Get the cheapest listings of a collection, starting at the `next` cursor.
+    pub async fn get_best_listings(
+        &self,
+        collection_slug: &str,
+        limit: Option<u32>,
+        next: Option<&str>,
+    ) -> Result<ListingsResponse, OpenSeaApiError> {
+        let url = format!(
+            "{}/listings/collection/{}/best",
+            self.base_url, collection_slug
+        );
+        let req = self.client.get(url).query(&page_query(limit, next));
+        send(req).await
+    }
+
+    /// Get all valid offers on a collection, including collection and trait offers,
+    /// starting at the `next` cursor.
+    pub async fn get_all_offers(
+        &self,
+        collection_slug: &str,
+        limit: Option<u32>,
+        next: Option<&str>,
+    ) -> Result<OffersResponse, OpenSeaApiError> {
+        let url = format!(
+            "{}/offers/collection/{}/all",
+            self.base_url, collection_slug
+        );
+        let req = self.client.get(url).query(&page_query(limit, next));
+        send(req).await
+    }
+
+    /// Get all listings of an NFT.
+    pub async fn get_nft_listings(
+        &self,
+        chain: Chain,
+        contract: H160,
+        token_id: &str,
+    ) -> Result<OrdersResponse, OpenSeaApiError> {
+        let url = format!(
+            "{}/orders/{}/seaport/listings",
+            self.base_url,
+            chain_name(chain)?
+        );
+        let req = self.client.get(url).query(&[
+            ("asset_contract_address", format!("{:?}", contract)),
+            ("token_ids", token_id.to_string()),
+        ]);
+        send(req).await
+    }
+
+    /// Call the fulfill offer endpoint, which returns the arguments necessary to
+    /// fulfill an offer onchain by selling an NFT.
+    pub async fn fulfill_offer(
+        &self,
+        req: FulfillOfferRequest,
+    ) -> Result<FulfillOfferResponse, OpenSeaApiError> {
+        let url = format!("{}/offers/fulfillment_data", self.base_url);
+        send(self.client.post(url).json(&req)).await
+    }
+
+    /// Get the stats of a collection, such as its floor price and volume.
+    pub async fn get_collection_stats(
+        &self,
+        collection_slug: &str,
+    ) -> Result<CollectionStats, OpenSeaApiError> {
+        let url = format!("{}/collections/{}/stats", self.base_url, collection_slug);
+        send(self.client.get(url)).await
+    }
+
+    /// Get the metadata, traits and owners of an NFT.
+    pub async fn get_nft(
+        &self,
+        chain: Chain,
+        contract: H160,
+        token_id: &str,
+    ) -> Result<NftResponse, OpenSeaApiError> {
+        let url = format!(
+            "{}/chain/{}/contract/{:?}/nfts/{}",
+            self.base_url,
+            chain_name(chain)?,
+            contract,
+            token_id
+        );
+        send(self.client.get(url)).await
+    }
+
+    /// Post a listing signed with [`sign_order`](crate::seaport::sign_order).
+    pub async fn post_listing(
+        &self,
+        chain: Chain,
+        req: PostOrderRequest,
+    ) -> Result<PostOrderResponse, OpenSeaApiError> {
+        let url = format!(
+            "{}/orders/{}/seaport/listings",
+            self.base_url,
+            chain_name(chain)?
+        );
+        send(self.client.post(url).json(&req)).await
+    }
+
+    /// Post an offer signed with [`sign_order`](crate::seaport::sign_order).
+    pub async fn post_offer(
+        &self,
+        chain: Chain,
+        req: PostOrderRequest,
+    ) -> Result<PostOrderResponse, OpenSeaApiError> {
+        let url = format!(
+            "{}/orders/{}/seaport/offers",
+            self.base_url,
+            chain_name(chain)?
+        );
+        send(self.client.post(url).json(&req)).await
+    }
+}
+
+/// Helper function to build the query of a paginated endpoint.
+fn page_query(limit: Option<u32>, next: Option<&str>) -> Vec<(&'static str, String)> {
+    let mut query = vec![];
+    if let Some(limit) = limit {
+        query.push(("limit", limit.to_string()));
+    }
+    if let Some(next) = next {
+        query.push(("next", next.to_string()));
+    }
+    query
+}
+
+/// Helper function to send a request, decoding the errors returned by the API.
+async fn send<T: DeserializeOwned>(req: RequestBuilder) -> Result<T, OpenSeaApiError> {
+    let res = req.send().await?;
+    let status = res.status();
+    if status.is_success() {
+        return Ok(res.json::<T>().await?);
     }
+    let body = res.text().await?;
+    let errors = match serde_json::from_str::<ErrorResponse>(&body) {
+        Ok(res) if !res.errors.is_empty() => res.errors,
+        _ => vec![body],
+    };
+    Err(OpenSeaApiError::Api {
+        status: status.as_u16(),
+        errors,
+    })
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use crate::types::{Fulfiller, Listing, Offer, OfferConsideration, ProtocolVersion};
+    use ethers::types::{Bytes, H256, U256};
     use std::path::PathBuf;
+    use test_support::mock_http::{HttpRequest, MockHttp};
 
     #[test]
     fn can_deserialize_response() {
@@ -81,4 +238,246 @@ mod tests {
         assert_eq!(res.protocol, "seaport1.5");
         assert_eq!(res.fulfillment_data.transaction.value, 20000000000000000);
     }
+
+    fn read_resource(name: &str) -> String {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources");
+        d.push(name);
+        std::fs::read_to_string(d).unwrap()
+    }
+
+    #[test]
+    fn can_deserialize_best_listings() {
+        let res: ListingsResponse =
+            serde_json::from_str(&read_resource("best_listings.json")).unwrap();
+        let listing = &res.listings[0];
+        assert_eq!(
+            listing.price.current.value,
+            U256::from_dec_str("27362500000000000000").unwrap()
+        );
+        let parameters = &listing.protocol_data.parameters;
+        assert_eq!(parameters.offer[0].identifier_or_criteria, U256::from(7090));
+        assert_eq!(parameters.consideration.len(), 2);
+        assert!(listing.protocol_data.signature.is_none());
+        assert!(res.next.is_some());
+    }
+
+    #[test]
+    fn can_deserialize_collection_offers() {
+        let res: OffersResponse =
+            serde_json::from_str(&read_resource("collection_offers.json")).unwrap();
+        assert_eq!(res.offers.len(), 2);
+        let criteria = res.offers[0].criteria.as_ref().unwrap();
+        assert_eq!(criteria.collection.slug, "boredapeyachtclub");
+        assert_eq!(
+            criteria.trait_criteria.as_ref().unwrap().value,
+            "Solid Gold"
+        );
+        assert!(res.offers[1].criteria.is_none());
+        assert_eq!(
+            res.offers[0].protocol_data.parameters.consideration[0].item_type,
+            4
+        );
+        assert!(res.next.is_none());
+    }
+
+    #[test]
+    fn can_deserialize_nft_listings() {
+        let res: OrdersResponse =
+            serde_json::from_str(&read_resource("nft_listings.json")).unwrap();
+        let order = &res.orders[0];
+        assert_eq!(order.side, "ask");
+        assert_eq!(
+            order.current_price,
+            U256::from_dec_str("27362500000000000000").unwrap()
+        );
+        assert_eq!(order.remaining_quantity, 1);
+        assert!(order.protocol_data.signature.is_some());
+    }
+
+    #[test]
+    fn can_deserialize_fulfill_offer_response() {
+        let res: FulfillOfferResponse =
+            serde_json::from_str(&read_resource("fulfill_offer.json")).unwrap();
+        assert_eq!(res.protocol, "seaport1.6");
+        let input_data = &res.fulfillment_data.transaction.input_data;
+        assert_eq!(input_data.orders.len(), 2);
+        assert_eq!(input_data.orders[0].numerator, U256::one());
+        assert_eq!(
+            input_data.orders[1].parameters.salt,
+            U256::from(0x1e2f3a4b_u64)
+        );
+        assert_eq!(
+            input_data.criteria_resolvers[0].identifier,
+            U256::from(7090)
+        );
+        assert_eq!(input_data.fulfillments.len(), 2);
+    }
+
+    #[test]
+    fn can_deserialize_collection_stats() {
+        let res: CollectionStats =
+            serde_json::from_str(&read_resource("collection_stats.json")).unwrap();
+        assert_eq!(res.total.floor_price, Some(27.3625));
+        assert_eq!(res.intervals[0].interval, "one_day");
+        assert_eq!(res.intervals[0].sales_diff, -4);
+    }
+
+    #[test]
+    fn can_deserialize_nft() {
+        let res: NftResponse = serde_json::from_str(&read_resource("nft.json")).unwrap();
+        assert_eq!(res.nft.identifier, "7090");
+        assert_eq!(res.nft.traits.len(), 2);
+        assert_eq!(res.nft.traits[1].value, 2);
+        assert_eq!(res.nft.owners[0].quantity, 1);
+        assert_eq!(res.nft.rarity.unwrap().rank, Some(42));
+    }
+
+    #[test]
+    fn can_deserialize_post_order_response() {
+        let res: PostOrderResponse =
+            serde_json::from_str(&read_resource("post_order.json")).unwrap();
+        assert_eq!(res.order.listing_time, 1697000000);
+        assert!(!res.order.cancelled);
+    }
+
+    #[tokio::test]
+    async fn calls_endpoints_under_base_url() {
+        let api = MockHttp::spawn(|request: &HttpRequest| {
+            let resource = match (request.method.as_str(), request.path.as_str()) {
+                ("POST", "/api/v2/listings/fulfillment_data") => "sample_response.json",
+                ("GET", "/api/v2/listings/collection/boredapeyachtclub/best") => {
+                    "best_listings.json"
+                }
+                ("GET", "/api/v2/offers/collection/boredapeyachtclub/all") => {
+                    "collection_offers.json"
+                }
+                ("GET", "/api/v2/orders/ethereum/seaport/listings") => "nft_listings.json",
+                ("POST", "/api/v2/offers/fulfillment_data") => "fulfill_offer.json",
+                ("GET", "/api/v2/collections/boredapeyachtclub/stats") => "collection_stats.json",
+                ("GET", path) if path.starts_with("/api/v2/chain/ethereum/contract/") => "nft.json",
+                (
+                    "POST",
+                    "/api/v2/orders/ethereum/seaport/listings"
+                    | "/api/v2/orders/ethereum/seaport/offers",
+                ) => "post_order.json",
+                _ => return (404, r#"{"errors":["not found"]}"#.to_string()),
+            };
+            (200, read_resource(resource))
+        })
+        .await;
+        let client = OpenSeaV2Client::new(OpenSeaApiConfig {
+            api_key: "api-key".to_string(),
+        })
+        .with_base_url(format!("{}/api/v2/", api.url()));
+        let contract = H160::repeat_byte(0xbc);
+        let hash = H256::repeat_byte(0x11);
+        let fulfiller = Fulfiller {
+            address: H160::repeat_byte(1),
+        };
+        let post_order: PostOrderResponse =
+            serde_json::from_str(&read_resource("post_order.json")).unwrap();
+        let order = PostOrderRequest {
+            parameters: post_order.order.protocol_data.parameters,
+            signature: Bytes::from(vec![0x1b]),
+            protocol_version: ProtocolVersion::V1_6,
+        };
+
+        client
+            .fulfill_listing(FulfillListingRequest {
+                listing: Listing {
+                    hash,
+                    chain: Chain::Mainnet,
+                    protocol_version: ProtocolVersion::V1_6,
+                },
+                fulfiller: fulfiller.clone(),
+            })
+            .await
+            .unwrap();
+        client
+            .get_best_listings("boredapeyachtclub", Some(2), Some("cursor"))
+            .await
+            .unwrap();
+        client
+            .get_all_offers("boredapeyachtclub", Some(2), None)
+            .await
+            .unwrap();
+        client
+            .get_nft_listings(Chain::Mainnet, contract, "7090")
+            .await
+            .unwrap();
+        client
+            .fulfill_offer(FulfillOfferRequest {
+                offer: Offer {
+                    hash,
+                    chain: Chain::Mainnet,
+                    protocol_version: ProtocolVersion::V1_6,
+                },
+                fulfiller,
+                consideration: OfferConsideration {
+                    asset_contract_address: contract,
+                    token_id: "7090".to_string(),
+                },
+            })
+            .await
+            .unwrap();
+        client
+            .get_collection_stats("boredapeyachtclub")
+            .await
+            .unwrap();
+        client
+            .get_nft(Chain::Mainnet, contract, "7090")
+            .await
+            .unwrap();
+        client
+            .post_listing(Chain::Mainnet, order.clone())
+            .await
+            .unwrap();
+        client.post_offer(Chain::Mainnet, order).await.unwrap();
+
+        let requests = api.requests();
+        let calls: Vec<_> = requests
+            .iter()
+            .map(|request| {
+                (
+                    request.method.as_str(),
+                    request.path.as_str(),
+                    request.query.as_str(),
+                )
+            })
+            .collect();
+        let nft_listings_query = format!("asset_contract_address={:?}&token_ids=7090", contract);
+        let nft_path = format!("/api/v2/chain/ethereum/contract/{:?}/nfts/7090", contract);
+        assert_eq!(
+            calls,
+            vec![
+                ("POST", "/api/v2/listings/fulfillment_data", ""),
+                (
+                    "GET",
+                    "/api/v2/listings/collection/boredapeyachtclub/best",
+                    "limit=2&next=cursor"
+                ),
+                (
+                    "GET",
+                    "/api/v2/offers/collection/boredapeyachtclub/all",
+                    "limit=2"
+                ),
+                (
+                    "GET",
+                    "/api/v2/orders/ethereum/seaport/listings",
+                    nft_listings_query.as_str()
+                ),
+                ("POST", "/api/v2/offers/fulfillment_data", ""),
+                ("GET", "/api/v2/collections/boredapeyachtclub/stats", ""),
+                ("GET", nft_path.as_str(), ""),
+                ("POST", "/api/v2/orders/ethereum/seaport/listings", ""),
+                ("POST", "/api/v2/orders/ethereum/seaport/offers", ""),
+            ]
+        );
+        assert!(requests
+            .iter()
+            .all(|request| request.header("x-api-key") == Some("api-key")));
+        let listing: serde_json::Value = serde_json::from_str(&requests[0].body).unwrap();
+        assert_eq!(listing["listing"]["hash"], serde_json::json!(hash));
+    }
 }
@@ -7,5 +7,8 @@ pub const SEAPORT_V4: &str = "0x00000000000001ad428e4906aE43D8F9852d0dD6";
 /// Address for the Seaport V5 contract.
 pub const SEAPORT_V5: &str = "0x00000000000000ADc04C56Bf30aC9d3c0aAF14dC";
 
-/// URL for the fulfill listing endpoint on the OpenSea API.
-pub const FULFILL_LISTING_URL: &str = "https://api.opensea.io/v2/listings/fulfillment_data";
+/// Address for the Seaport V6 contract.
+pub const SEAPORT_V6: &str = "0x0000000000000068F116a894984e2DB1123eB395";
+
+/// URL of the OpenSea API v2.
+pub const OPENSEA_API_URL: &str = "https://api.opensea.io/api/v2";
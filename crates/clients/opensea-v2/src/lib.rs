@@ -4,10 +4,12 @@
     no_crate_inject,
     attr(deny(warnings, rust_2018_idioms), allow(dead_code, unused_variables))
 ))]
-//! A partial implementation of the Opensea V2 API, supporting the
+//! An implementation of the Opensea V2 API, supporting the
 //! [fulfill listing endpoint](https://docs.opensea.io/reference/fulfill-a-listing).
 //! This endpoint is useful for taker stragegies, as it provides the arguments
-//! necessary to fulfill orders onchain.
+//! necessary to fulfill orders onchain. The client also covers fulfilling offers,
+//! fetching listings, offers, collection stats and NFT metadata, and posting
+//! listings and offers signed with Seaport.
 
 /// This module contains the core client implementation.
 pub mod client;
@@ -15,5 +17,8 @@ pub mod client;
 /// This module contains constants used by the client.
 mod constants;
 
+/// This module implements signing of Seaport orders.
+pub mod seaport;
+
 /// This module contains the core type definitions for the client.
 pub mod types;
@@ -0,0 +1,251 @@
+use std::{convert::Infallible, str::FromStr};
+
+use ethers::{
+    abi::{encode, Token},
+    signers::Signer,
+    types::{
+        transaction::eip712::{EIP712Domain, Eip712},
+        Bytes, Chain, H160, U256,
+    },
+    utils::keccak256,
+};
+
+use crate::types::{
+    ConsiderationItem, OfferItem, OpenSeaApiError, OrderParameters, PostOrderRequest,
+    ProtocolVersion,
+};
+
+/// EIP-712 type of a Seaport order, followed by the types it references in alphabetical order.
+const ORDER_COMPONENTS_TYPE: &str = "OrderComponents(address offerer,address zone,OfferItem[] offer,ConsiderationItem[] consideration,uint8 orderType,uint256 startTime,uint256 endTime,bytes32 zoneHash,uint256 salt,bytes32 conduitKey,uint256 counter)";
+const CONSIDERATION_ITEM_TYPE: &str = "ConsiderationItem(uint8 itemType,address token,uint256 identifierOrCriteria,uint256 startAmount,uint256 endAmount,address recipient)";
+const OFFER_ITEM_TYPE: &str = "OfferItem(uint8 itemType,address token,uint256 identifierOrCriteria,uint256 startAmount,uint256 endAmount)";
+
+/// A Seaport order to sign, with the Seaport deployment it is signed for.
+#[derive(Clone, Debug)]
+pub struct SeaportOrder {
+    pub parameters: OrderParameters,
+    pub protocol_version: ProtocolVersion,
+    pub chain_id: u64,
+}
+
+impl Eip712 for SeaportOrder {
+    type Error = Infallible;
+
+    fn domain(&self) -> Result<EIP712Domain, Self::Error> {
+        Ok(EIP712Domain {
+            name: Some("Seaport".to_string()),
+            version: Some(self.protocol_version.eip712_version().to_string()),
+            chain_id: Some(U256::from(self.chain_id)),
+            verifying_contract: Some(H160::from_str(self.protocol_version.address()).unwrap()),
+            salt: None,
+        })
+    }
+
+    fn type_hash() -> Result<[u8; 32], Self::Error> {
+        Ok(keccak256(format!(
+            "{}{}{}",
+            ORDER_COMPONENTS_TYPE, CONSIDERATION_ITEM_TYPE, OFFER_ITEM_TYPE
+        )))
+    }
+
+    fn struct_hash(&self) -> Result<[u8; 32], Self::Error> {
+        let parameters = &self.parameters;
+        let offer: Vec<u8> = parameters.offer.iter().flat_map(offer_item_hash).collect();
+        let consideration: Vec<u8> = parameters
+            .consideration
+            .iter()
+            .flat_map(consideration_item_hash)
+            .collect();
+        Ok(keccak256(encode(&[
+            Token::FixedBytes(Self::type_hash()?.to_vec()),
+            Token::Address(parameters.offerer),
+            Token::Address(parameters.zone),
+            Token::FixedBytes(keccak256(offer).to_vec()),
+            Token::FixedBytes(keccak256(consideration).to_vec()),
+            Token::Uint(U256::from(parameters.order_type)),
+            Token::Uint(parameters.start_time),
+            Token::Uint(parameters.end_time),
+            Token::FixedBytes(parameters.zone_hash.as_bytes().to_vec()),
+            Token::Uint(parameters.salt),
+            Token::FixedBytes(parameters.conduit_key.as_bytes().to_vec()),
+            Token::Uint(parameters.counter),
+        ])))
+    }
+}
+
+/// Sign a Seaport order, returning the request posting it to OpenSea. The order must
+/// be offered by the signer, with their current counter on Seaport.
+pub async fn sign_order<S: Signer>(
+    signer: &S,
+    parameters: OrderParameters,
+    protocol_version: ProtocolVersion,
+    chain: Chain,
+) -> Result<PostOrderRequest, OpenSeaApiError> {
+    let order = SeaportOrder {
+        parameters,
+        protocol_version,
+        chain_id: chain.into(),
+    };
+    let signature = signer
+        .sign_typed_data(&order)
+        .await
+        .map_err(|e| OpenSeaApiError::Signing(e.to_string()))?;
+    Ok(PostOrderRequest {
+        parameters: order.parameters,
+        signature: Bytes::from(signature.to_vec()),
+        protocol_version: order.protocol_version,
+    })
+}
+
+/// Helper function to hash an offer item.
+fn offer_item_hash(item: &OfferItem) -> [u8; 32] {
+    keccak256(encode(&[
+        Token::FixedBytes(keccak256(OFFER_ITEM_TYPE).to_vec()),
+        Token::Uint(U256::from(item.item_type)),
+        Token::Address(item.token),
+        Token::Uint(item.identifier_or_criteria),
+        Token::Uint(item.start_amount),
+        Token::Uint(item.end_amount),
+    ]))
+}
+
+/// Helper function to hash a consideration item.
+fn consideration_item_hash(item: &ConsiderationItem) -> [u8; 32] {
+    keccak256(encode(&[
+        Token::FixedBytes(keccak256(CONSIDERATION_ITEM_TYPE).to_vec()),
+        Token::Uint(U256::from(item.item_type)),
+        Token::Address(item.token),
+        Token::Uint(item.identifier_or_criteria),
+        Token::Uint(item.start_amount),
+        Token::Uint(item.end_amount),
+        Token::Address(item.recipient),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::SEAPORT_V6;
+    use crate::types::ProtocolData;
+    use ethers::{
+        core::rand::thread_rng,
+        signers::LocalWallet,
+        types::{Signature, H256},
+    };
+    use std::path::PathBuf;
+
+    /// Read the first order of a fulfill listing response, which OpenSea returns with
+    /// the signature of its offerer.
+    fn read_order(name: &str) -> ProtocolData {
+        let mut d = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        d.push("resources");
+        d.push(name);
+        let res: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(d).unwrap()).unwrap();
+        serde_json::from_value(res["fulfillment_data"]["orders"][0].clone()).unwrap()
+    }
+
+    /// Expand a 64 bytes EIP-2098 signature, where the parity is the top bit of `s`.
+    fn expand_compact_signature(signature: &[u8]) -> Signature {
+        let r = U256::from_big_endian(&signature[..32]);
+        let mut s = signature[32..64].to_vec();
+        let v = 27 + (s[0] >> 7) as u64;
+        s[0] &= 0x7f;
+        Signature {
+            r,
+            s: U256::from_big_endian(&s),
+            v,
+        }
+    }
+
+    /// Orders are hashed the same way since Seaport 1.1, only the domain changes.
+    #[test]
+    fn hashes_orders_like_seaport() {
+        for (name, protocol_version, order_hash) in [
+            (
+                "sample_response.json",
+                ProtocolVersion::V1_4,
+                "0x552c46c3a8638dd75325f40bf4289d33e9b34901eb085e48311b84987ea1e03c",
+            ),
+            (
+                "sample_response_1.5.json",
+                ProtocolVersion::V1_5,
+                "0xd33ce18dc4be112458515f5017fdd69a9dc934154107e7951bdde6292dc629f9",
+            ),
+        ] {
+            let order = read_order(name);
+            let offerer = order.parameters.offerer;
+            let signature = expand_compact_signature(order.signature.as_ref().unwrap());
+            let order = SeaportOrder {
+                parameters: order.parameters,
+                protocol_version,
+                chain_id: 1,
+            };
+            assert_eq!(
+                H256::from(order.struct_hash().unwrap()),
+                H256::from_str(order_hash).unwrap()
+            );
+            // Signatures of real orders only recover to their offerer if the order is
+            // hashed exactly as Seaport does.
+            let digest = H256::from(order.encode_eip712().unwrap());
+            assert_eq!(signature.recover(digest).unwrap(), offerer, "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn signs_orders() {
+        let signer = LocalWallet::new(&mut thread_rng());
+        let parameters = OrderParameters {
+            offerer: signer.address(),
+            zone: H160::zero(),
+            offer: vec![OfferItem {
+                item_type: 2,
+                token: H160::random(),
+                identifier_or_criteria: U256::from(1234),
+                start_amount: U256::one(),
+                end_amount: U256::one(),
+            }],
+            consideration: vec![ConsiderationItem {
+                item_type: 0,
+                token: H160::zero(),
+                identifier_or_criteria: U256::zero(),
+                start_amount: U256::exp10(18),
+                end_amount: U256::exp10(18),
+                recipient: signer.address(),
+            }],
+            order_type: 0,
+            start_time: U256::from(1690000000),
+            end_time: U256::from(1700000000),
+            zone_hash: H256::zero(),
+            salt: U256::from(42),
+            conduit_key: H256::zero(),
+            total_original_consideration_items: U256::one(),
+            counter: U256::zero(),
+        };
+
+        let req = sign_order(
+            &signer,
+            parameters.clone(),
+            ProtocolVersion::V1_6,
+            Chain::Mainnet,
+        )
+        .await
+        .unwrap();
+        let order = SeaportOrder {
+            parameters,
+            protocol_version: ProtocolVersion::V1_6,
+            chain_id: 1,
+        };
+        let signature = Signature::try_from(req.signature.as_ref()).unwrap();
+        let digest = H256::from(order.encode_eip712().unwrap());
+        assert_eq!(signature.recover(digest).unwrap(), signer.address());
+
+        let body = serde_json::to_value(&req).unwrap();
+        assert_eq!(body["protocol_address"], SEAPORT_V6);
+        assert_eq!(body["parameters"]["startTime"], "1690000000");
+        assert_eq!(
+            body["parameters"]["consideration"][0]["startAmount"],
+            "1000000000000000000"
+        );
+    }
+}
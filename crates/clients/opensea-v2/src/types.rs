@@ -2,9 +2,10 @@ use std::str::FromStr;
 
 use ethers::types::{Bytes, Chain, H160, H256, U256};
 use serde::{de, Deserialize, Serialize, Serializer};
+use serde_json::Value;
 use thiserror::Error;
 
-use super::constants::{SEAPORT_V1, SEAPORT_V4, SEAPORT_V5};
+use super::constants::{SEAPORT_V1, SEAPORT_V4, SEAPORT_V5, SEAPORT_V6};
 
 /// Request to fulfill a listing on OpenSea.
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -45,6 +46,29 @@ pub enum ProtocolVersion {
     V1_1,
     V1_4,
     V1_5,
+    V1_6,
+}
+
+impl ProtocolVersion {
+    /// Address of the Seaport contract for this version.
+    pub fn address(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V1_1 => SEAPORT_V1,
+            ProtocolVersion::V1_4 => SEAPORT_V4,
+            ProtocolVersion::V1_5 => SEAPORT_V5,
+            ProtocolVersion::V1_6 => SEAPORT_V6,
+        }
+    }
+
+    /// Version of the EIP-712 domain orders are signed in.
+    pub fn eip712_version(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V1_1 => "1.1",
+            ProtocolVersion::V1_4 => "1.4",
+            ProtocolVersion::V1_5 => "1.5",
+            ProtocolVersion::V1_6 => "1.6",
+        }
+    }
 }
 
 /// Information needed to fulfill the listing.
@@ -110,19 +134,445 @@ pub struct AdditionalRecipient {
     pub recipient: H160,
 }
 
+/// A Seaport order, as returned by the API and signed by its offerer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProtocolData {
+    pub parameters: OrderParameters,
+    pub signature: Option<Bytes>,
+}
+
+/// Parameters of a Seaport order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrderParameters {
+    pub offerer: H160,
+    pub zone: H160,
+    pub offer: Vec<OfferItem>,
+    pub consideration: Vec<ConsiderationItem>,
+    pub order_type: u8,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub start_time: U256,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub end_time: U256,
+    pub zone_hash: H256,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub salt: U256,
+    pub conduit_key: H256,
+    #[serde(
+        default,
+        serialize_with = "u256_to_dec_str",
+        deserialize_with = "u256_from_any"
+    )]
+    pub total_original_consideration_items: U256,
+    /// Counter of the offerer on Seaport, which cancels their orders when incremented.
+    #[serde(
+        default,
+        serialize_with = "u256_to_dec_str",
+        deserialize_with = "u256_from_any"
+    )]
+    pub counter: U256,
+}
+
+/// An item offered by a Seaport order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfferItem {
+    pub item_type: u8,
+    pub token: H160,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub identifier_or_criteria: U256,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub start_amount: U256,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub end_amount: U256,
+}
+
+/// An item a Seaport order asks for in exchange, and who receives it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsiderationItem {
+    pub item_type: u8,
+    pub token: H160,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub identifier_or_criteria: U256,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub start_amount: U256,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub end_amount: U256,
+    pub recipient: H160,
+}
+
+/// Price of an order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Price {
+    pub currency: String,
+    pub decimals: u8,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub value: U256,
+}
+
+/// Current price of a listing.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListingPrice {
+    pub current: Price,
+}
+
+/// Response from OpenSea best listings by collection endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ListingsResponse {
+    pub listings: Vec<CollectionListing>,
+    /// Cursor to the next page of listings.
+    pub next: Option<String>,
+}
+
+/// A listing of an NFT in a collection.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CollectionListing {
+    pub order_hash: H256,
+    pub chain: String,
+    #[serde(rename = "type")]
+    pub order_type: String,
+    pub price: ListingPrice,
+    pub protocol_data: ProtocolData,
+    pub protocol_address: H160,
+}
+
+/// Response from OpenSea offers by collection endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OffersResponse {
+    pub offers: Vec<CollectionOffer>,
+    /// Cursor to the next page of offers.
+    pub next: Option<String>,
+}
+
+/// An offer on NFTs of a collection.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CollectionOffer {
+    pub order_hash: H256,
+    pub chain: String,
+    /// Which NFTs the offer is for. Offers for a single NFT have none.
+    pub criteria: Option<OfferCriteria>,
+    pub price: Price,
+    pub protocol_data: ProtocolData,
+    pub protocol_address: H160,
+}
+
+/// Which NFTs an offer is for.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OfferCriteria {
+    pub collection: CollectionRef,
+    pub contract: ContractRef,
+    /// Trait the NFTs must have, for trait offers.
+    #[serde(rename = "trait")]
+    pub trait_criteria: Option<TraitCriteria>,
+    pub encoded_token_ids: Option<String>,
+}
+
+/// Reference to a collection.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CollectionRef {
+    pub slug: String,
+}
+
+/// Reference to an NFT contract.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ContractRef {
+    pub address: H160,
+}
+
+/// Trait NFTs must have for a trait offer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TraitCriteria {
+    #[serde(rename = "type")]
+    pub trait_type: String,
+    pub value: String,
+}
+
+/// Response from OpenSea listings by NFT endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OrdersResponse {
+    pub orders: Vec<Order>,
+    /// Cursor to the next page of orders.
+    pub next: Option<String>,
+    /// Cursor to the previous page of orders.
+    pub previous: Option<String>,
+}
+
+/// An order, as returned by the orders endpoints.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Order {
+    pub created_date: String,
+    pub closing_date: Option<String>,
+    pub listing_time: u64,
+    pub expiration_time: u64,
+    pub order_hash: Option<H256>,
+    pub protocol_data: ProtocolData,
+    pub protocol_address: H160,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub current_price: U256,
+    pub maker: Account,
+    pub taker: Option<Account>,
+    /// Either `ask` for listings or `bid` for offers.
+    pub side: String,
+    pub order_type: String,
+    pub cancelled: bool,
+    pub finalized: bool,
+    pub marked_invalid: bool,
+    #[serde(default)]
+    pub remaining_quantity: u64,
+}
+
+/// An OpenSea account.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Account {
+    pub address: H160,
+}
+
+/// Request to post a listing or an offer on OpenSea.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostOrderRequest {
+    pub parameters: OrderParameters,
+    /// Signature of the order by its offerer.
+    pub signature: Bytes,
+    #[serde(
+        rename = "protocol_address",
+        serialize_with = "protocol_version_to_str"
+    )]
+    pub protocol_version: ProtocolVersion,
+}
+
+/// Response from OpenSea post listing and post offer endpoints.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PostOrderResponse {
+    pub order: Order,
+}
+
+/// Request to fulfill an offer on OpenSea.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FulfillOfferRequest {
+    pub offer: Offer,
+    pub fulfiller: Fulfiller,
+    pub consideration: OfferConsideration,
+}
+
+/// Offer we want to fulfill on OpenSea.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Offer {
+    pub hash: H256,
+    #[serde(serialize_with = "chain_to_str")]
+    pub chain: Chain,
+    #[serde(
+        rename = "protocol_address",
+        serialize_with = "protocol_version_to_str"
+    )]
+    pub protocol_version: ProtocolVersion,
+}
+
+/// NFT sold to fulfill an offer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OfferConsideration {
+    pub asset_contract_address: H160,
+    pub token_id: String,
+}
+
+/// Response from OpenSea fulfill offer endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FulfillOfferResponse {
+    pub protocol: String,
+    pub fulfillment_data: OfferFulfillmentData,
+}
+
+/// Information needed to fulfill the offer.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OfferFulfillmentData {
+    pub transaction: OfferTransaction,
+    pub orders: Vec<ProtocolData>,
+}
+
+/// Transaction data for onchain fulfillment of an offer, with `matchAdvancedOrders`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OfferTransaction {
+    pub function: String,
+    pub chain: u64,
+    pub to: H160,
+    pub value: u64,
+    pub input_data: MatchAdvancedOrdersData,
+}
+
+/// Arguments of `matchAdvancedOrders`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MatchAdvancedOrdersData {
+    pub orders: Vec<AdvancedOrder>,
+    pub criteria_resolvers: Vec<CriteriaResolver>,
+    pub fulfillments: Vec<Fulfillment>,
+    pub recipient: H160,
+}
+
+/// A Seaport order which can be partially filled.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdvancedOrder {
+    pub parameters: OrderParameters,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub numerator: U256,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub denominator: U256,
+    pub signature: Bytes,
+    pub extra_data: Bytes,
+}
+
+/// Proof that an NFT matches the criteria of an order item.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CriteriaResolver {
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub order_index: U256,
+    pub side: u8,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub index: U256,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub identifier: U256,
+    pub criteria_proof: Vec<H256>,
+}
+
+/// Items of the matched orders which fulfill each other.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Fulfillment {
+    pub offer_components: Vec<FulfillmentComponent>,
+    pub consideration_components: Vec<FulfillmentComponent>,
+}
+
+/// An item of a matched order.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FulfillmentComponent {
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub order_index: U256,
+    #[serde(serialize_with = "u256_to_dec_str", deserialize_with = "u256_from_any")]
+    pub item_index: U256,
+}
+
+/// Response from OpenSea collection stats endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CollectionStats {
+    pub total: TotalStats,
+    pub intervals: Vec<IntervalStats>,
+}
+
+/// All time stats of a collection.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TotalStats {
+    pub volume: f64,
+    pub sales: u64,
+    pub average_price: f64,
+    pub num_owners: u64,
+    pub market_cap: f64,
+    pub floor_price: Option<f64>,
+    pub floor_price_symbol: Option<String>,
+}
+
+/// Stats of a collection over an interval, e.g. `one_day`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IntervalStats {
+    pub interval: String,
+    pub volume: f64,
+    pub volume_diff: f64,
+    pub volume_change: f64,
+    pub sales: u64,
+    pub sales_diff: i64,
+    pub average_price: f64,
+}
+
+/// Response from OpenSea NFT endpoint.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NftResponse {
+    pub nft: Nft,
+}
+
+/// Metadata of an NFT.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Nft {
+    pub identifier: String,
+    pub collection: String,
+    pub contract: H160,
+    pub token_standard: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub metadata_url: Option<String>,
+    pub opensea_url: Option<String>,
+    pub updated_at: Option<String>,
+    pub is_disabled: bool,
+    pub is_nsfw: bool,
+    pub is_suspicious: bool,
+    pub creator: Option<H160>,
+    #[serde(default)]
+    pub traits: Vec<NftTrait>,
+    #[serde(default)]
+    pub owners: Vec<NftOwner>,
+    pub rarity: Option<Rarity>,
+}
+
+/// A trait of an NFT. Values are strings or numbers.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NftTrait {
+    pub trait_type: String,
+    pub display_type: Option<String>,
+    pub max_value: Option<Value>,
+    pub value: Value,
+}
+
+/// An owner of an NFT, with the quantity they own.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NftOwner {
+    pub address: H160,
+    pub quantity: u64,
+}
+
+/// Rarity of an NFT in its collection.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rarity {
+    pub rank: Option<u64>,
+}
+
+/// Error payload returned by the OpenSea API.
+#[derive(Clone, Debug, Deserialize)]
+pub(crate) struct ErrorResponse {
+    #[serde(default)]
+    pub(crate) errors: Vec<String>,
+}
+
 /// Error returned by the OpenSea API.
 #[derive(Debug, Error)]
 pub enum OpenSeaApiError {
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
+    /// The API rejected the request.
+    #[error("opensea api error ({status}): {}", errors.join(", "))]
+    Api { status: u16, errors: Vec<String> },
+    #[error("unsupported chain {0}")]
+    UnsupportedChain(Chain),
+    #[error("failed to sign order: {0}")]
+    Signing(String),
+}
+
+/// Name of a chain in the OpenSea API.
+pub(crate) fn chain_name(chain: Chain) -> Result<&'static str, OpenSeaApiError> {
+    match chain {
+        Chain::Mainnet => Ok("ethereum"),
+        Chain::Sepolia => Ok("sepolia"),
+        Chain::Polygon => Ok("matic"),
+        Chain::Arbitrum => Ok("arbitrum"),
+        Chain::Optimism => Ok("optimism"),
+        _ => Err(OpenSeaApiError::UnsupportedChain(chain)),
+    }
 }
 
 /// Helper function to convert a chain to a string.
 fn chain_to_str<S: Serializer>(chain: &Chain, serializer: S) -> Result<S::Ok, S::Error> {
-    let chain_str = match chain {
-        Chain::Mainnet => "ethereum",
-        _ => Err(serde::ser::Error::custom("Unsupported chain"))?,
-    };
+    let chain_str = chain_name(*chain).map_err(serde::ser::Error::custom)?;
     serializer.serialize_str(chain_str)
 }
 
@@ -131,12 +581,7 @@ fn protocol_version_to_str<S: Serializer>(
     protocol_version: &ProtocolVersion,
     serializer: S,
 ) -> Result<S::Ok, S::Error> {
-    let protocol_version_str = match protocol_version {
-        ProtocolVersion::V1_1 => SEAPORT_V1,
-        ProtocolVersion::V1_4 => SEAPORT_V4,
-        ProtocolVersion::V1_5 => SEAPORT_V5,
-    };
-    serializer.serialize_str(protocol_version_str)
+    serializer.serialize_str(protocol_version.address())
 }
 
 /// Helper function to convert a string to bytes.
@@ -156,3 +601,23 @@ where
     let val = String::deserialize(deserializer)?;
     U256::from_dec_str(&val).map_err(de::Error::custom)
 }
+
+/// Helper function to convert a U256 to a decimal string.
+fn u256_to_dec_str<S: Serializer>(val: &U256, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&val.to_string())
+}
+
+/// Helper function to convert a decimal or hex string, or a number, to a U256.
+fn u256_from_any<'de, D>(deserializer: D) -> Result<U256, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    match Value::deserialize(deserializer)? {
+        Value::String(val) => match val.strip_prefix("0x") {
+            Some(hex) => U256::from_str_radix(hex, 16).map_err(de::Error::custom),
+            None => U256::from_dec_str(&val).map_err(de::Error::custom),
+        },
+        Value::Number(val) => U256::from_dec_str(&val.to_string()).map_err(de::Error::custom),
+        val => Err(de::Error::custom(format!("invalid number: {}", val))),
+    }
+}
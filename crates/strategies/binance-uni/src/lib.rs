@@ -1,3 +1,4 @@
 pub mod constants;
+pub mod pool_state_collector;
 pub mod strategy;
 pub mod types;
@@ -0,0 +1,589 @@
+use anyhow::Result;
+use artemis_core::types::{Collector, CollectorStream};
+use async_trait::async_trait;
+use bindings_binance_uni::uniswap_v3_pool::{
+    BurnFilter, MintFilter, SwapFilter, UniswapV3Pool, UniswapV3PoolEvents,
+};
+use ethers::{
+    abi::RawLog,
+    contract::{EthEvent, EthLogDecode},
+    prelude::Middleware,
+    providers::PubsubClient,
+    types::{Address, BlockId, Filter, Log, H256, U256, U64},
+};
+use futures::{
+    stream::{self, Peekable},
+    FutureExt, Stream, StreamExt,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    pin::Pin,
+    sync::Arc,
+};
+use tracing::{info, warn};
+
+/// Lowest tick supported by Uniswap V3.
+pub const MIN_TICK: i32 = -887272;
+
+/// Highest tick supported by Uniswap V3.
+pub const MAX_TICK: i32 = 887272;
+
+/// Maximum number of concurrent calls made while loading initialized ticks.
+const MAX_CONCURRENT_CALLS: usize = 32;
+
+/// A collector that loads the state of a Uniswap V3 pool once, and then keeps it
+/// current by applying `Swap`, `Mint` and `Burn` logs, generating a stream of
+/// [events](PoolStateUpdate) with the updated state.
+pub struct PoolStateCollector<M> {
+    provider: Arc<M>,
+    pool: UniswapV3Pool<M>,
+}
+
+/// Liquidity tracked for an initialized tick.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TickInfo {
+    pub liquidity_gross: u128,
+    pub liquidity_net: i128,
+}
+
+/// State of a Uniswap V3 pool.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PoolState {
+    pub address: Address,
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    /// Liquidity in range at the current tick.
+    pub liquidity: u128,
+    pub tick_spacing: i32,
+    /// Initialized ticks, keyed by tick index.
+    pub ticks: BTreeMap<i32, TickInfo>,
+}
+
+/// Why the state has to be reloaded.
+#[derive(Debug, Clone)]
+enum Reload {
+    /// Logs were removed by a reorg.
+    Reorg(Vec<Log>),
+    /// A log failed to apply.
+    Full,
+}
+
+/// A pool state update event, emitted once the state is loaded and after each log
+/// that touches the pool.
+#[derive(Debug, Clone)]
+pub struct PoolStateUpdate {
+    /// Block of the log that caused the update, or the block the state was loaded at.
+    pub block_number: U64,
+    /// Hash of the transaction that caused the update, if any.
+    pub transaction_hash: Option<H256>,
+    pub state: PoolState,
+}
+
+impl PoolState {
+    /// Apply a swap, which moves the price and the in-range liquidity.
+    pub fn apply_swap(&mut self, swap: &SwapFilter) {
+        self.sqrt_price_x96 = swap.sqrt_price_x96;
+        self.tick = swap.tick;
+        self.liquidity = swap.liquidity;
+    }
+
+    /// Apply a mint, adding `amount` of liquidity between the two ticks.
+    pub fn apply_mint(&mut self, mint: &MintFilter) {
+        self.update_position(mint.tick_lower, mint.tick_upper, mint.amount as i128);
+    }
+
+    /// Apply a burn, removing `amount` of liquidity between the two ticks.
+    pub fn apply_burn(&mut self, burn: &BurnFilter) {
+        self.update_position(burn.tick_lower, burn.tick_upper, -(burn.amount as i128));
+    }
+
+    fn update_position(&mut self, tick_lower: i32, tick_upper: i32, liquidity_delta: i128) {
+        if liquidity_delta == 0 {
+            return;
+        }
+        self.update_tick(tick_lower, liquidity_delta, false);
+        self.update_tick(tick_upper, liquidity_delta, true);
+        if tick_lower <= self.tick && self.tick < tick_upper {
+            self.liquidity = self.liquidity.saturating_add_signed(liquidity_delta);
+        }
+    }
+
+    fn update_tick(&mut self, tick: i32, liquidity_delta: i128, upper: bool) {
+        let info = self.ticks.entry(tick).or_default();
+        info.liquidity_gross = info.liquidity_gross.saturating_add_signed(liquidity_delta);
+        // Liquidity is added when crossing a lower tick left to right, and removed
+        // when crossing an upper tick.
+        if upper {
+            info.liquidity_net -= liquidity_delta;
+        } else {
+            info.liquidity_net += liquidity_delta;
+        }
+        if info.liquidity_gross == 0 {
+            self.ticks.remove(&tick);
+        }
+    }
+}
+
+impl<M: Middleware + 'static> PoolStateCollector<M> {
+    pub fn new(provider: Arc<M>, address: Address) -> Self {
+        let pool = UniswapV3Pool::new(address, provider.clone());
+        Self { provider, pool }
+    }
+
+    /// Load the full pool state at a given block.
+    pub async fn load_state(&self, block: BlockId) -> Result<PoolState> {
+        let (sqrt_price_x96, tick, _, _, _, _, _) = self.pool.slot_0().block(block).call().await?;
+        let liquidity = self.pool.liquidity().block(block).call().await?;
+        let tick_spacing = self.pool.tick_spacing().block(block).call().await?;
+        let min_word = word_of(MIN_TICK, tick_spacing);
+        let max_word = word_of(MAX_TICK, tick_spacing);
+        let ticks = self
+            .load_ticks(block, tick_spacing, min_word..=max_word)
+            .await?;
+        Ok(PoolState {
+            address: self.pool.address(),
+            sqrt_price_x96,
+            tick,
+            liquidity,
+            tick_spacing,
+            ticks,
+        })
+    }
+
+    /// Find the initialized ticks of the given tick bitmap words, and load their liquidity.
+    async fn load_ticks(
+        &self,
+        block: BlockId,
+        tick_spacing: i32,
+        words: impl IntoIterator<Item = i16>,
+    ) -> Result<BTreeMap<i32, TickInfo>> {
+        let words: Vec<(i16, U256)> = stream::iter(words)
+            .map(|word| async move {
+                let bitmap = self.pool.tick_bitmap(word).block(block).call().await?;
+                Ok::<_, anyhow::Error>((word, bitmap))
+            })
+            .buffer_unordered(MAX_CONCURRENT_CALLS)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_>>()?;
+
+        let initialized_ticks = words.into_iter().flat_map(|(word, bitmap)| {
+            (0..256)
+                .filter(move |bit| bitmap.bit(*bit))
+                .map(move |bit| ((word as i32) * 256 + bit as i32) * tick_spacing)
+        });
+
+        let ticks = stream::iter(initialized_ticks)
+            .map(|tick| async move {
+                let (liquidity_gross, liquidity_net, ..) =
+                    self.pool.ticks(tick).block(block).call().await?;
+                Ok::<_, anyhow::Error>((
+                    tick,
+                    TickInfo {
+                        liquidity_gross,
+                        liquidity_net,
+                    },
+                ))
+            })
+            .buffer_unordered(MAX_CONCURRENT_CALLS)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<_>>()?;
+        Ok(ticks)
+    }
+
+    /// Load the state at the latest block, returning it with the block it was loaded at.
+    async fn load_latest_state(&self) -> Result<(PoolState, U64)> {
+        let block_number = self.provider.get_block_number().await?;
+        let state = self.load_state(block_number.into()).await?;
+        Ok((state, block_number))
+    }
+
+    /// Reload the state at the latest block after a reorg. Only the tick bitmap words
+    /// touched by the removed logs, or by the logs since the fork point, are reloaded.
+    async fn reload_after_reorg(
+        &self,
+        state: &PoolState,
+        removed: &[Log],
+    ) -> Result<(PoolState, U64)> {
+        let Some(fork) = removed.iter().filter_map(|log| log.block_number).min() else {
+            return self.load_latest_state().await;
+        };
+        let block_number = self.provider.get_block_number().await?;
+        let filter = self.filter().from_block(fork).to_block(block_number);
+        let logs = self.provider.get_logs(&filter).await?;
+        let Some(words) = touched_words(removed.iter().chain(&logs), state.tick_spacing) else {
+            return self.load_latest_state().await;
+        };
+
+        let block = BlockId::from(block_number);
+        let (sqrt_price_x96, tick, _, _, _, _, _) = self.pool.slot_0().block(block).call().await?;
+        let liquidity = self.pool.liquidity().block(block).call().await?;
+        let mut ticks = state.ticks.clone();
+        ticks.retain(|tick, _| !words.contains(&word_of(*tick, state.tick_spacing)));
+        ticks.extend(self.load_ticks(block, state.tick_spacing, words).await?);
+        let state = PoolState {
+            sqrt_price_x96,
+            tick,
+            liquidity,
+            ticks,
+            ..state.clone()
+        };
+        Ok((state, block_number))
+    }
+
+    /// Filter matching the logs which change the pool state.
+    fn filter(&self) -> Filter {
+        Filter::new().address(self.pool.address()).topic0(vec![
+            SwapFilter::signature(),
+            MintFilter::signature(),
+            BurnFilter::signature(),
+        ])
+    }
+}
+
+/// Tick bitmap word holding a tick.
+fn word_of(tick: i32, tick_spacing: i32) -> i16 {
+    (tick.div_euclid(tick_spacing) >> 8) as i16
+}
+
+/// Tick bitmap words of the ticks changed by the logs, or `None` if a log can't be
+/// decoded.
+fn touched_words<'a>(
+    logs: impl IntoIterator<Item = &'a Log>,
+    tick_spacing: i32,
+) -> Option<BTreeSet<i16>> {
+    let mut words = BTreeSet::new();
+    for log in logs {
+        let raw = RawLog::from((log.topics.clone(), log.data.to_vec()));
+        let (tick_lower, tick_upper) = match UniswapV3PoolEvents::decode_log(&raw).ok()? {
+            UniswapV3PoolEvents::MintFilter(mint) => (mint.tick_lower, mint.tick_upper),
+            UniswapV3PoolEvents::BurnFilter(burn) => (burn.tick_lower, burn.tick_upper),
+            _ => continue,
+        };
+        words.insert(word_of(tick_lower, tick_spacing));
+        words.insert(word_of(tick_upper, tick_spacing));
+    }
+    Some(words)
+}
+
+/// Apply a log to the state. Returns `false` if the log does not change the state.
+fn apply_log(state: &mut PoolState, log: &Log) -> Result<bool> {
+    let raw = RawLog::from((log.topics.clone(), log.data.to_vec()));
+    match UniswapV3PoolEvents::decode_log(&raw)? {
+        UniswapV3PoolEvents::SwapFilter(swap) => state.apply_swap(&swap),
+        UniswapV3PoolEvents::MintFilter(mint) => state.apply_mint(&mint),
+        UniswapV3PoolEvents::BurnFilter(burn) => state.apply_burn(&burn),
+        _ => return Ok(false),
+    }
+    Ok(true)
+}
+
+/// Consume the removed logs already received after a removed log, so that all the
+/// logs removed by a reorg trigger a single reload. Returns the logs consumed.
+fn take_removed_logs<S: Stream<Item = Log> + Unpin>(logs: &mut Peekable<S>) -> Vec<Log> {
+    let mut removed = vec![];
+    while let Some(Some(log)) = Pin::new(&mut *logs)
+        .next_if(|log| log.removed == Some(true))
+        .now_or_never()
+    {
+        removed.push(log);
+    }
+    removed
+}
+
+/// Implementation of the [Collector](Collector) trait for the
+/// [PoolStateCollector](PoolStateCollector). Logs are subscribed to before the state
+/// is loaded, so that no update between the load and the subscription is missed.
+/// Removed logs can't be unapplied, so on a reorg the price, liquidity and the ticks
+/// touched since the fork point are reloaded instead, and if a log fails to apply the
+/// whole state is. Logs at or below the block the state was reloaded at are skipped.
+#[async_trait]
+impl<M> Collector<PoolStateUpdate> for PoolStateCollector<M>
+where
+    M: Middleware + 'static,
+    M::Provider: PubsubClient,
+    M::Error: 'static,
+{
+    async fn get_event_stream(&self) -> Result<CollectorStream<'_, PoolStateUpdate>> {
+        let filter = self.filter();
+        let logs = self
+            .provider
+            .subscribe_logs(&filter)
+            .await?
+            .boxed()
+            .peekable();
+
+        let (state, block_number) = self.load_latest_state().await?;
+        info!(
+            "loaded pool {:?} at block {} with {} initialized ticks",
+            state.address,
+            block_number,
+            state.ticks.len()
+        );
+        let initial = PoolStateUpdate {
+            block_number,
+            transaction_hash: None,
+            state: state.clone(),
+        };
+
+        let updates = stream::unfold(
+            (logs, state, block_number, None),
+            move |(mut logs, mut state, mut loaded_at, mut reload)| async move {
+                loop {
+                    let log = logs.next().await?;
+                    if log.removed == Some(true) {
+                        let mut removed = vec![log];
+                        removed.extend(take_removed_logs(&mut logs));
+                        warn!(
+                            "reorg removed {} logs of pool {:?}, reloading state",
+                            removed.len(),
+                            state.address
+                        );
+                        reload = Some(match reload {
+                            Some(Reload::Reorg(mut pending)) => {
+                                pending.extend(removed);
+                                Reload::Reorg(pending)
+                            }
+                            Some(Reload::Full) => Reload::Full,
+                            None => Reload::Reorg(removed),
+                        });
+                    } else if reload.is_none() {
+                        // Skip logs which are already reflected in the loaded state.
+                        if matches!(log.block_number, Some(number) if number <= loaded_at) {
+                            continue;
+                        }
+                        match apply_log(&mut state, &log) {
+                            Ok(true) => {
+                                let update = PoolStateUpdate {
+                                    block_number: log.block_number.unwrap_or_default(),
+                                    transaction_hash: log.transaction_hash,
+                                    state: state.clone(),
+                                };
+                                return Some((update, (logs, state, loaded_at, reload)));
+                            }
+                            Ok(false) => continue,
+                            Err(e) => {
+                                warn!(
+                                    "error applying log to pool {:?}, reloading state: {}",
+                                    state.address, e
+                                );
+                                reload = Some(Reload::Full);
+                            }
+                        }
+                    }
+
+                    // Retried on the next log if the reload fails, as the state has diverged.
+                    let reloaded = match &reload {
+                        Some(Reload::Reorg(removed)) => {
+                            self.reload_after_reorg(&state, removed).await
+                        }
+                        _ => self.load_latest_state().await,
+                    };
+                    match reloaded {
+                        Ok((reloaded, block_number)) => {
+                            state = reloaded;
+                            loaded_at = block_number;
+                            reload = None;
+                            let update = PoolStateUpdate {
+                                block_number,
+                                transaction_hash: None,
+                                state: state.clone(),
+                            };
+                            return Some((update, (logs, state, loaded_at, reload)));
+                        }
+                        Err(e) => warn!("error reloading pool {:?}: {}", state.address, e),
+                    }
+                }
+            },
+        );
+
+        Ok(Box::pin(stream::once(async { initial }).chain(updates)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        abi::{self, Token},
+        providers::Provider,
+        types::{Bytes, I256},
+    };
+
+    fn state() -> PoolState {
+        PoolState {
+            tick: 0,
+            liquidity: 100,
+            tick_spacing: 10,
+            ..Default::default()
+        }
+    }
+
+    fn mint(tick_lower: i32, tick_upper: i32, amount: u128) -> MintFilter {
+        MintFilter {
+            sender: Address::zero(),
+            owner: Address::zero(),
+            tick_lower,
+            tick_upper,
+            amount,
+            amount_0: U256::zero(),
+            amount_1: U256::zero(),
+        }
+    }
+
+    #[test]
+    fn mint_and_burn_update_ticks_and_liquidity() {
+        let mut state = state();
+        // In range position.
+        state.apply_mint(&mint(-10, 10, 50));
+        // Out of range position sharing the upper tick.
+        state.apply_mint(&mint(10, 20, 50));
+        assert_eq!(state.liquidity, 150);
+        assert_eq!(
+            state.ticks[&10],
+            TickInfo {
+                liquidity_gross: 100,
+                liquidity_net: 0
+            }
+        );
+        assert_eq!(state.ticks[&-10].liquidity_net, 50);
+
+        state.apply_burn(&BurnFilter {
+            owner: Address::zero(),
+            tick_lower: -10,
+            tick_upper: 10,
+            amount: 50,
+            amount_0: U256::zero(),
+            amount_1: U256::zero(),
+        });
+        assert_eq!(state.liquidity, 100);
+        assert!(!state.ticks.contains_key(&-10));
+        assert_eq!(state.ticks[&10].liquidity_net, 50);
+    }
+
+    #[test]
+    fn skips_logs_removed_by_the_same_reorg() {
+        let log = |removed| Log {
+            removed: Some(removed),
+            ..Default::default()
+        };
+        let mut logs = stream::iter(vec![log(true), log(true), log(false), log(true)]).peekable();
+        assert_eq!(take_removed_logs(&mut logs).len(), 2);
+        assert_eq!(
+            logs.next().now_or_never().flatten().unwrap().removed,
+            Some(false)
+        );
+        assert_eq!(take_removed_logs(&mut logs).len(), 1);
+    }
+
+    fn uint(value: u64) -> Token {
+        Token::Uint(value.into())
+    }
+
+    fn int(value: i32) -> Token {
+        Token::Int(I256::from(value).into_raw())
+    }
+
+    fn mint_log(block_number: u64, tick_lower: i32, tick_upper: i32) -> Log {
+        let topic = |tick| H256::from_slice(&abi::encode(&[int(tick)]));
+        Log {
+            topics: vec![
+                MintFilter::signature(),
+                H256::zero(),
+                topic(tick_lower),
+                topic(tick_upper),
+            ],
+            data: abi::encode(&[Token::Address(Address::zero()), uint(50), uint(0), uint(0)])
+                .into(),
+            block_number: Some(block_number.into()),
+            removed: Some(true),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn reorg_reloads_only_touched_tick_words() {
+        let (provider, mock) = Provider::mocked();
+        let collector = PoolStateCollector::new(Arc::new(provider), Address::zero());
+        let info = |liquidity| TickInfo {
+            liquidity_gross: liquidity,
+            liquidity_net: liquidity as i128,
+        };
+        // Ticks 100 and 200 are in word 0, tick 5000 in word 1.
+        let state = PoolState {
+            tick_spacing: 10,
+            ticks: BTreeMap::from([(100, info(100)), (200, info(100)), (5000, info(30))]),
+            ..Default::default()
+        };
+
+        // The mock answers with the last pushed response first. The reload fetches the
+        // block number, the logs since the fork point, slot0, the liquidity, then word
+        // 0 and its only initialized tick.
+        let calls = [
+            vec![
+                uint(5),
+                int(5),
+                uint(0),
+                uint(0),
+                int(0),
+                uint(0),
+                uint(0),
+                Token::Bool(true),
+            ],
+            vec![Token::Uint(U256::one() << 10)],
+            vec![uint(7)],
+            vec![
+                uint(42),
+                int(-5),
+                uint(0),
+                uint(0),
+                uint(0),
+                uint(0),
+                Token::Bool(false),
+            ],
+        ];
+        for tokens in calls {
+            mock.push::<Bytes, _>(Bytes::from(abi::encode(&tokens)))
+                .unwrap();
+        }
+        mock.push::<Vec<Log>, _>(vec![]).unwrap();
+        mock.push(U64::from(12)).unwrap();
+
+        let (reloaded, block_number) = futures::executor::block_on(
+            collector.reload_after_reorg(&state, &[mint_log(10, 100, 200)]),
+        )
+        .unwrap();
+        assert_eq!(block_number, U64::from(12));
+        assert_eq!(reloaded.sqrt_price_x96, U256::from(42));
+        assert_eq!(reloaded.tick, -5);
+        assert_eq!(reloaded.liquidity, 7);
+        assert_eq!(
+            reloaded.ticks,
+            BTreeMap::from([(100, info(5)), (5000, info(30))])
+        );
+
+        mock.assert_request("eth_blockNumber", ()).unwrap();
+        let filter = collector.filter().from_block(10).to_block(12);
+        mock.assert_request("eth_getLogs", [filter]).unwrap();
+    }
+
+    #[test]
+    fn swap_moves_price_and_liquidity() {
+        let mut state = state();
+        state.apply_swap(&SwapFilter {
+            sender: Address::zero(),
+            recipient: Address::zero(),
+            amount_0: 1.into(),
+            amount_1: (-1).into(),
+            sqrt_price_x96: U256::from(42),
+            liquidity: 7,
+            tick: -5,
+        });
+        assert_eq!(state.sqrt_price_x96, U256::from(42));
+        assert_eq!(state.tick, -5);
+        assert_eq!(state.liquidity, 7);
+    }
+}
@@ -1,19 +1,26 @@
-use super::types::{Action, Event, BinanceOrdersResponse, TokensPrice, PriceDifference, Profit};
+use super::types::{Action, Event, TokensPrice, PriceDifference, Profit};
 use anyhow::Result;
 use artemis_core::types::Strategy;
 use async_trait::async_trait;
 use ethers::{prelude::*};
 use std::sync::Arc;
 use artemis_core::collectors::block_collector::NewBlock;
-use super::constants::{POOL_ADDRESS, FEE};
+use artemis_core::collectors::cex_collector::{best_ask_book, best_bid_book, CexOrderBook, PriceLevel, Venue};
+use super::constants::{POOL_ADDRESS, FEE, QUOTE_ASSET};
+use super::pool_state_collector::PoolState;
 use bindings_binance_uni::uniswap_v3_pool::{UniswapV3Pool};
 use std::collections::HashMap;
 use ethers::utils::{parse_units, format_units};
-use tracing::{info};
+use rust_decimal::prelude::ToPrimitive;
+use tracing::{debug, info};
 
 pub struct BinanceUni<M> {
     client: Arc<M>,
     pool_contract: Arc<UniswapV3Pool<M>>,
+    /// Latest order book seen for each venue and symbol.
+    cex_books: HashMap<(Venue, String), CexOrderBook>,
+    /// Latest pool state, if a pool state collector is running.
+    pool_state: Option<PoolState>,
 }
 
 impl<M: Middleware + 'static> BinanceUni<M> {
@@ -22,7 +29,7 @@ impl<M: Middleware + 'static> BinanceUni<M> {
             *POOL_ADDRESS,
             client.clone(),
         ));
-        Self { client, pool_contract }
+        Self { client, pool_contract, cex_books: HashMap::new(), pool_state: None }
     }
 }
 
@@ -39,6 +46,14 @@ impl<M: Middleware + 'static> Strategy<Event, Action> for BinanceUni<M> {
                     panic!("Strategy is out of sync {}", e);
                 }
             },
+            Event::CexOrderBook(book) => {
+                self.cex_books.insert((book.venue, book.symbol.clone()), book);
+                None
+            }
+            Event::PoolState(update) => {
+                self.pool_state = Some(update.state);
+                None
+            }
         }
     }
 }
@@ -46,83 +61,90 @@ impl<M: Middleware + 'static> Strategy<Event, Action> for BinanceUni<M> {
 impl<M: Middleware + 'static> BinanceUni<M> {
     async fn process_new_block_event(&mut self, event: NewBlock) -> Result<()> {
         info!("blockNumber {}", event.number);
-        let slot0 = self.pool_contract.slot_0().call().await?;
-        let liquidity = self.pool_contract.liquidity().call().await?;
-        let sqrt_px96 = slot0.0;
+        // Only poll the pool when its state isn't streamed by a pool state collector.
+        let (sqrt_px96, liquidity) = match &self.pool_state {
+            Some(state) => (state.sqrt_price_x96, state.liquidity),
+            None => {
+                let slot0 = self.pool_contract.slot_0().call().await?;
+                let liquidity = self.pool_contract.liquidity().call().await?;
+                (slot0.0, liquidity)
+            }
+        };
         self.arb_possibility(U256::from(sqrt_px96), U256::from(liquidity)).await;
         Ok(())
     }
 
-    async fn get_binance_orders(&self) -> Result<BinanceOrdersResponse> {
-        let res: BinanceOrdersResponse = reqwest::get("https://api.binance.com/api/v3/depth?symbol=ETHUSDC")
-            .await?
-            .json()
-            .await?;
-        Ok(res)
-    }
-
     async fn arb_possibility(&self, sqrt_px96: U256, liquidity: U256) {
         let uni_price = self.get_uni_price(sqrt_px96);
-        let binance_orders = self.get_binance_orders().await;
+        // Sell into the venue with the highest bid, and buy from the venue with the lowest ask,
+        // among the books quoted in the same asset as the pool.
+        let books = || self.cex_books.values().filter(|book| book.is_quoted_in(QUOTE_ASSET));
+        let bid_book = best_bid_book(books());
+        let ask_book = best_ask_book(books());
 
-        if let Ok(binance_response) = binance_orders {
-            // Access the bids field
-            let bids = binance_response.bids;
-            let asks = binance_response.asks;
-            let binance_price_bids: f64 = (bids[0].0).to_string().parse::<f64>().unwrap();
-            let binance_price_asks: f64 = (asks[0].0).to_string().parse::<f64>().unwrap();
-            info!("binance_price_bids {:?}", binance_price_bids);
-            info!("binance_price_asks {:?}", binance_price_asks);
+        if let (Some(bid_book), Some(ask_book)) = (bid_book, ask_book) {
+            let (Some(best_bid), Some(best_ask)) = (bid_book.best_bid(), ask_book.best_ask()) else {
+                debug!("no CEX bids or asks are available");
+                return;
+            };
+            let bids = bid_book.bids.clone();
+            let asks = ask_book.asks.clone();
+            let cex_price_bids: f64 = best_bid.price.to_f64().unwrap();
+            let cex_price_asks: f64 = best_ask.price.to_f64().unwrap();
+            info!("cex_price_bids {:?} on {}", cex_price_bids, bid_book.venue);
+            info!("cex_price_asks {:?} on {}", cex_price_asks, ask_book.venue);
             info!("uni_price.eth {:?}", uni_price.eth);
             let mut i = 0;
             let mut amount: f64 = 0.0;
             // ETH -> USDC -> ETH
-            if uni_price.eth > binance_price_bids {
-                let mut sell_price: f64 = (asks[0].0).to_string().parse::<f64>().unwrap();
+            if uni_price.eth > cex_price_bids {
+                let mut sell_price: f64 = best_ask.price.to_f64().unwrap();
                 while i < asks.len() && sell_price <= uni_price.eth {
-                    amount = amount + ((asks[i].1).to_string().parse::<f64>().unwrap());
-                    sell_price = (asks[i].0).to_string().parse::<f64>().unwrap();
+                    amount = amount + (asks[i].size.to_f64().unwrap());
+                    sell_price = asks[i].price.to_f64().unwrap();
                     i = i + 1;
                 }
                 let res = self.binary_search_uni(amount, sqrt_px96, liquidity, asks.clone(), false);
-                info!("ETH -> USDC on {:?} -- USDC -> ETH on Binance. Profit: {:?}. Amount: {:?}", res.name, res.profit, res.amount);
+                info!("ETH -> USDC on {:?} -- USDC -> ETH on {}. Profit: {:?}. Amount: {:?}", res.name, ask_book.venue, res.profit, res.amount);
             } else {
-                let mut buy_price: f64 = (bids[0].0).to_string().parse::<f64>().unwrap();
+                let mut buy_price: f64 = best_bid.price.to_f64().unwrap();
                 while i < bids.len() && buy_price >= uni_price.eth {
-                    amount = amount + ((bids[i].1).to_string().parse::<f64>().unwrap());
-                    buy_price = (bids[i].0).to_string().parse::<f64>().unwrap();
+                    amount = amount + (bids[i].size.to_f64().unwrap());
+                    buy_price = bids[i].price.to_f64().unwrap();
                     i = i + 1;
                 }
-                let res = self.binary_search_binance(amount, sqrt_px96, liquidity, bids.clone(), false);
+                let res = self.binary_search_cex(amount, sqrt_px96, liquidity, bids.clone(), false, bid_book.venue);
                 info!("ETH -> USDC on {:?} -- USDC -> ETH on Uniswap. Profit: {:?}. Amount: {:?}", res.name, res.profit, res.amount);
             }
 
             // USDC -> ETH -> USDC
-            if uni_price.eth < binance_price_asks {
-                let mut buy_price: f64 = (bids[0].0).to_string().parse::<f64>().unwrap();
+            if uni_price.eth < cex_price_asks {
+                let mut buy_price: f64 = best_bid.price.to_f64().unwrap();
                 while i < bids.len() && buy_price >= uni_price.eth {
-                    amount = amount + ((bids[i].1).to_string().parse::<f64>().unwrap() * (bids[i].0).to_string().parse::<f64>().unwrap());
-                    buy_price = (bids[i].0).to_string().parse::<f64>().unwrap();
+                    amount = amount + (bids[i].size.to_f64().unwrap() * bids[i].price.to_f64().unwrap());
+                    buy_price = bids[i].price.to_f64().unwrap();
                     i = i + 1;
                 }
                 let res = self.binary_search_uni(amount, sqrt_px96, liquidity, bids.clone(), true);
-                info!("USDC -> ETH on {:?} -- ETH -> USDC on Binance. Profit: {:?}. Amount: {:?}", res.name, res.profit, res.amount);
+                info!("USDC -> ETH on {:?} -- ETH -> USDC on {}. Profit: {:?}. Amount: {:?}", res.name, bid_book.venue, res.profit, res.amount);
             } else {
-                let mut sell_price: f64 = (asks[0].0).to_string().parse::<f64>().unwrap();
+                let mut sell_price: f64 = best_ask.price.to_f64().unwrap();
                 while i < asks.len() && sell_price <= uni_price.eth {
-                    amount = amount + ((asks[i].1).to_string().parse::<f64>().unwrap() * (asks[i].0).to_string().parse::<f64>().unwrap());
-                    sell_price = (asks[i].0).to_string().parse::<f64>().unwrap();
+                    amount = amount + (asks[i].size.to_f64().unwrap() * asks[i].price.to_f64().unwrap());
+                    sell_price = asks[i].price.to_f64().unwrap();
                     i = i + 1;
                 }
-                let res = self.binary_search_binance(amount, sqrt_px96, liquidity, asks.clone(), true);
+                let res = self.binary_search_cex(amount, sqrt_px96, liquidity, asks.clone(), true, ask_book.venue);
                 info!("USDC -> ETH on {:?} -- ETH -> USDC on Uniswap. Profit: {:?}. Amount: {:?}", res.name, res.profit, res.amount);
             }
         } else {
-            println!("Error: BinanceOrdersResponse is not available");
+            debug!("no CEX order book quoted in {} is available", QUOTE_ASSET);
         }
     }
 
-    fn check_uni_profit(&self, amount: f64, sqrt_px96: U256, liquidity: U256, orders: Vec<(String, String)>, zero_for_one: bool) -> f64 {
+    /// Profit of swapping `amount` on Uniswap and closing on the CEX `orders`, or `None` if
+    /// the book isn't deep enough.
+    fn check_uni_profit(&self, amount: f64, sqrt_px96: U256, liquidity: U256, orders: Vec<PriceLevel>, zero_for_one: bool) -> Option<f64> {
         if zero_for_one {
             let next_price = self.get_uni_price_after_swap(sqrt_px96, liquidity, parse_units(amount.to_string(), "mwei").unwrap().into(), zero_for_one);
             let amount_out = self.get_amount_out(next_price, sqrt_px96, liquidity, zero_for_one);
@@ -130,18 +152,21 @@ impl<M: Middleware + 'static> BinanceUni<M> {
             let mut profit_amount = 0.0;
             let mut i = 0;
             while amount_local != self.u256_to_f64(amount_out, "ether") {
-                if (amount_local + (orders[i].1).to_string().parse::<f64>().unwrap()) < self.u256_to_f64(amount_out, "ether") {
-                    amount_local = amount_local + (orders[i].1).to_string().parse::<f64>().unwrap();
-                    profit_amount = profit_amount + ((orders[i].1).to_string().parse::<f64>().unwrap() * (orders[i].0).to_string().parse::<f64>().unwrap());
+                if i >= orders.len() {
+                    return None;
+                }
+                if (amount_local + orders[i].size.to_f64().unwrap()) < self.u256_to_f64(amount_out, "ether") {
+                    amount_local = amount_local + orders[i].size.to_f64().unwrap();
+                    profit_amount = profit_amount + (orders[i].size.to_f64().unwrap() * orders[i].price.to_f64().unwrap());
                     i = i + 1;
                 } else {
                     let difference = self.u256_to_f64(amount_out, "ether") - amount_local;
                     amount_local = self.u256_to_f64(amount_out, "ether");
-                    profit_amount = profit_amount + (difference * (orders[i].0).to_string().parse::<f64>().unwrap());
+                    profit_amount = profit_amount + (difference * orders[i].price.to_f64().unwrap());
                     i = i + 1;
                 }
             }
-            profit_amount - amount
+            Some(profit_amount - amount)
         } else {
             let next_price = self.get_uni_price_after_swap(sqrt_px96, liquidity, parse_units(amount.to_string(), "ether").unwrap().into(), zero_for_one);
             let amount_out = self.get_amount_out(next_price, sqrt_px96, liquidity, false);
@@ -149,64 +174,75 @@ impl<M: Middleware + 'static> BinanceUni<M> {
             let mut profit_amount = 0.0;
             let mut i = 0;
             while amount_local != self.u256_to_f64(amount_out, "mwei") {
-                if amount_local + ((orders[i].1).to_string().parse::<f64>().unwrap() * (orders[i].0).to_string().parse::<f64>().unwrap()) < self.u256_to_f64(amount_out, "mwei") {
-                    amount_local = amount_local + ((orders[i].1).to_string().parse::<f64>().unwrap() * (orders[i].0).to_string().parse::<f64>().unwrap());
-                    profit_amount = profit_amount + (orders[i].1).to_string().parse::<f64>().unwrap();
+                if i >= orders.len() {
+                    return None;
+                }
+                if amount_local + (orders[i].size.to_f64().unwrap() * orders[i].price.to_f64().unwrap()) < self.u256_to_f64(amount_out, "mwei") {
+                    amount_local = amount_local + (orders[i].size.to_f64().unwrap() * orders[i].price.to_f64().unwrap());
+                    profit_amount = profit_amount + orders[i].size.to_f64().unwrap();
                     i = i + 1;
                 } else {
                     let difference = self.u256_to_f64(amount_out, "mwei") - amount_local;
                     amount_local = self.u256_to_f64(amount_out, "mwei");
-                    profit_amount = profit_amount + (difference / (orders[i].0).to_string().parse::<f64>().unwrap());
+                    profit_amount = profit_amount + (difference / orders[i].price.to_f64().unwrap());
                     i = i + 1;
                 }
             }
-            profit_amount - amount
+            Some(profit_amount - amount)
         }
     }
 
-    fn check_binance_profit(&self, amount: f64, sqrt_px96: U256, liquidity: U256, orders: Vec<(String, String)>, zero_for_one: bool) -> f64 {
+    /// Profit of trading `amount` on the CEX `orders` and closing on Uniswap, or `None` if
+    /// the book isn't deep enough.
+    fn check_cex_profit(&self, amount: f64, sqrt_px96: U256, liquidity: U256, orders: Vec<PriceLevel>, zero_for_one: bool) -> Option<f64> {
         if zero_for_one {
             let mut amount_local: f64 = 0.0;
             let mut amount_out: f64 = 0.0;
             let mut i = 0;
             while amount_local != amount {
-                if (amount_local + ((orders[i].1).to_string().parse::<f64>().unwrap() * (orders[i].0).to_string().parse::<f64>().unwrap())) < amount {
-                    amount_out = amount_out + (orders[i].1).to_string().parse::<f64>().unwrap();
-                    amount_local = amount_local + ((orders[i].1).to_string().parse::<f64>().unwrap() * (orders[i].0).to_string().parse::<f64>().unwrap());
+                if i >= orders.len() {
+                    return None;
+                }
+                if (amount_local + (orders[i].size.to_f64().unwrap() * orders[i].price.to_f64().unwrap())) < amount {
+                    amount_out = amount_out + orders[i].size.to_f64().unwrap();
+                    amount_local = amount_local + (orders[i].size.to_f64().unwrap() * orders[i].price.to_f64().unwrap());
                     i = i + 1;
                 } else {
                     let difference = amount - amount_local;
                     amount_local = amount;
-                    amount_out = amount_out + (difference / (orders[i].0).to_string().parse::<f64>().unwrap());
+                    amount_out = amount_out + (difference / orders[i].price.to_f64().unwrap());
                     i = i + 1;
                 }
             }
             let next_price = self.get_uni_price_after_swap(sqrt_px96, liquidity, parse_units(amount_out.to_string(), "ether").unwrap().into(), !zero_for_one);
             let amount_out = self.get_amount_out(next_price, sqrt_px96, liquidity, !zero_for_one);
-            self.u256_to_f64(amount_out, "mwei") - amount
+            Some(self.u256_to_f64(amount_out, "mwei") - amount)
         } else {
             let mut amount_local: f64 = 0.0;
             let mut amount_out: f64 = 0.0;
             let mut i = 0;
             while amount_local != amount {
-                if amount_local + (orders[i].1).to_string().parse::<f64>().unwrap() < amount {
-                    amount_out = amount_out + ((orders[i].1).to_string().parse::<f64>().unwrap() * (orders[i].0).to_string().parse::<f64>().unwrap());
-                    amount_local = amount_local + (orders[i].1).to_string().parse::<f64>().unwrap();
+                if i >= orders.len() {
+                    return None;
+                }
+                if amount_local + orders[i].size.to_f64().unwrap() < amount {
+                    amount_out = amount_out + (orders[i].size.to_f64().unwrap() * orders[i].price.to_f64().unwrap());
+                    amount_local = amount_local + orders[i].size.to_f64().unwrap();
                     i = i + 1;
                 } else {
                     let difference = amount - amount_local;
                     amount_local = amount;
-                    amount_out = amount_out + (difference * (orders[i].0).to_string().parse::<f64>().unwrap());
+                    amount_out = amount_out + (difference * orders[i].price.to_f64().unwrap());
                     i = i + 1;
                 }
             }
             let next_price = self.get_uni_price_after_swap(sqrt_px96, liquidity, parse_units(amount_out.to_string(), "mwei").unwrap().into(), !zero_for_one);
             let amount_out = self.get_amount_out(next_price, sqrt_px96, liquidity, !zero_for_one);
-            self.u256_to_f64(amount_out, "ether") - amount
+            Some(self.u256_to_f64(amount_out, "ether") - amount)
         }
     }
 
-    fn binary_search_uni(&self, amount: f64, sqrt_px96: U256, liquidity: U256, orders: Vec<(String, String)>, zero_for_one: bool) -> Profit {
+    fn binary_search_uni(&self, amount: f64, sqrt_px96: U256, liquidity: U256, orders: Vec<PriceLevel>, zero_for_one: bool) -> Profit {
         let mut left = 1.0;
         let mut right = amount;
         let mut max_return_value: f64 = 0.0;
@@ -214,7 +250,11 @@ impl<M: Middleware + 'static> BinanceUni<M> {
 
         while left <= right {
             let mid = (left + right) / 2.0;
-            let return_value = self.check_uni_profit(mid, sqrt_px96, liquidity, orders.clone(), zero_for_one);
+            // Larger amounts can't be filled either when the book runs out.
+            let Some(return_value) = self.check_uni_profit(mid, sqrt_px96, liquidity, orders.clone(), zero_for_one) else {
+                right = mid - 1.0;
+                continue;
+            };
             if return_value > max_return_value {
                 right = mid - 1.0;
                 max_return_value = return_value;
@@ -230,7 +270,7 @@ impl<M: Middleware + 'static> BinanceUni<M> {
         }
     }
 
-    fn binary_search_binance(&self, amount: f64, sqrt_px96: U256, liquidity: U256, orders: Vec<(String, String)>, zero_for_one: bool) -> Profit {
+    fn binary_search_cex(&self, amount: f64, sqrt_px96: U256, liquidity: U256, orders: Vec<PriceLevel>, zero_for_one: bool, venue: Venue) -> Profit {
         let mut left = 1.0;
         let mut right = amount;
         let mut max_return_value: f64 = 0.0;
@@ -238,7 +278,11 @@ impl<M: Middleware + 'static> BinanceUni<M> {
 
         while left <= right {
             let mid = (left + right) / 2.0;
-            let return_value = self.check_binance_profit(mid, sqrt_px96, liquidity, orders.clone(), zero_for_one);
+            // Larger amounts can't be filled either when the book runs out.
+            let Some(return_value) = self.check_cex_profit(mid, sqrt_px96, liquidity, orders.clone(), zero_for_one) else {
+                right = mid - 1.0;
+                continue;
+            };
             if return_value > max_return_value {
                 right = mid - 1.0;
                 max_return_value = return_value;
@@ -250,7 +294,7 @@ impl<M: Middleware + 'static> BinanceUni<M> {
         Profit {
             profit: max_return_value,
             amount: amount_local,
-            name: String::from("Binance"),
+            name: venue.to_string(),
         }
     }
 
@@ -1,11 +1,15 @@
 use artemis_core::{
-    collectors::{block_collector::NewBlock},
+    collectors::{block_collector::NewBlock, cex_collector::CexOrderBook},
 };
 use serde::{Deserialize};
 
+use crate::pool_state_collector::PoolStateUpdate;
+
 #[derive(Debug, Clone)]
 pub enum Event {
-    NewBlock(NewBlock)
+    NewBlock(NewBlock),
+    CexOrderBook(CexOrderBook),
+    PoolState(PoolStateUpdate),
 }
 
 #[derive(Debug, Clone)]
@@ -14,12 +18,6 @@ pub enum Action {}
 #[derive(Debug, Clone)]
 pub struct Config {}
 
-#[derive(Debug, Deserialize)]
-pub struct BinanceOrdersResponse {
-    pub asks: Vec<(String, String)>,
-    pub bids: Vec<(String, String)>,
-}
-
 #[derive(Debug, Deserialize)]
 pub struct TokensPrice {
     pub usdc: f64,
@@ -209,6 +209,7 @@ impl<M: Middleware + 'static> OpenseaSudoArb<M> {
                 total_profit,
                 bid_percentage: self.bid_percentage,
             }),
+            deadline: None,
         }))
     }
 
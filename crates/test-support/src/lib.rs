@@ -0,0 +1,7 @@
+//! Test support for Artemis crates, so that clients and executors can be tested
+//! without hitting real services.
+
+/// This module implements an in-process HTTP server with scripted responses.
+pub mod mock_http;
+/// This module implements an in-process mock of a Flashbots-style JSON-RPC relay.
+pub mod mock_relay;
@@ -0,0 +1,143 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+/// A request received by the mock HTTP server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    /// Query string, without the leading `?`.
+    pub query: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpRequest {
+    /// Value of the header `name`, compared case insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+type Handler = dyn Fn(&HttpRequest) -> (u16, String) + Send + Sync;
+
+/// An in-process HTTP server answering each request with the status and body returned
+/// by a handler, for testing REST clients.
+#[derive(Debug)]
+pub struct MockHttp {
+    url: String,
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockHttp {
+    /// Start a mock server on a random local port, answering requests with `handler`.
+    pub async fn spawn<F>(handler: F) -> Self
+    where
+        F: Fn(&HttpRequest) -> (u16, String) + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let handler: Arc<Handler> = Arc::new(handler);
+        let seen = requests.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, handler.clone(), seen.clone()));
+            }
+        });
+        Self {
+            url,
+            requests,
+            handle,
+        }
+    }
+
+    /// Url of the server.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Requests received so far.
+    pub fn requests(&self) -> Vec<HttpRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockHttp {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+async fn serve(
+    mut stream: TcpStream,
+    handler: Arc<Handler>,
+    requests: Arc<Mutex<Vec<HttpRequest>>>,
+) {
+    let Some((headers, body)) = read_http_request(&mut stream).await else {
+        return;
+    };
+    let mut lines = headers.lines();
+    let mut request_line = lines.next().unwrap_or_default().split(' ');
+    let method = request_line.next().unwrap_or_default().to_string();
+    let target = request_line.next().unwrap_or_default();
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let request = HttpRequest {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        headers: lines
+            .filter_map(|line| {
+                let (name, value) = line.split_once(':')?;
+                Some((name.trim().to_string(), value.trim().to_string()))
+            })
+            .collect(),
+        body,
+    };
+
+    let (status, body) = handler(&request);
+    requests.lock().unwrap().push(request);
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+/// Helper function to read an HTTP request, returning its headers and body.
+pub(crate) async fn read_http_request(stream: &mut TcpStream) -> Option<(String, String)> {
+    let mut request = vec![];
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = stream.read(&mut buf).await.ok()?;
+        request.extend_from_slice(&buf[..n]);
+        let text = String::from_utf8_lossy(&request).to_string();
+        if let Some((headers, body)) = text.split_once("\r\n\r\n") {
+            let content_length = headers
+                .lines()
+                .find_map(|line| {
+                    let (name, value) = line.split_once(':')?;
+                    name.eq_ignore_ascii_case("content-length")
+                        .then(|| value.trim().parse::<usize>().ok())?
+                })
+                .unwrap_or(0);
+            if body.len() >= content_length {
+                return Some((headers.to_string(), body.to_string()));
+            }
+        }
+        if n == 0 {
+            return None;
+        }
+    }
+}
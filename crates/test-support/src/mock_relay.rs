@@ -0,0 +1,236 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use ethers::{
+    types::{Address, Signature, H256},
+    utils::keccak256,
+};
+use serde_json::{json, Value};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{TcpListener, TcpStream},
+    task::JoinHandle,
+};
+
+use crate::mock_http::read_http_request;
+
+/// Name of the header authenticating requests to the relay.
+const SIGNATURE_HEADER: &str = "x-flashbots-signature";
+
+/// A response scripted for a method of the mock relay.
+#[derive(Debug, Clone)]
+pub enum MockResponse {
+    /// A JSON-RPC result.
+    Result(Value),
+    /// A JSON-RPC error.
+    Error { code: i64, message: String },
+    /// An HTTP error status, without a JSON-RPC body.
+    HttpStatus(u16),
+}
+
+/// A request received by the mock relay.
+#[derive(Debug, Clone)]
+pub struct RelayRequest {
+    pub method: String,
+    pub params: Value,
+    /// Address recovered from the `X-Flashbots-Signature` header, if it is valid.
+    pub signer: Option<Address>,
+}
+
+#[derive(Debug, Default)]
+struct RelayState {
+    /// Responses used once each, in order, before the default responses.
+    scripted: HashMap<String, VecDeque<MockResponse>>,
+    defaults: HashMap<String, MockResponse>,
+    requests: Vec<RelayRequest>,
+}
+
+/// An in-process JSON-RPC relay. Requests must be signed with a valid
+/// `X-Flashbots-Signature` header, as relays require, or they are rejected with a
+/// signature error. Bundles are accepted by default, and other methods answer with
+/// a method not found error, unless responses are scripted.
+#[derive(Debug)]
+pub struct MockRelay {
+    url: String,
+    state: Arc<Mutex<RelayState>>,
+    handle: JoinHandle<()>,
+}
+
+impl MockRelay {
+    /// Start a mock relay on a random local port.
+    pub async fn spawn() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let state = Arc::new(Mutex::new(RelayState::default()));
+        let relay_state = state.clone();
+        let handle = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(serve(stream, relay_state.clone()));
+            }
+        });
+        Self { url, state, handle }
+    }
+
+    /// Url of the relay.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Answer every call to `method` with `response`, once scripted responses are used.
+    pub fn respond(&self, method: &str, response: MockResponse) {
+        let mut state = self.state.lock().unwrap();
+        state.defaults.insert(method.to_string(), response);
+    }
+
+    /// Answer the next call to `method` with `response`. Scripted responses are used
+    /// in the order they were added.
+    pub fn respond_once(&self, method: &str, response: MockResponse) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .scripted
+            .entry(method.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Requests received so far, including rejected ones.
+    pub fn requests(&self) -> Vec<RelayRequest> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Bundles received so far with `mev_sendBundle` or `eth_sendBundle`, from
+    /// correctly signed requests.
+    pub fn bundles(&self) -> Vec<Value> {
+        self.requests()
+            .into_iter()
+            .filter(|request| {
+                request.signer.is_some()
+                    && matches!(request.method.as_str(), "mev_sendBundle" | "eth_sendBundle")
+            })
+            .map(|request| request.params[0].clone())
+            .collect()
+    }
+}
+
+impl Drop for MockRelay {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+/// Recover the signer of a request from its `X-Flashbots-Signature` header, which
+/// holds `address:signature`, the signature being over the hex encoded keccak of the
+/// body. Returns `None` if the header is missing, or isn't signed by the address.
+pub fn recover_signer(header: Option<&str>, body: &str) -> Option<Address> {
+    let (address, signature) = header?.split_once(':')?;
+    let address = Address::from_str(address).ok()?;
+    let signature = Signature::from_str(signature).ok()?;
+    let message = format!("0x{:x}", H256::from(keccak256(body.as_bytes())));
+    signature.verify(message, address).ok()?;
+    Some(address)
+}
+
+/// Response to a call no response was scripted for.
+fn default_response(method: &str, params: &Value) -> MockResponse {
+    match method {
+        "mev_sendBundle" | "eth_sendBundle" => MockResponse::Result(json!({
+            "bundleHash": H256::from(keccak256(params.to_string().as_bytes())),
+        })),
+        "eth_cancelBundle" => MockResponse::Result(Value::Null),
+        _ => MockResponse::Error {
+            code: -32601,
+            message: format!("the method {} does not exist/is not available", method),
+        },
+    }
+}
+
+async fn serve(mut stream: TcpStream, state: Arc<Mutex<RelayState>>) {
+    let Some((headers, body)) = read_http_request(&mut stream).await else {
+        return;
+    };
+    let header = headers.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        name.eq_ignore_ascii_case(SIGNATURE_HEADER)
+            .then(|| value.trim().to_string())
+    });
+    let signer = recover_signer(header.as_deref(), &body);
+    let request: Value = serde_json::from_str(&body).unwrap_or_default();
+    let method = request["method"].as_str().unwrap_or_default().to_string();
+    let params = request["params"].clone();
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(RelayRequest {
+            method: method.clone(),
+            params: params.clone(),
+            signer,
+        });
+        if signer.is_none() {
+            MockResponse::Error {
+                code: -32600,
+                message: "error in signature check".to_string(),
+            }
+        } else if let Some(response) = state
+            .scripted
+            .get_mut(&method)
+            .and_then(|responses| responses.pop_front())
+        {
+            response
+        } else if let Some(response) = state.defaults.get(&method) {
+            response.clone()
+        } else {
+            default_response(&method, &params)
+        }
+    };
+
+    let (status, body) = match response {
+        MockResponse::Result(result) => (
+            200,
+            json!({ "jsonrpc": "2.0", "id": request["id"], "result": result }).to_string(),
+        ),
+        MockResponse::Error { code, message } => (
+            200,
+            json!({
+                "jsonrpc": "2.0",
+                "id": request["id"],
+                "error": { "code": code, "message": message },
+            })
+            .to_string(),
+        ),
+        MockResponse::HttpStatus(status) => (status, String::new()),
+    };
+    let response = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{
+        core::rand::thread_rng,
+        signers::{LocalWallet, Signer},
+    };
+
+    #[tokio::test]
+    async fn verifies_signatures() {
+        let signer = LocalWallet::new(&mut thread_rng());
+        let body = r#"{"jsonrpc":"2.0","id":0,"method":"eth_sendBundle","params":[]}"#;
+        let message = format!("0x{:x}", H256::from(keccak256(body.as_bytes())));
+        let signature = signer.sign_message(message).await.unwrap();
+        let header = format!("{:?}:0x{}", signer.address(), signature);
+
+        assert_eq!(recover_signer(Some(&header), body), Some(signer.address()));
+        assert_eq!(recover_signer(Some(&header), "{}"), None);
+        assert_eq!(recover_signer(None, body), None);
+        let other = format!("{:?}:0x{}", Address::random(), signature);
+        assert_eq!(recover_signer(Some(&other), body), None);
+    }
+}